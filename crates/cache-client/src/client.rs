@@ -17,7 +17,7 @@
 
 use crate::dto::{
     CacheEntryDto, ClearTapResp, CreatePreloadReq, DeleteEntryResp, EntryQuery,
-    PreloadCreatedResp, StoreMetadataReq, TapQuery,
+    PreloadCreatedResp, SetLoudnessReq, StoreMetadataReq, TapQuery,
 };
 
 const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
@@ -279,4 +279,30 @@ async fn store_metadata(
     async fn delete(&self, tap_id: &TapId, key: &AudioCacheItemKey) -> io::Result<()> {
         self.delete_entry(tap_id, key).await.map(|_| ())
     }
+
+    async fn set_loudness(
+        &self,
+        tap_id: &TapId,
+        key: &AudioCacheItemKey,
+        loudness_lufs: f32,
+    ) -> io::Result<()> {
+        let q = EntryQuery::new(tap_id, key);
+        let resp = self
+            .request(reqwest::Method::PUT, "/entry/loudness")
+            .query(&q)
+            .json(&SetLoudnessReq { loudness_lufs })
+            .send()
+            .await
+            .map_err(io_other)?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "entry not found"));
+        }
+        if !resp.status().is_success() {
+            return Err(io_other(format!(
+                "PUT /entry/loudness failed: {}",
+                resp.status()
+            )));
+        }
+        Ok(())
+    }
 }
@@ -28,6 +28,12 @@ pub struct StoreMetadataReq {
     pub cache_key: AudioCachePolicy,
 }
 
+/// Request body for `PUT /entry/loudness` (entry selected by [`EntryQuery`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLoudnessReq {
+    pub loudness_lufs: f32,
+}
+
 /// Query string for entry/stream/delete endpoints. `key` is the JSON-encoded
 /// `AudioCacheItemKey` (same encoding `FileAudioCache` uses on disk).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +108,10 @@ pub struct CacheEntryDto {
     pub metadatas: Vec<AudioMetadata>,
     pub cache_key: AudioCachePolicy,
     pub kind: CacheEntryKindDto,
+    #[serde(default)]
+    pub loudness_lufs: Option<f32>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
 }
 
 impl From<CacheEntry> for CacheEntryDto {
@@ -111,6 +121,8 @@ fn from(e: CacheEntry) -> Self {
             metadatas: e.metadatas,
             cache_key: e.cache_key,
             kind: e.kind.into(),
+            loudness_lufs: e.loudness_lufs,
+            duration_ms: e.duration_ms,
         }
     }
 }
@@ -122,6 +134,8 @@ fn from(d: CacheEntryDto) -> Self {
             metadatas: d.metadatas,
             cache_key: d.cache_key,
             kind: d.kind.into(),
+            loudness_lufs: d.loudness_lufs,
+            duration_ms: d.duration_ms,
         }
     }
 }
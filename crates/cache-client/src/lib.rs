@@ -4,5 +4,5 @@
 pub use client::RemoteAudioCache;
 pub use dto::{
     CacheEntryDto, CacheEntryKindDto, ClearTapResp, CreatePreloadReq, DeleteEntryResp, EntryQuery,
-    PreloadCreatedResp, StoreMetadataReq, TapQuery,
+    PreloadCreatedResp, SetLoudnessReq, StoreMetadataReq, TapQuery,
 };
@@ -75,6 +75,15 @@ async fn store_metadata(
 
     /// Delete cached files for the given key.
     async fn delete(&self, tap_id: &TapId, key: &AudioCacheItemKey) -> io::Result<()>;
+
+    /// Persist the integrated loudness measured for an entry.
+    /// Fails with `NotFound` if the entry does not exist.
+    async fn set_loudness(
+        &self,
+        tap_id: &TapId,
+        key: &AudioCacheItemKey,
+        loudness_lufs: f32,
+    ) -> io::Result<()>;
 }
 
 pub struct FileAudioCache {
@@ -213,6 +222,8 @@ async fn store(
             gdsf_priority: 0.0,
             is_downloading: true,
             has_opus: false,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         // Register the entry (writes initial sidecar to disk).
@@ -221,8 +232,10 @@ async fn store(
         let result: io::Result<()> = async {
             let mut file = fs::File::create(&opus_path).await?;
             let mut total_bytes: u64 = 0;
+            let mut frames: u64 = 0;
             while let Some(frame) = stream.recv().await {
                 total_bytes += 4 + frame.len() as u64;
+                frames += 1;
                 if let Some(max) = self.max_file_bytes
                     && total_bytes > max
                 {
@@ -248,7 +261,9 @@ async fn store(
             }
 
             // Mark complete: updates sidecar on disk (is_downloading=false, has_opus=true).
-            self.db.mark_complete(tap_id_str.clone(), key_json.clone()).await?;
+            self.db
+                .mark_complete(tap_id_str.clone(), key_json.clone(), Some(frames * 20))
+                .await?;
             tracing::info!(tap_id = %item.tap_id, key = %item.key, "audio cached successfully");
             Ok(())
         }
@@ -301,6 +316,8 @@ async fn store_from_path(
             gdsf_priority: 0.0,
             is_downloading: true,
             has_opus: false,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         self.db.insert_sidecar(dest_json.clone(), sidecar).await?;
@@ -325,7 +342,7 @@ async fn store_from_path(
 
         if let Err(e) = self
             .db
-            .mark_complete(tap_id_str.clone(), key_json.clone())
+            .mark_complete(tap_id_str.clone(), key_json.clone(), None)
             .await
         {
             let _ = fs::remove_file(&dest_opus).await;
@@ -392,6 +409,8 @@ async fn get_entry(&self, tap_id: &TapId, key: &AudioCacheItemKey) -> Option<Cac
             metadatas: sidecar.metadatas,
             cache_key: sidecar.cache_policy,
             kind,
+            loudness_lufs: sidecar.loudness_lufs,
+            duration_ms: sidecar.duration_ms,
         })
     }
 
@@ -419,6 +438,8 @@ async fn store_metadata(
             gdsf_priority: 0.0,
             is_downloading: false,
             has_opus: false,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         self.db.insert_sidecar(json_path, sidecar).await
@@ -427,6 +448,17 @@ async fn store_metadata(
     async fn delete(&self, tap_id: &TapId, key: &AudioCacheItemKey) -> io::Result<()> {
         self.delete_returning_found(tap_id, key).await.map(|_| ())
     }
+
+    async fn set_loudness(
+        &self,
+        tap_id: &TapId,
+        key: &AudioCacheItemKey,
+        loudness_lufs: f32,
+    ) -> io::Result<()> {
+        self.db
+            .set_loudness(tap_id.to_string(), key_to_json(key), loudness_lufs)
+            .await
+    }
 }
 
 // ---------------------------------------------------------------------------
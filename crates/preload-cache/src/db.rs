@@ -36,6 +36,12 @@ pub(crate) struct MetaSidecar {
     /// True when a companion .opus file exists alongside this .json.
     #[serde(default)]
     pub has_opus: bool,
+    /// Integrated loudness (EBU R128, LUFS) measured by the audio engine.
+    #[serde(default)]
+    pub loudness_lufs: Option<f32>,
+    /// Length of the stored audio, counted in 20 ms frames while writing.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -135,6 +141,8 @@ pub async fn insert(&self, entry: DbEntry) -> io::Result<()> {
             gdsf_priority: entry.gdsf_priority,
             is_downloading: entry.is_downloading,
             has_opus: entry.opus_path.is_some(),
+            loudness_lufs: None,
+            duration_ms: None,
         };
         let json_path = PathBuf::from(&entry.json_path);
         self.insert_sidecar(json_path, sidecar).await
@@ -150,7 +158,12 @@ pub(crate) async fn insert_sidecar(&self, json_path: PathBuf, sidecar: MetaSidec
     }
 
     /// Mark an entry as fully written (`is_downloading = false`, `has_opus = true`).
-    pub async fn mark_complete(&self, tap_id: String, cache_key: String) -> io::Result<()> {
+    pub async fn mark_complete(
+        &self,
+        tap_id: String,
+        cache_key: String,
+        duration_ms: Option<u64>,
+    ) -> io::Result<()> {
         let (path, sidecar) = {
             let mut map = self.entries.write().await;
             let e = map
@@ -158,6 +171,9 @@ pub async fn mark_complete(&self, tap_id: String, cache_key: String) -> io::Resu
                 .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "entry not found"))?;
             e.1.is_downloading = false;
             e.1.has_opus = true;
+            if duration_ms.is_some() {
+                e.1.duration_ms = duration_ms;
+            }
             (e.0.clone(), e.1.clone())
         };
         write_sidecar(&path, &sidecar).await
@@ -237,6 +253,24 @@ pub async fn set_gdsf_priority(
         write_sidecar(&path, &sidecar).await
     }
 
+    /// Record the measured integrated loudness for an entry.
+    pub async fn set_loudness(
+        &self,
+        tap_id: String,
+        cache_key: String,
+        loudness_lufs: f32,
+    ) -> io::Result<()> {
+        let (path, sidecar) = {
+            let mut map = self.entries.write().await;
+            let e = map
+                .get_mut(&(tap_id, cache_key))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "entry not found"))?;
+            e.1.loudness_lufs = Some(loudness_lufs);
+            (e.0.clone(), e.1.clone())
+        };
+        write_sidecar(&path, &sidecar).await
+    }
+
     /// Return every entry in the index.
     pub async fn get_all_entries(&self) -> io::Result<Vec<DbEntry>> {
         let map = self.entries.read().await;
@@ -1,9 +1,11 @@
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use bytes::Bytes;
-use tokio::io::{AsyncRead, BufWriter};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncSeekExt, BufWriter};
 use tokio::{
     fs,
     io::{self, AsyncReadExt, AsyncWriteExt, BufReader},
@@ -172,15 +174,50 @@ async fn write_task(
 }
 
 pub struct PreloadReader {
-    pub(crate) inner: Box<dyn AsyncRead + Send + Unpin>,
+    pub(crate) inner: Source,
     pub(crate) signal: Option<Arc<WriteSignal>>,
 }
 
+pub(crate) enum Source {
+    /// A local file, whose frames can be skipped without reading them.
+    File(BufReader<fs::File>),
+    Stream(Box<dyn AsyncRead + Send + Unpin>),
+}
+
+impl Source {
+    fn reader(&mut self) -> &mut (dyn AsyncRead + Send + Unpin) {
+        match self {
+            Source::File(file) => file,
+            Source::Stream(stream) => stream.as_mut(),
+        }
+    }
+
+    /// Move past the next `len` bytes without copying them out.
+    async fn skip(&mut self, len: usize) -> io::Result<()> {
+        match self {
+            Source::File(file) => {
+                if len <= file.buffer().len() {
+                    Pin::new(file).consume(len);
+                } else {
+                    file.seek(SeekFrom::Current(len as i64)).await?;
+                }
+            }
+            Source::Stream(stream) => {
+                let skipped = io::copy(&mut stream.take(len as u64), &mut io::sink()).await?;
+                if skipped < len as u64 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 impl PreloadReader {
     /// Build a `PreloadReader` from a tokio file (wrapped in a `BufReader` for performance).
     pub fn from_file(file: fs::File, signal: Option<Arc<WriteSignal>>) -> Self {
         Self {
-            inner: Box::new(BufReader::new(file)),
+            inner: Source::File(BufReader::new(file)),
             signal,
         }
     }
@@ -192,33 +229,47 @@ pub fn from_reader<R: AsyncRead + Send + Unpin + 'static>(
         signal: Option<Arc<WriteSignal>>,
     ) -> Self {
         Self {
-            inner: Box::new(reader),
+            inner: Source::Stream(Box::new(reader)),
             signal,
         }
     }
 
     pub async fn next_frame(&mut self) -> io::Result<NextFrame> {
-        let mut len_buf = [0u8; 4];
-        match self.inner.read_exact(&mut len_buf).await {
-            Ok(_) => {
-                let frame_len = u32::from_le_bytes(len_buf) as usize;
-                let mut buf = vec![0u8; frame_len];
-                self.inner.read_exact(&mut buf).await?;
-                Ok(NextFrame::Frame(Bytes::from(buf)))
-            }
-            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => match &self.signal {
-                None => Ok(NextFrame::Done),
-                Some(sig) => {
-                    if sig.done.load(Ordering::Acquire) {
-                        Ok(NextFrame::Done)
-                    } else {
-                        tokio::time::timeout(Duration::from_millis(500), sig.notify.notified())
-                            .await
-                            .ok();
-                        Ok(NextFrame::Pending)
-                    }
+        let Some(frame_len) = self.next_frame_len().await? else {
+            return Ok(wait_for_writer(&self.signal).await);
+        };
+        let mut buf = vec![0u8; frame_len];
+        self.inner.reader().read_exact(&mut buf).await?;
+        Ok(NextFrame::Frame(Bytes::from(buf)))
+    }
+
+    /// Discard up to `frames` frames, waiting on the writer if needed. File
+    /// readers seek over the frames instead of reading them.
+    /// Returns how many were skipped; fewer than `frames` means the audio ended.
+    pub async fn skip_frames(&mut self, frames: u64) -> io::Result<u64> {
+        let mut skipped = 0;
+        while skipped < frames {
+            match self.next_frame_len().await? {
+                Some(frame_len) => {
+                    self.inner.skip(frame_len).await?;
+                    skipped += 1;
                 }
-            },
+                None => match wait_for_writer(&self.signal).await {
+                    NextFrame::Done => break,
+                    _ => continue,
+                },
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// Length prefix of the next frame, or `None` at the end of what has been
+    /// written so far.
+    async fn next_frame_len(&mut self) -> io::Result<Option<usize>> {
+        let mut len_buf = [0u8; 4];
+        match self.inner.reader().read_exact(&mut len_buf).await {
+            Ok(_) => Ok(Some(u32::from_le_bytes(len_buf) as usize)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
             Err(e) => Err(e),
         }
     }
@@ -252,6 +303,24 @@ pub async fn finalize(
     }
 }
 
+/// Wait briefly for the writer to append more frames. Takes the signal rather
+/// than the reader so the future stays `Send` while the reader is borrowed.
+async fn wait_for_writer(signal: &Option<Arc<WriteSignal>>) -> NextFrame {
+    match signal {
+        None => NextFrame::Done,
+        Some(sig) => {
+            if sig.done.load(Ordering::Acquire) {
+                NextFrame::Done
+            } else {
+                tokio::time::timeout(Duration::from_millis(500), sig.notify.notified())
+                    .await
+                    .ok();
+                NextFrame::Pending
+            }
+        }
+    }
+}
+
 async fn remove_if_exists(path: &Path) -> io::Result<()> {
     match fs::remove_file(path).await {
         Ok(()) => Ok(()),
@@ -26,6 +26,9 @@ pub struct CacheEntry {
     pub metadatas: Vec<AudioMetadata>,
     pub cache_key: AudioCachePolicy,
     pub kind: CacheEntryKind,
+    /// Integrated loudness (LUFS), once the audio engine has measured it.
+    pub loudness_lufs: Option<f32>,
+    pub duration_ms: Option<u64>,
 }
 
 impl CacheEntry {
@@ -133,6 +133,66 @@ async fn open_reader_reads_back_frames() {
     }
 }
 
+#[tokio::test]
+async fn store_records_duration_from_frame_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = open_cache(&dir).await;
+
+    store_n_frames(&cache, "tap1", "k1", 50).await;
+
+    let entry = cache.get_entry(&tap("tap1"), &key("k1")).await.unwrap();
+    assert_eq!(entry.duration_ms, Some(1000));
+}
+
+#[tokio::test]
+async fn skip_frames_starts_reader_at_offset() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = open_cache(&dir).await;
+
+    let (tx, rx) = mpsc::channel(16);
+    let (done_tx, done_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        for i in 0..5u8 {
+            tx.send(Bytes::from(vec![i; 8])).await.unwrap();
+        }
+        done_tx.send(()).unwrap();
+    });
+    cache.store(item("tap1", "k1"), meta("t"), policy(), rx, done_rx).await.unwrap();
+
+    let mut reader = cache.open_reader(&tap("tap1"), &key("k1")).await.unwrap();
+    assert_eq!(reader.skip_frames(3).await.unwrap(), 3);
+    match reader.next_frame().await.unwrap() {
+        zako3_preload_cache::NextFrame::Frame(f) => assert_eq!(f, Bytes::from(vec![3u8; 8])),
+        _ => panic!("expected Frame, got Pending or Done"),
+    }
+
+    // Skipping past the end stops at the last frame.
+    assert_eq!(reader.skip_frames(10).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn skip_frames_seeks_over_frames_larger_than_the_read_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = open_cache(&dir).await;
+
+    let (tx, rx) = mpsc::channel(16);
+    let (done_tx, done_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        for i in 0..5u8 {
+            tx.send(Bytes::from(vec![i; 20_000])).await.unwrap();
+        }
+        done_tx.send(()).unwrap();
+    });
+    cache.store(item("tap1", "k1"), meta("t"), policy(), rx, done_rx).await.unwrap();
+
+    let mut reader = cache.open_reader(&tap("tap1"), &key("k1")).await.unwrap();
+    assert_eq!(reader.skip_frames(4).await.unwrap(), 4);
+    match reader.next_frame().await.unwrap() {
+        zako3_preload_cache::NextFrame::Frame(f) => assert_eq!(f, Bytes::from(vec![4u8; 20_000])),
+        _ => panic!("expected Frame, got Pending or Done"),
+    }
+}
+
 #[tokio::test]
 async fn open_reader_returns_none_for_unknown_key() {
     let dir = tempfile::tempdir().unwrap();
@@ -455,3 +515,38 @@ async fn entry_survives_cache_reopen() {
         serde_json::to_string(&meta("track")).unwrap()
     );
 }
+
+// ---------------------------------------------------------------------------
+// Loudness
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn set_loudness_is_persisted_in_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let path: PathBuf = dir.path().to_path_buf();
+
+    {
+        let cache = FileAudioCache::open(path.clone(), None).await.unwrap();
+        store_n_frames(&cache, "tap1", "k1", 2).await;
+
+        let entry = cache.get_entry(&tap("tap1"), &key("k1")).await.unwrap();
+        assert_eq!(entry.loudness_lufs, None);
+
+        cache.set_loudness(&tap("tap1"), &key("k1"), -14.5).await.unwrap();
+        let entry = cache.get_entry(&tap("tap1"), &key("k1")).await.unwrap();
+        assert_eq!(entry.loudness_lufs, Some(-14.5));
+    }
+
+    let cache2 = FileAudioCache::open(path, None).await.unwrap();
+    let entry = cache2.get_entry(&tap("tap1"), &key("k1")).await.unwrap();
+    assert_eq!(entry.loudness_lufs, Some(-14.5));
+}
+
+#[tokio::test]
+async fn set_loudness_on_unknown_key_is_not_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = open_cache(&dir).await;
+
+    let err = cache.set_loudness(&tap("tap1"), &key("nope"), -20.0).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
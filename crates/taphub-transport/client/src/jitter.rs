@@ -8,12 +8,21 @@
 //! [`zako3_taphub_transport_lib::encode_chunk`]).
 
 use std::collections::BTreeMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use protofish3::xfer::RecvXfer;
 use zako3_taphub_transport_lib::parse_chunk;
 
+use crate::metrics::metrics;
+
 const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+/// Largest Opus frame, 120 ms at 48 kHz, in samples per channel.
+const MAX_FRAME_SAMPLES: usize = 5760;
+/// Playout delay aimed for, in multiples of the estimated jitter.
+const JITTER_MULTIPLIER: f64 = 3.0;
+/// How slowly the delay shrinks back once the link calms down, as a
+/// fraction of the gap closed per packet.
+const DELAY_RELEASE: f64 = 1.0 / 64.0;
 
 #[derive(Debug, thiserror::Error)]
 pub enum JitterError {
@@ -23,24 +32,122 @@ pub enum JitterError {
     Stalled,
 }
 
-pub struct OpusJitterBuffer<'a> {
-    receiver: RecvXfer<'a>,
+/// Range the playout delay adapts within, in ms of stream time.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayoutDelayBounds {
+    pub initial_ms: u64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+}
+
+/// How long a missing frame is waited for, sized from the interarrival
+/// jitter of the packets so far.
+struct PlayoutDelay {
+    bounds: PlayoutDelayBounds,
+    frame_size_ms: u64,
+    delay_ms: f64,
+    /// RFC 3550 interarrival jitter estimate, in ms.
+    jitter_ms: f64,
+    /// Stream timestamp and arrival time of the previous packet.
+    previous: Option<(u64, Instant)>,
+    newest_ts: Option<u64>,
+}
+
+impl PlayoutDelay {
+    fn new(bounds: PlayoutDelayBounds, frame_size_ms: u64) -> Self {
+        let delay_ms = bounds.initial_ms.clamp(bounds.min_ms, bounds.max_ms) as f64;
+        metrics().playout_delay_ms.record(delay_ms as u64, &[]);
+        Self {
+            bounds,
+            frame_size_ms,
+            delay_ms,
+            jitter_ms: 0.0,
+            previous: None,
+            newest_ts: None,
+        }
+    }
+
+    fn current_ms(&self) -> u64 {
+        self.delay_ms.round() as u64
+    }
+
+    /// Fold in a packet that arrived at `arrival`. `late` means its frame
+    /// was already concealed.
+    fn on_arrival(&mut self, ts: u64, arrival: Instant, late: bool) {
+        // RFC 3550 section 6.4.1: D = (Rj - Ri) - (Sj - Si) against the
+        // previous packet, smoothed with a gain of 1/16.
+        if let Some((previous_ts, previous_arrival)) = self.previous {
+            let received_ms = signed_ms(arrival, previous_arrival);
+            let sent_ms = ts as f64 - previous_ts as f64;
+            let transit_diff = (received_ms - sent_ms).abs();
+            self.jitter_ms += (transit_diff - self.jitter_ms) / 16.0;
+        }
+        self.previous = Some((ts, arrival));
+
+        let behind = self.newest_ts.map_or(0, |newest| newest.saturating_sub(ts));
+        self.newest_ts = Some(self.newest_ts.map_or(ts, |newest| newest.max(ts)));
+
+        let target = if late {
+            // At least enough to have waited for this one.
+            (behind + self.frame_size_ms) as f64
+        } else {
+            JITTER_MULTIPLIER * self.jitter_ms + self.frame_size_ms as f64
+        };
+        let delay_ms = if target > self.delay_ms {
+            target
+        } else {
+            self.delay_ms + (target - self.delay_ms) * DELAY_RELEASE
+        };
+
+        let previous = self.current_ms();
+        self.delay_ms = delay_ms.clamp(self.bounds.min_ms as f64, self.bounds.max_ms as f64);
+        if self.current_ms() != previous {
+            metrics().playout_delay_ms.record(self.current_ms(), &[]);
+        }
+    }
+}
+
+/// `later - earlier` in ms, negative if `later` is actually earlier.
+fn signed_ms(later: Instant, earlier: Instant) -> f64 {
+    match later.checked_duration_since(earlier) {
+        Some(elapsed) => elapsed.as_secs_f64() * 1000.0,
+        None => -(earlier.duration_since(later).as_secs_f64() * 1000.0),
+    }
+}
+
+/// Where a jitter buffer reads its chunks from.
+pub trait ChunkSource {
+    /// The next chunk as framed by [`zako3_taphub_transport_lib::encode_chunk`],
+    /// or `None` once the stream has ended.
+    fn recv(&mut self) -> impl Future<Output = Option<Vec<u8>>> + Send;
+}
+
+impl ChunkSource for RecvXfer<'_> {
+    async fn recv(&mut self) -> Option<Vec<u8>> {
+        RecvXfer::recv(self).await
+    }
+}
+
+pub struct OpusJitterBuffer<S> {
+    receiver: S,
     decoder: opus::Decoder,
     buffer: BTreeMap<u64, Vec<u8>>,
     frame_size_ms: u64,
-    playout_delay_ms: u64,
+    /// Samples per channel in one frame.
+    frame_samples: usize,
+    playout_delay: PlayoutDelay,
     next_play_ts: Option<u64>,
     channels: opus::Channels,
     is_eof: bool,
 }
 
-impl<'a> OpusJitterBuffer<'a> {
+impl<S: ChunkSource> OpusJitterBuffer<S> {
     pub fn new(
-        receiver: RecvXfer<'a>,
+        receiver: S,
         sample_rate: u32,
         channels: opus::Channels,
         frame_size_ms: u64,
-        playout_delay_ms: u64,
+        playout_delay: PlayoutDelayBounds,
     ) -> Result<Self, opus::Error> {
         let decoder = opus::Decoder::new(sample_rate, channels)?;
         Ok(Self {
@@ -48,14 +155,17 @@ pub fn new(
             decoder,
             buffer: BTreeMap::new(),
             frame_size_ms,
-            playout_delay_ms,
+            frame_samples: (sample_rate as u64 * frame_size_ms / 1000) as usize,
+            playout_delay: PlayoutDelay::new(playout_delay, frame_size_ms),
             next_play_ts: None,
             channels,
             is_eof: false,
         })
     }
 
-    /// Yields the next decoded PCM frame.
+    /// Yields the next decoded PCM frame. A frame that never arrives is
+    /// rebuilt from the next packet's in-band FEC, or concealed by the
+    /// decoder, so the stream keeps its timing.
     ///
     /// Returns `Err(JitterError::Stalled)` if no frame arrives for
     /// [`STALL_TIMEOUT`].
@@ -63,29 +173,28 @@ pub async fn yield_pcm(&mut self) -> Result<Option<Vec<f32>>, JitterError> {
         loop {
             // Buffer management
             if let Some(next_play_ts) = self.next_play_ts {
+                // A frame that turns up after it was concealed is too late.
+                if self
+                    .buffer
+                    .first_key_value()
+                    .is_some_and(|(&ts, _)| ts < next_play_ts)
+                {
+                    self.buffer = self.buffer.split_off(&next_play_ts);
+                }
                 let max_ts = self.buffer.keys().last().copied().unwrap_or(0);
 
                 if let Some(chunk) = self.buffer.remove(&next_play_ts) {
-                    let max_samples = 5760 * self.channels as usize;
-                    let mut pcm = vec![0f32; max_samples];
+                    let mut pcm = vec![0f32; MAX_FRAME_SAMPLES * self.channels as usize];
                     let decoded_len = self.decoder.decode_float(&chunk, &mut pcm, false)?;
                     pcm.truncate(decoded_len * self.channels as usize);
                     self.next_play_ts = Some(next_play_ts + self.frame_size_ms);
                     return Ok(Some(pcm));
-                } else if self.is_eof {
-                    if self.buffer.is_empty() {
-                        return Ok(None); // Stop if EOF and empty
-                    }
-                    // Skip gap: set next_play_ts to the next available ts
-                    self.next_play_ts = Some(*self.buffer.keys().next().unwrap());
-                    continue;
-                } else if max_ts.saturating_sub(next_play_ts) >= self.playout_delay_ms {
-                    let max_samples = 5760 * self.channels as usize;
-                    let mut pcm = vec![0f32; max_samples];
-                    let decoded_len = self.decoder.decode_float(&[], &mut pcm, true)?;
-                    pcm.truncate(decoded_len * self.channels as usize);
-                    self.next_play_ts = Some(next_play_ts + self.frame_size_ms);
-                    return Ok(Some(pcm));
+                } else if self.is_eof && self.buffer.is_empty() {
+                    return Ok(None); // Stop if EOF and empty
+                } else if self.is_eof
+                    || max_ts.saturating_sub(next_play_ts) >= self.playout_delay.current_ms()
+                {
+                    return Ok(Some(self.conceal(next_play_ts)?));
                 }
             }
 
@@ -102,6 +211,12 @@ pub async fn yield_pcm(&mut self) -> Result<Option<Vec<f32>>, JitterError> {
                     if self.next_play_ts.is_none() {
                         self.next_play_ts = Some(ts);
                     }
+                    let late = self.next_play_ts.is_some_and(|next| ts < next);
+                    metrics().received_frames.add(1, &[]);
+                    if late {
+                        metrics().late_frames.add(1, &[]);
+                    }
+                    self.playout_delay.on_arrival(ts, Instant::now(), late);
                     self.buffer.insert(ts, body.to_vec());
                 }
                 Ok(None) => {
@@ -114,4 +229,242 @@ pub async fn yield_pcm(&mut self) -> Result<Option<Vec<f32>>, JitterError> {
             }
         }
     }
+
+    /// Stand in for the lost frame at `ts`. FEC needs the packet right after
+    /// it; otherwise the decoder extrapolates from what it last played.
+    fn conceal(&mut self, ts: u64) -> Result<Vec<f32>, opus::Error> {
+        // Both produce exactly as many samples as the buffer has room for.
+        let mut pcm = vec![0f32; self.frame_samples * self.channels as usize];
+        let next = self
+            .buffer
+            .get(&(ts + self.frame_size_ms))
+            .filter(|packet| may_carry_fec(packet));
+        let decoded_len = match next {
+            Some(packet) => {
+                let len = self.decoder.decode_float(packet, &mut pcm, true)?;
+                metrics().recovered_frames.add(1, &[]);
+                len
+            }
+            None => {
+                let len = self.decoder.decode_float(&[], &mut pcm, false)?;
+                metrics().concealed_frames.add(1, &[]);
+                len
+            }
+        };
+        pcm.truncate(decoded_len * self.channels as usize);
+        self.next_play_ts = Some(ts + self.frame_size_ms);
+        Ok(pcm)
+    }
+}
+
+/// In-band FEC only exists in SILK and hybrid packets, which use the lower
+/// half of the TOC configurations. CELT-only packets never carry it.
+fn may_carry_fec(packet: &[u8]) -> bool {
+    packet.first().is_some_and(|toc| toc >> 3 < 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use zako3_taphub_transport_lib::{Timestamp, encode_chunk};
+
+    const SAMPLE_RATE: u32 = 48000;
+    const FRAME_MS: u64 = 20;
+    const FRAME_SAMPLES: usize = 960;
+    /// A fixed delay keeps the tests independent of the delay estimate.
+    const FIXED_DELAY: PlayoutDelayBounds = PlayoutDelayBounds {
+        initial_ms: FRAME_MS,
+        min_ms: FRAME_MS,
+        max_ms: FRAME_MS,
+    };
+
+    /// Room to adapt in either direction from a one-frame start.
+    const ADAPTIVE_DELAY: PlayoutDelayBounds = PlayoutDelayBounds {
+        initial_ms: FRAME_MS,
+        min_ms: FRAME_MS,
+        max_ms: 500,
+    };
+
+    impl ChunkSource for mpsc::UnboundedReceiver<Vec<u8>> {
+        async fn recv(&mut self) -> Option<Vec<u8>> {
+            mpsc::UnboundedReceiver::recv(self).await
+        }
+    }
+
+    /// Stereo packets of a tone, encoded with in-band FEC so every packet
+    /// also carries the one before it.
+    fn encode_packets(count: usize) -> Vec<Vec<u8>> {
+        let mut encoder =
+            opus::Encoder::new(SAMPLE_RATE, opus::Channels::Stereo, opus::Application::Voip)
+                .unwrap();
+        encoder.set_bitrate(opus::Bitrate::Bits(24_000)).unwrap();
+        encoder.set_inband_fec(true).unwrap();
+        encoder.set_packet_loss_perc(20).unwrap();
+
+        (0..count)
+            .map(|i| {
+                let pcm: Vec<f32> = (0..FRAME_SAMPLES)
+                    .flat_map(|n| {
+                        let t = (i * FRAME_SAMPLES + n) as f32 / SAMPLE_RATE as f32;
+                        let s = 0.4 * (t * 440.0 * std::f32::consts::TAU).sin();
+                        [s, s]
+                    })
+                    .collect();
+                let mut out = vec![0u8; 4000];
+                let len = encoder.encode_float(&pcm, &mut out).unwrap();
+                out.truncate(len);
+                out
+            })
+            .collect()
+    }
+
+    /// Send the packets at the given frame indices, in that order, then end
+    /// the stream.
+    fn jitter_buffer(
+        packets: &[Vec<u8>],
+        order: &[usize],
+    ) -> OpusJitterBuffer<mpsc::UnboundedReceiver<Vec<u8>>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for &i in order {
+            let ts = Timestamp(i as u64 * FRAME_MS);
+            tx.send(encode_chunk(ts, &packets[i])).unwrap();
+        }
+        OpusJitterBuffer::new(
+            rx,
+            SAMPLE_RATE,
+            opus::Channels::Stereo,
+            FRAME_MS,
+            FIXED_DELAY,
+        )
+        .unwrap()
+    }
+
+    async fn drain(
+        buffer: &mut OpusJitterBuffer<mpsc::UnboundedReceiver<Vec<u8>>>,
+    ) -> Vec<Vec<f32>> {
+        let mut frames = Vec::new();
+        while let Some(pcm) = buffer.yield_pcm().await.unwrap() {
+            frames.push(pcm);
+        }
+        frames
+    }
+
+    /// How a reference decoder should be driven for one frame.
+    enum Step<'a> {
+        Packet(&'a [u8]),
+        Fec(&'a [u8]),
+        Plc,
+    }
+
+    fn reference(steps: &[Step]) -> Vec<Vec<f32>> {
+        let mut decoder = opus::Decoder::new(SAMPLE_RATE, opus::Channels::Stereo).unwrap();
+        steps
+            .iter()
+            .map(|step| {
+                let mut pcm = vec![0f32; FRAME_SAMPLES * 2];
+                let len = match step {
+                    Step::Packet(p) => decoder.decode_float(p, &mut pcm, false),
+                    Step::Fec(p) => decoder.decode_float(p, &mut pcm, true),
+                    Step::Plc => decoder.decode_float(&[], &mut pcm, false),
+                }
+                .unwrap();
+                pcm.truncate(len * 2);
+                pcm
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn lost_frame_is_rebuilt_from_the_next_packets_fec() {
+        let packets = encode_packets(4);
+        assert!(may_carry_fec(&packets[2]));
+        let mut buffer = jitter_buffer(&packets, &[0, 2, 3]);
+
+        let frames = drain(&mut buffer).await;
+
+        let recovered = reference(&[
+            Step::Packet(&packets[0]),
+            Step::Fec(&packets[2]),
+            Step::Packet(&packets[2]),
+            Step::Packet(&packets[3]),
+        ]);
+        assert_eq!(frames, recovered);
+        let concealed = reference(&[Step::Packet(&packets[0]), Step::Plc]);
+        assert_ne!(frames[1], concealed[1]);
+    }
+
+    #[tokio::test]
+    async fn gap_without_a_following_packet_is_concealed() {
+        let packets = encode_packets(5);
+        let mut buffer = jitter_buffer(&packets, &[0, 1, 4]);
+
+        let frames = drain(&mut buffer).await;
+
+        // Frame 2 has no packet behind it to take FEC from; frame 3 does.
+        let expected = reference(&[
+            Step::Packet(&packets[0]),
+            Step::Packet(&packets[1]),
+            Step::Plc,
+            Step::Fec(&packets[4]),
+            Step::Packet(&packets[4]),
+        ]);
+        assert_eq!(frames, expected);
+    }
+
+    #[tokio::test]
+    async fn frame_arriving_after_it_was_concealed_is_dropped() {
+        let packets = encode_packets(5);
+        let mut buffer = jitter_buffer(&packets, &[0, 2, 3, 1, 4]);
+
+        let frames = drain(&mut buffer).await;
+
+        let expected = reference(&[
+            Step::Packet(&packets[0]),
+            Step::Fec(&packets[2]),
+            Step::Packet(&packets[2]),
+            Step::Packet(&packets[3]),
+            Step::Packet(&packets[4]),
+        ]);
+        assert_eq!(frames, expected);
+    }
+
+    #[test]
+    fn playout_delay_follows_interarrival_jitter() {
+        let mut delay = PlayoutDelay::new(ADAPTIVE_DELAY, FRAME_MS);
+        let start = Instant::now();
+        let mut arrival_ms = 0;
+
+        // Sent every 20 ms, delivered in pairs every 40 ms.
+        for i in 0..200 {
+            if i % 2 == 1 {
+                arrival_ms += 2 * FRAME_MS;
+            }
+            let arrival = start + Duration::from_millis(arrival_ms);
+            delay.on_arrival(i * FRAME_MS, arrival, false);
+        }
+        let grown = delay.current_ms();
+        assert!(grown >= 70, "delay only grew to {grown} ms");
+
+        // Delivered as evenly as it was sent.
+        for i in 200..600 {
+            arrival_ms += FRAME_MS;
+            let arrival = start + Duration::from_millis(arrival_ms);
+            delay.on_arrival(i * FRAME_MS, arrival, false);
+        }
+        let settled = delay.current_ms();
+        assert!(settled <= 25, "delay only shrank to {settled} ms");
+    }
+
+    #[test]
+    fn steady_arrivals_leave_the_delay_alone() {
+        let mut delay = PlayoutDelay::new(ADAPTIVE_DELAY, FRAME_MS);
+        let start = Instant::now();
+
+        for i in 0..100 {
+            let arrival = start + Duration::from_millis(i * FRAME_MS);
+            delay.on_arrival(i * FRAME_MS, arrival, false);
+        }
+        assert_eq!(delay.current_ms(), FRAME_MS);
+    }
 }
@@ -1,4 +1,5 @@
 mod jitter;
+mod metrics;
 
 use protofish3::xfer::XferRecv;
 use protofish3::{Client, ClientConfig, ReconnectConfig, ReconnectingClient, XferMode};
@@ -12,10 +13,17 @@
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use jitter::OpusJitterBuffer;
+use jitter::{OpusJitterBuffer, PlayoutDelayBounds};
 use zako3_taphub_transport_lib::{TapHubRequest, TapHubResponse};
 use zako3_types::{AudioMetaResponse, AudioRequest, AudioResponse, CachedAudioRequest, TapHubError};
 
+/// Starts at the old fixed delay; allowed to grow to ride out congested links.
+const PLAYOUT_DELAY: PlayoutDelayBounds = PlayoutDelayBounds {
+    initial_ms: 100,
+    min_ms: 40,
+    max_ms: 400,
+};
+
 pub struct TransportClient {
     conn: Arc<ReconnectingClient>,
 }
@@ -138,7 +146,7 @@ pub async fn request_audio(&self, req: CachedAudioRequest) -> Result<AudioRespon
                         48000,
                         opus::Channels::Stereo,
                         20,
-                        100,
+                        PLAYOUT_DELAY,
                     ) {
                         Ok(j) => j,
                         Err(e) => {
@@ -173,6 +181,8 @@ pub async fn request_audio(&self, req: CachedAudioRequest) -> Result<AudioRespon
                     cache_key: Some(meta.cache_key),
                     metadatas: meta.metadatas,
                     stream: rx,
+                    loudness_lufs: meta.loudness_lufs,
+                    duration_ms: meta.duration_ms,
                 })
             }
             TapHubResponse::Error(e) => Err(e),
@@ -216,6 +226,24 @@ pub async fn request_audio_meta(
             ))),
         }
     }
+
+    pub async fn store_loudness(
+        &self,
+        req: CachedAudioRequest,
+        loudness_lufs: f32,
+    ) -> Result<(), TapHubError> {
+        match self
+            .execute_request(TapHubRequest::StoreLoudness(req, loudness_lufs))
+            .await?
+        {
+            TapHubResponse::StoreLoudnessOk => Ok(()),
+            TapHubResponse::Error(e) => Err(e),
+            resp => Err(TapHubError::Internal(format!(
+                "Unexpected response to StoreLoudness: {:?}",
+                resp
+            ))),
+        }
+    }
 }
 
 async fn send_invalidate_cache(conn: Arc<ReconnectingClient>, req: CachedAudioRequest) {
@@ -0,0 +1,48 @@
+use opentelemetry::{
+    global,
+    metrics::{Counter, Gauge},
+};
+use std::sync::OnceLock;
+
+pub struct JitterMetrics {
+    pub concealed_frames: Counter<u64>,
+    pub recovered_frames: Counter<u64>,
+    pub received_frames: Counter<u64>,
+    pub late_frames: Counter<u64>,
+    pub playout_delay_ms: Gauge<u64>,
+}
+
+static METRICS: OnceLock<JitterMetrics> = OnceLock::new();
+
+/// Returns the process-wide jitter buffer OTel metrics.
+///
+/// The global meter provider must have been set (via `zako3_telemetry::init`) before the
+/// first call, otherwise instruments will be no-ops.
+pub fn metrics() -> &'static JitterMetrics {
+    METRICS.get_or_init(|| {
+        let meter = global::meter("taphub-transport-client");
+        JitterMetrics {
+            concealed_frames: meter
+                .u64_counter("taphub_jitter_concealed_frames_total")
+                .with_description("Lost audio frames filled in by Opus packet loss concealment")
+                .build(),
+            recovered_frames: meter
+                .u64_counter("taphub_jitter_recovered_frames_total")
+                .with_description("Lost audio frames rebuilt from the next packet's in-band FEC")
+                .build(),
+            received_frames: meter
+                .u64_counter("taphub_jitter_received_frames_total")
+                .with_description("Audio frames received by jitter buffers")
+                .build(),
+            late_frames: meter
+                .u64_counter("taphub_jitter_late_frames_total")
+                .with_description("Audio frames that arrived after they were concealed")
+                .build(),
+            playout_delay_ms: meter
+                .u64_gauge("taphub_jitter_playout_delay_ms")
+                .with_description("Playout delay of the most recently adapted jitter buffer")
+                .with_unit("ms")
+                .build(),
+        }
+    })
+}
@@ -34,6 +34,8 @@ pub enum TapHubRequest {
     PreloadAudio(CachedAudioRequest),
     RequestAudioMeta(AudioRequest),
     InvalidateCache(CachedAudioRequest),
+    /// Integrated loudness (LUFS) the client measured while playing the request.
+    StoreLoudness(CachedAudioRequest, f32),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,4 +44,5 @@ pub enum TapHubResponse {
     MetaReady(AudioMetaResponse),
     Error(TapHubError),
     InvalidateCacheOk,
+    StoreLoudnessOk,
 }
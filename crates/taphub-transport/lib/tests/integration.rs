@@ -32,6 +32,8 @@ async fn handle_request_audio(
                 AudioMetadata::Artist("Test Artist".to_string()),
             ],
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         tokio::spawn(async move {
@@ -53,6 +55,8 @@ async fn handle_preload_audio(
                 AudioMetadata::Artist("Preload Artist".to_string()),
             ],
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -71,6 +75,8 @@ async fn handle_request_audio_meta(
                 AudioMetadata::Artist("Meta Artist".to_string()),
             ],
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -81,6 +87,19 @@ async fn handle_invalidate_cache(
     ) -> Result<(), TapHubError> {
         Ok(())
     }
+
+    async fn handle_store_loudness(
+        &self,
+        _req: CachedAudioRequest,
+        loudness_lufs: f32,
+        _headers: HashMap<String, String>,
+    ) -> Result<(), TapHubError> {
+        if loudness_lufs.is_finite() {
+            Ok(())
+        } else {
+            Err(TapHubError::Internal("non-finite loudness".to_string()))
+        }
+    }
 }
 
 fn generate_certs() -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) {
@@ -131,8 +150,15 @@ async fn test_transport_integration() {
         },
         discord_user_id: "123".to_string().into(),
         headers: HashMap::new(),
+        start_offset_ms: 0,
     };
 
+    client
+        .store_loudness(req.clone(), -14.0)
+        .await
+        .expect("store_loudness failed");
+    assert!(client.store_loudness(req.clone(), f32::NAN).await.is_err());
+
     let resp = client
         .preload_audio(req)
         .await
@@ -39,6 +39,13 @@ async fn handle_invalidate_cache(
         req: CachedAudioRequest,
         headers: HashMap<String, String>,
     ) -> Result<(), TapHubError>;
+
+    async fn handle_store_loudness(
+        &self,
+        req: CachedAudioRequest,
+        loudness_lufs: f32,
+        headers: HashMap<String, String>,
+    ) -> Result<(), TapHubError>;
 }
 
 pub struct TransportServer {
@@ -176,6 +183,17 @@ async fn handle_stream(
             };
             sender.send_msg(rmp_serde::to_vec(&resp)?).await?;
         }
+        TapHubRequest::StoreLoudness(req, loudness_lufs) => {
+            let headers = req.headers.clone();
+            let resp = match handler
+                .handle_store_loudness(req, loudness_lufs, headers)
+                .await
+            {
+                Ok(()) => TapHubResponse::StoreLoudnessOk,
+                Err(e) => TapHubResponse::Error(e),
+            };
+            sender.send_msg(rmp_serde::to_vec(&resp)?).await?;
+        }
     }
 
     Ok(())
@@ -1,17 +1,21 @@
 use std::collections::HashMap;
 
 use anyhow::{Context as _, Result};
+use jsonrpsee::core::client::Subscription;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
 use opentelemetry::global;
 use thiserror::Error;
 use tl_protocol::{
     AudioEngineCommand, AudioEngineCommandRequest, AudioEngineCommandResponse, AudioEngineError,
-    AudioEngineSessionCommand, AudioPlayRequest, SessionInfo, TrafficLightRpcClient,
+    AudioEngineSessionCommand, AudioPlayRequest, SessionInfo, TrafficLightEventsRpcClient,
+    TrafficLightRpcClient,
 };
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use zako3_types::{
-    AudioRequestString, AudioStopFilter, ChannelId, GuildId, QueueName, SessionState, TrackId,
-    Volume, hq::{DiscordUserId, TapId},
+    AudioRequestString, AudioStopFilter, ChannelId, GuildId, Pan, QueueMode, QueueName,
+    SessionAudioSettings, SessionEvent, SessionState, TrackDsp, TrackId, Volume,
+    hq::{DiscordUserId, TapId},
 };
 
 #[derive(Debug, Error)]
@@ -104,6 +108,7 @@ pub async fn leave(&self, guild_id: GuildId, channel_id: ChannelId) -> Result<()
         Self::ok_or_err(resp)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn play(
         &self,
         guild_id: GuildId,
@@ -113,30 +118,110 @@ pub async fn play(
         ars: AudioRequestString,
         volume: Volume,
         initiator: DiscordUserId,
+        dsp: TrackDsp,
     ) -> Result<(), TlClientError> {
         let req = Self::build_req(
             guild_id,
             channel_id,
             AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::Play(
-                AudioPlayRequest {
-                    queue_name,
-                    tap_id,
-                    ars,
-                    volume,
-                    initiator,
-                    headers: {
-                        let mut h = HashMap::new();
-                        let cx = tracing::Span::current().context();
-                        global::get_text_map_propagator(|p| p.inject_context(&cx, &mut h));
-                        h
-                    },
-                },
+                Self::play_request(queue_name, tap_id, ars, volume, initiator, dsp),
             )),
         );
         let resp = self.client.execute(req).await.map_err(Self::map_err)?;
         Self::ok_or_err(resp)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub async fn play_next(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        queue_name: QueueName,
+        tap_id: TapId,
+        ars: AudioRequestString,
+        volume: Volume,
+        initiator: DiscordUserId,
+        dsp: TrackDsp,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::PlayNext(
+                Self::play_request(queue_name, tap_id, ars, volume, initiator, dsp),
+            )),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_at(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        index: usize,
+        queue_name: QueueName,
+        tap_id: TapId,
+        ars: AudioRequestString,
+        volume: Volume,
+        initiator: DiscordUserId,
+        dsp: TrackDsp,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::InsertAt {
+                request: Self::play_request(queue_name, tap_id, ars, volume, initiator, dsp),
+                index,
+            }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    fn play_request(
+        queue_name: QueueName,
+        tap_id: TapId,
+        ars: AudioRequestString,
+        volume: Volume,
+        initiator: DiscordUserId,
+        dsp: TrackDsp,
+    ) -> AudioPlayRequest {
+        AudioPlayRequest {
+            queue_name,
+            tap_id,
+            ars,
+            volume,
+            initiator,
+            headers: {
+                let mut h = HashMap::new();
+                let cx = tracing::Span::current().context();
+                global::get_text_map_propagator(|p| p.inject_context(&cx, &mut h));
+                h
+            },
+            dsp,
+        }
+    }
+
+    pub async fn move_track(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        index: usize,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::Move {
+                track_id,
+                index,
+            }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
     pub async fn stop(
         &self,
         guild_id: GuildId,
@@ -230,6 +315,90 @@ pub async fn set_volume(
         Self::ok_or_err(resp)
     }
 
+    pub async fn set_dsp(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        dsp: TrackDsp,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::SetDsp {
+                track_id,
+                dsp,
+            }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    pub async fn set_pan(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        pan: Pan,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::SetPan { track_id, pan }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    pub async fn set_session_settings(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        settings: SessionAudioSettings,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::SetSettings(settings)),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    pub async fn set_queue_mode(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        mode: QueueMode,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::SetQueueMode(mode)),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
+    pub async fn seek(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        position_ms: u64,
+    ) -> Result<(), TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::Seek {
+                track_id,
+                position_ms,
+            }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        Self::ok_or_err(resp)
+    }
+
     pub async fn get_sessions_in_guild(
         &self,
         guild_id: GuildId,
@@ -272,6 +441,17 @@ pub async fn heartbeat_ae(&self, token: String, listen_addr: String) -> Result<(
             .map_err(Self::map_err)
     }
 
+    pub async fn publish_session_events(
+        &self,
+        token: String,
+        events: Vec<SessionEvent>,
+    ) -> Result<(), TlClientError> {
+        self.client
+            .publish_session_events(token, events)
+            .await
+            .map_err(Self::map_err)
+    }
+
     pub async fn get_session_state(
         &self,
         guild_id: GuildId,
@@ -305,4 +485,82 @@ pub async fn get_session_state(
             ))),
         }
     }
+
+    /// The last `seconds` of the session recording (everything still retained
+    /// if `None`), as an Ogg Opus file.
+    pub async fn export_recording(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        seconds: Option<u32>,
+    ) -> Result<Vec<u8>, TlClientError> {
+        let req = Self::build_req(
+            guild_id,
+            channel_id,
+            AudioEngineCommand::SessionCommand(AudioEngineSessionCommand::ExportRecording {
+                seconds,
+            }),
+        );
+        let resp = self.client.execute(req).await.map_err(Self::map_err)?;
+        match resp {
+            AudioEngineCommandResponse::Recording(ogg) => Ok(ogg.0),
+            AudioEngineCommandResponse::Error(AudioEngineError::AlreadyJoined) => {
+                Err(TlClientError::AlreadyJoined)
+            }
+            AudioEngineCommandResponse::Error(AudioEngineError::NotJoined) => {
+                Err(TlClientError::NotJoined)
+            }
+            AudioEngineCommandResponse::Error(AudioEngineError::PermissionDenied) => {
+                Err(TlClientError::PermissionDenied)
+            }
+            AudioEngineCommandResponse::Error(AudioEngineError::Tap(t)) => {
+                Err(TlClientError::Tap(t))
+            }
+            AudioEngineCommandResponse::Error(AudioEngineError::InternalError(msg)) => {
+                Err(TlClientError::Transport(anyhow::anyhow!("{msg}")))
+            }
+            other => Err(TlClientError::Transport(anyhow::anyhow!(
+                "unexpected response: {other:?}"
+            ))),
+        }
+    }
+}
+
+/// Track lifecycle events from every audio engine behind a TL instance.
+pub struct SessionEventStream {
+    // Dropping the client tears down the subscription, so it lives alongside it.
+    _client: WsClient,
+    subscription: Subscription<SessionEvent>,
+}
+
+impl SessionEventStream {
+    /// Subscribe to a TL instance at `url`. Takes the same `http://` address
+    /// as [`TlClient::connect`]; the subscription itself runs over WebSocket.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let ws_url = if let Some(rest) = url.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            url.to_string()
+        };
+        let client = WsClientBuilder::default()
+            .build(&ws_url)
+            .await
+            .with_context(|| format!("failed to connect to TL events at {ws_url}"))?;
+        let subscription = client
+            .subscribe_session_events()
+            .await
+            .context("failed to subscribe to session events")?;
+        Ok(Self {
+            _client: client,
+            subscription,
+        })
+    }
+
+    /// The next event, or `None` once the connection is gone.
+    pub async fn next(&mut self) -> Option<Result<SessionEvent, TlClientError>> {
+        let item = self.subscription.next().await?;
+        Some(item.map_err(|e| TlClientError::Transport(anyhow::anyhow!("{e}"))))
+    }
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use zako3_types::{hq::*, *};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,9 +21,37 @@ pub enum AudioEngineCommandResponse {
     Ok,
     SessionState(SessionState),
     DiscordVoiceState(Vec<SessionInfo>),
+    Recording(OggOpus),
     Error(AudioEngineError),
 }
 
+/// An Ogg Opus file. Carried as base64 so a few minutes of audio doesn't
+/// turn into a JSON array of numbers.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OggOpus(pub Vec<u8>);
+
+impl std::fmt::Debug for OggOpus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OggOpus({} bytes)", self.0.len())
+    }
+}
+
+impl Serialize for OggOpus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for OggOpus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(encoded)
+            .map(OggOpus)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct SessionInfo {
     pub guild_id: GuildId,
@@ -56,6 +85,19 @@ pub trait TrafficLightRpc {
 
     #[method(name = "heartbeat_ae")]
     async fn heartbeat_ae(&self, token: String, listen_addr: String) -> jsonrpsee::core::RpcResult<()>;
+
+    /// Called by an AE with the lifecycle events its sessions produced, in order.
+    #[method(name = "publish_session_events")]
+    async fn publish_session_events(&self, token: String, events: Vec<SessionEvent>) -> jsonrpsee::core::RpcResult<()>;
+}
+
+/// Lifecycle events relayed from every AE. Kept apart from `TrafficLightRpc`
+/// because subscriptions need a WebSocket client, while the rest of TL is
+/// called over plain HTTP.
+#[jsonrpsee::proc_macros::rpc(server, client)]
+pub trait TrafficLightEventsRpc {
+    #[subscription(name = "subscribe_session_events" => "session_event", unsubscribe = "unsubscribe_session_events", item = SessionEvent)]
+    async fn subscribe_session_events(&self) -> jsonrpsee::core::SubscriptionResult;
 }
 
 #[jsonrpsee::proc_macros::rpc(server, client)]
@@ -72,6 +114,8 @@ pub struct AudioPlayRequest {
     pub volume: Volume,
     pub initiator: DiscordUserId,
     pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub dsp: TrackDsp,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -89,13 +133,22 @@ pub fn operation(&self) -> &'static str {
             AudioEngineCommand::SessionCommand(sc) => match sc {
                 AudioEngineSessionCommand::Leave => "leave",
                 AudioEngineSessionCommand::Play(_) => "play",
+                AudioEngineSessionCommand::PlayNext(_) => "play_next",
+                AudioEngineSessionCommand::InsertAt { .. } => "insert_at",
+                AudioEngineSessionCommand::Move { .. } => "move",
                 AudioEngineSessionCommand::Stop(_) => "stop",
                 AudioEngineSessionCommand::StopMany(_) => "stop_many",
                 AudioEngineSessionCommand::SetVolume { .. } => "set_volume",
+                AudioEngineSessionCommand::SetDsp { .. } => "set_dsp",
+                AudioEngineSessionCommand::SetPan { .. } => "set_pan",
+                AudioEngineSessionCommand::Seek { .. } => "seek",
+                AudioEngineSessionCommand::SetSettings(_) => "set_settings",
                 AudioEngineSessionCommand::NextMusic => "next_music",
+                AudioEngineSessionCommand::SetQueueMode(_) => "set_queue_mode",
                 AudioEngineSessionCommand::Pause(_) => "pause",
                 AudioEngineSessionCommand::Resume(_) => "resume",
                 AudioEngineSessionCommand::GetSessionState => "get_session_state",
+                AudioEngineSessionCommand::ExportRecording { .. } => "export_recording",
             },
         }
     }
@@ -106,14 +159,26 @@ pub enum AudioEngineSessionCommand {
     Leave,
 
     Play(AudioPlayRequest),
+    /// Queue right behind the playing track.
+    PlayNext(AudioPlayRequest),
+    InsertAt { request: AudioPlayRequest, index: usize },
+    Move { track_id: TrackId, index: usize },
     Stop(TrackId),
     StopMany(AudioStopFilter),
     SetVolume { track_id: TrackId, volume: Volume },
+    SetDsp { track_id: TrackId, dsp: TrackDsp },
+    SetPan { track_id: TrackId, pan: Pan },
+    Seek { track_id: TrackId, position_ms: u64 },
+    SetSettings(SessionAudioSettings),
 
     NextMusic,
+    SetQueueMode(QueueMode),
 
     Pause(QueueName),
     Resume(QueueName),
 
     GetSessionState,
+    /// Export the last `seconds` of the session recording, or everything
+    /// still retained.
+    ExportRecording { seconds: Option<u32> },
 }
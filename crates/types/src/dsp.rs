@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+pub const DSP_RATE_MIN: f32 = 0.5;
+pub const DSP_RATE_MAX: f32 = 2.0;
+pub const DSP_PITCH_SEMITONES_MAX: f32 = 12.0;
+
+/// Per-track DSP parameters applied by the mixer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackDsp {
+    /// Time-stretch factor. Changes duration without changing pitch.
+    pub speed: f32,
+    /// Pitch shift in semitones. Duration is kept.
+    pub pitch: f32,
+    /// Playback rate. Changes duration and pitch together ("nightcore").
+    pub tempo: f32,
+}
+
+impl Default for TrackDsp {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch: 0.0,
+            tempo: 1.0,
+        }
+    }
+}
+
+impl TrackDsp {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Clamp every parameter to the supported range. Non-finite values fall
+    /// back to their defaults.
+    pub fn clamped(self) -> Self {
+        fn rate(v: f32) -> f32 {
+            if v.is_finite() {
+                v.clamp(DSP_RATE_MIN, DSP_RATE_MAX)
+            } else {
+                1.0
+            }
+        }
+        let pitch = if self.pitch.is_finite() {
+            self.pitch
+                .clamp(-DSP_PITCH_SEMITONES_MAX, DSP_PITCH_SEMITONES_MAX)
+        } else {
+            0.0
+        };
+        Self {
+            speed: rate(self.speed),
+            pitch,
+            tempo: rate(self.tempo),
+        }
+    }
+
+    /// Pitch shift expressed as a frequency ratio.
+    pub fn pitch_ratio(&self) -> f32 {
+        2f32.powf(self.pitch / 12.0)
+    }
+}
@@ -52,6 +52,18 @@ pub enum ZakoError {
 
     #[error("taphub request timed out")]
     TaphubTimeout,
+
+    #[error("track not found: {0}")]
+    TrackNotFound(crate::TrackId),
+
+    #[error("track is playing: {0}")]
+    TrackPlaying(crate::TrackId),
+
+    #[error("track is not cached and cannot seek: {0}")]
+    SeekUnsupported(crate::TrackId),
+
+    #[error("no recording available")]
+    RecordingUnavailable,
 }
 
 pub type ZakoResult<T> = Result<T, ZakoError>;
@@ -1,4 +1,5 @@
 use super::tap::{TapOccupation, TapPermission, TapRole};
+use crate::OnlineTapState;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -121,6 +122,33 @@ pub struct TapStatsDto {
     pub uptime_percent: f64,
     pub use_rate_history: Vec<TimeSeriesPointDto>,
     pub cache_hit_rate_history: Vec<TimeSeriesPointDto>,
+    pub connections: Vec<TapConnectionDto>,
+}
+
+/// A live connection of a tap and what it announced when it connected.
+#[derive(Debug, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TapConnectionDto {
+    pub friendly_name: String,
+    pub connected_at: DateTime<Utc>,
+    pub protocol_version: u32,
+    pub max_concurrent_requests: Option<u32>,
+    pub supports_metadata_only: bool,
+    pub supports_cancel: bool,
+}
+
+impl From<&OnlineTapState> for TapConnectionDto {
+    fn from(state: &OnlineTapState) -> Self {
+        let capabilities = &state.capabilities;
+        Self {
+            friendly_name: state.friendly_name.clone(),
+            connected_at: state.connected_at,
+            protocol_version: state.protocol_version,
+            max_concurrent_requests: capabilities.max_concurrent_requests,
+            supports_metadata_only: capabilities.supports_metadata_only,
+            supports_cancel: capabilities.supports_cancel,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema)]
@@ -3,6 +3,8 @@
 use std::collections::HashMap;
 use utoipa::ToSchema;
 
+use crate::{QueueMode, RepeatMode, SessionAudioSettings, SessionEvent, TrackEventKind};
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioMetadataDto {
@@ -21,6 +23,10 @@ pub struct TrackDto {
     pub requested_by: String,
     pub volume: f32,
     pub paused: bool,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub position_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -48,6 +54,10 @@ pub struct GuildPlaybackStateDto {
     pub queues: HashMap<String, Vec<TrackDto>>,
     #[serde(default)]
     pub queue_meta: HashMap<String, QueueMetaDto>,
+    #[serde(default)]
+    pub settings: SessionAudioSettings,
+    #[serde(default)]
+    pub queue_mode: QueueMode,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -78,14 +88,33 @@ pub struct SkipDto {
     pub channel_id: String,
 }
 
+/// Change the music queue mode. Omitted fields keep their current value.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetQueueModeDto {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub repeat: Option<RepeatMode>,
+    pub shuffle: Option<bool>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueueOperation {
-    /// "remove" or "set_volume"
+    /// "remove", "set_volume", "move", "play_next" or "insert_at"
     pub op: String,
+    /// The track to act on. Unused by "play_next" and "insert_at", which
+    /// queue a new track instead.
+    #[serde(default)]
     pub track_id: String,
+    /// Position in the queue for "move" and "insert_at". Index 0 is the
+    /// playing track.
     pub target_index: Option<usize>,
     pub volume: Option<f32>,
+    /// Queue for "play_next" and "insert_at". Defaults to "music".
+    pub queue_name: Option<String>,
+    pub tap_id: Option<String>,
+    pub audio_request: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
@@ -112,9 +141,67 @@ pub struct ResumeTrackDto {
     pub track_id: String,
 }
 
+/// A message spoken in every session of a guild, interrupting what plays.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementDto {
+    pub guild_id: String,
+    pub message: String,
+    /// TTS tap to speak it with. Defaults to `google`.
+    #[serde(default)]
+    pub tap_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementResultDto {
+    /// Voice sessions the announcement was queued in.
+    pub sessions: usize,
+    /// Voice sessions it could not be queued in.
+    pub failures: Vec<AnnouncementFailureDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementFailureDto {
+    pub channel_id: String,
+    pub error: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase", tag = "type")]
 pub enum PlaybackEvent {
     PlaybackChanged,
     VoiceStateChanged,
+    Track(TrackEventDto),
+}
+
+/// A track lifecycle event from the audio engine.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackEventDto {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub track_id: String,
+    /// `queued`, `started`, `ended`, `skipped`, `stopped`, `paused`,
+    /// `resumed` or `failed`.
+    pub event: String,
+    /// Why the track failed. Only set for `failed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&SessionEvent> for TrackEventDto {
+    fn from(event: &SessionEvent) -> Self {
+        Self {
+            guild_id: event.guild_id.to_string(),
+            channel_id: event.channel_id.to_string(),
+            track_id: event.track_id.to_string(),
+            event: event.kind.as_str().to_string(),
+            error: match &event.kind {
+                TrackEventKind::Failed { error } => Some(error.to_string()),
+                _ => None,
+            },
+        }
+    }
 }
@@ -5,6 +5,10 @@
 use utoipa::ToSchema;
 
 use super::TapId;
+use crate::{
+    DuckingSettings, EncoderSettings, FadeOutSettings, LoudnessSettings, RecordingSettings,
+    SessionAudioSettings, TransitionSettings, TtsPanSettings,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema)]
 pub struct TextMappingRule {
@@ -68,6 +72,20 @@ pub struct PartialUserSettings {
     pub max_message_length: UserSettingsField<u16>,
     pub enable_tts_queue: UserSettingsField<bool>,
     pub tts_voice: UserSettingsField<Option<TapId>>,
+    #[serde(default)]
+    pub music_ducking: UserSettingsField<DuckingSettings>,
+    #[serde(default)]
+    pub loudness_normalization: UserSettingsField<LoudnessSettings>,
+    #[serde(default)]
+    pub music_transitions: UserSettingsField<TransitionSettings>,
+    #[serde(default)]
+    pub stop_fade_out: UserSettingsField<FadeOutSettings>,
+    #[serde(default)]
+    pub session_recording: UserSettingsField<RecordingSettings>,
+    #[serde(default)]
+    pub opus_encoder: UserSettingsField<EncoderSettings>,
+    #[serde(default)]
+    pub tts_auto_pan: UserSettingsField<TtsPanSettings>,
 }
 
 /// Merge two scalar settings fields.
@@ -157,6 +175,13 @@ pub fn empty() -> Self {
             max_message_length: UserSettingsField::None,
             enable_tts_queue: UserSettingsField::None,
             tts_voice: UserSettingsField::None,
+            music_ducking: UserSettingsField::None,
+            loudness_normalization: UserSettingsField::None,
+            music_transitions: UserSettingsField::None,
+            stop_fade_out: UserSettingsField::None,
+            session_recording: UserSettingsField::None,
+            opus_encoder: UserSettingsField::None,
+            tts_auto_pan: UserSettingsField::None,
         }
     }
 
@@ -188,6 +213,16 @@ pub fn fold(more: &Self, less: &Self) -> Self {
             max_message_length: fold_field(&more.max_message_length, &less.max_message_length),
             enable_tts_queue: fold_field(&more.enable_tts_queue, &less.enable_tts_queue),
             tts_voice: fold_field(&more.tts_voice, &less.tts_voice),
+            music_ducking: fold_field(&more.music_ducking, &less.music_ducking),
+            loudness_normalization: fold_field(
+                &more.loudness_normalization,
+                &less.loudness_normalization,
+            ),
+            music_transitions: fold_field(&more.music_transitions, &less.music_transitions),
+            stop_fade_out: fold_field(&more.stop_fade_out, &less.stop_fade_out),
+            session_recording: fold_field(&more.session_recording, &less.session_recording),
+            opus_encoder: fold_field(&more.opus_encoder, &less.opus_encoder),
+            tts_auto_pan: fold_field(&more.tts_auto_pan, &less.tts_auto_pan),
         }
     }
 
@@ -209,6 +244,16 @@ fn extract<T>(field: UserSettingsField<T>, default: T) -> T {
             max_message_length: extract(self.max_message_length, 100),
             enable_tts_queue: extract(self.enable_tts_queue, true),
             tts_voice: extract(self.tts_voice, None),
+            music_ducking: extract(self.music_ducking, DuckingSettings::default()),
+            loudness_normalization: extract(
+                self.loudness_normalization,
+                LoudnessSettings::default(),
+            ),
+            music_transitions: extract(self.music_transitions, TransitionSettings::default()),
+            stop_fade_out: extract(self.stop_fade_out, FadeOutSettings::default()),
+            session_recording: extract(self.session_recording, RecordingSettings::default()),
+            opus_encoder: extract(self.opus_encoder, EncoderSettings::default()),
+            tts_auto_pan: extract(self.tts_auto_pan, TtsPanSettings::default()),
         }
     }
 }
@@ -224,6 +269,28 @@ pub struct UserSettings {
     pub max_message_length: u16,
     pub enable_tts_queue: bool,
     pub tts_voice: Option<TapId>,
+    pub music_ducking: DuckingSettings,
+    pub loudness_normalization: LoudnessSettings,
+    pub music_transitions: TransitionSettings,
+    pub stop_fade_out: FadeOutSettings,
+    pub session_recording: RecordingSettings,
+    pub opus_encoder: EncoderSettings,
+    pub tts_auto_pan: TtsPanSettings,
+}
+
+impl UserSettings {
+    /// The subset of guild-scope settings the audio engine applies to a session.
+    pub fn session_audio_settings(&self) -> SessionAudioSettings {
+        SessionAudioSettings {
+            ducking: self.music_ducking,
+            loudness: self.loudness_normalization,
+            transitions: self.music_transitions,
+            fade_out: self.stop_fade_out,
+            recording: self.session_recording,
+            encoder: self.opus_encoder,
+            tts_pan: self.tts_auto_pan,
+        }
+    }
 }
 
 impl Default for UserSettings {
@@ -4,6 +4,7 @@
 
 pub use zakofish::types::{
     AttachedMetadata, AudioCachePolicy, AudioCacheType, AudioMetadata, AudioRequestString,
+    TapCapabilities,
 };
 
 pub mod taphub;
@@ -15,8 +16,14 @@
 pub mod session_state;
 pub use session_state::*;
 
+pub mod session_event;
+pub use session_event::*;
+
 pub mod cache;
 pub use cache::*;
+
+pub mod dsp;
+pub use dsp::*;
 use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Into, From, Display, Serialize, Deserialize)]
@@ -33,6 +40,46 @@
 #[display("{_0}")]
 pub struct QueueName(String);
 
+impl QueueName {
+    /// Queues starting with this belong to the announcement class.
+    pub const ANNOUNCEMENT_PREFIX: &'static str = "announce-";
+
+    /// A fresh queue in the announcement class.
+    pub fn announcement() -> Self {
+        Self(format!(
+            "{}{}",
+            Self::ANNOUNCEMENT_PREFIX,
+            uuid::Uuid::new_v4()
+        ))
+    }
+
+    pub fn priority(&self) -> QueuePriority {
+        if self.0.starts_with(Self::ANNOUNCEMENT_PREFIX) {
+            QueuePriority::Announcement
+        } else if self.0.starts_with("tts_") || self.0.starts_with("temp-") {
+            QueuePriority::Tts
+        } else {
+            QueuePriority::Music
+        }
+    }
+}
+
+/// Priority class of a queue, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QueuePriority {
+    Music,
+    Tts,
+    Announcement,
+}
+
+impl QueuePriority {
+    /// Whether a playing track of this class holds tracks of `other` until it
+    /// ends. Speech over music is left to ducking instead.
+    pub fn interrupts(self, other: QueuePriority) -> bool {
+        self == QueuePriority::Announcement && other < self
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Into, From, Display, Serialize, Deserialize)]
 #[display("{_0}")]
 pub struct TrackId(u64);
@@ -64,10 +111,36 @@
 #[display("{_0}")]
 pub struct Volume(f32);
 
+/// Stereo position, from -1.0 (left) through 0.0 (centre) to 1.0 (right).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Into, From, Display, Serialize, Deserialize)]
+#[display("{_0}")]
+pub struct Pan(f32);
+
+impl Pan {
+    pub const CENTER: Self = Self(0.0);
+
+    pub fn is_center(&self) -> bool {
+        *self == Self::CENTER
+    }
+
+    /// Clamp to the supported range. A non-finite value is centred.
+    pub fn clamped(self) -> Self {
+        if self.0.is_finite() {
+            Self(self.0.clamp(-1.0, 1.0))
+        } else {
+            Self::CENTER
+        }
+    }
+}
+
 pub struct AudioResponse {
     pub cache_key: Option<AudioCachePolicy>,
     pub metadatas: Vec<AudioMetadata>,
     pub stream: tokio::sync::mpsc::Receiver<Vec<f32>>,
+    /// Integrated loudness (LUFS) if it was measured on an earlier play.
+    pub loudness_lufs: Option<f32>,
+    /// Full length of the audio, when the tap or the cache knows it.
+    pub duration_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +167,9 @@ pub struct CachedAudioRequest {
     pub discord_user_id: hq::DiscordUserId,
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Start streaming this far into the audio. Rounded down to a 20 ms frame.
+    #[serde(default)]
+    pub start_offset_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +180,17 @@ pub struct Track {
     pub volume: Volume,
     pub queue_name: QueueName,
     pub paused: bool,
+    #[serde(default)]
+    pub dsp: TrackDsp,
+    /// Set explicitly, centre included. `None` leaves a TTS track to auto-pan.
+    #[serde(default)]
+    pub pan: Option<Pan>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Elapsed position. Persisted as the offset playback last started from
+    /// and filled in with the live position when the session state is read.
+    #[serde(default)]
+    pub position_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,6 +198,10 @@ pub struct AudioMetaResponse {
     pub metadatas: Vec<AudioMetadata>,
     pub cache_key: AudioCachePolicy,
     pub base_volume: f32,
+    #[serde(default)]
+    pub loudness_lufs: Option<f32>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
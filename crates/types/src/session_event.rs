@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ChannelId, GuildId, TapHubError, TrackId};
+
+/// Something that happened to a track in a voice session. Published by the
+/// audio engine as it happens and relayed to HQ by traffic-light.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEvent {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub track_id: TrackId,
+    pub kind: TrackEventKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrackEventKind {
+    Queued,
+    Started,
+    /// Played to the end.
+    Ended,
+    Skipped,
+    /// Removed by a stop command before it ended.
+    Stopped,
+    Paused,
+    Resumed,
+    /// Could not be started and was dropped from the queue.
+    Failed {
+        error: TapHubError,
+    },
+}
+
+impl TrackEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackEventKind::Queued => "queued",
+            TrackEventKind::Started => "started",
+            TrackEventKind::Ended => "ended",
+            TrackEventKind::Skipped => "skipped",
+            TrackEventKind::Stopped => "stopped",
+            TrackEventKind::Paused => "paused",
+            TrackEventKind::Resumed => "resumed",
+            TrackEventKind::Failed { .. } => "failed",
+        }
+    }
+}
@@ -1,15 +1,370 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::{ChannelId, GuildId, QueueName, Track, TrackId};
+use crate::{ChannelId, GuildId, Pan, QueueName, Track, TrackId};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionState {
     pub guild_id: GuildId,
     pub channel_id: ChannelId,
     pub queues: HashMap<QueueName, Vec<Track>>,
+    #[serde(default)]
+    pub settings: SessionAudioSettings,
+    #[serde(default)]
+    pub queue_mode: QueueMode,
 }
+
+/// How music queues advance when a track ends or is skipped.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    zod_gen_derive::ZodSchema,
+)]
+pub struct QueueMode {
+    pub repeat: RepeatMode,
+    /// Play the rest of the queue in random order. Newly queued tracks go
+    /// to a random place after the next one.
+    pub shuffle: bool,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    zod_gen_derive::ZodSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    /// Replay the current track until it is skipped.
+    One,
+    /// Send finished and skipped tracks back to the end of the queue.
+    Queue,
+}
+
+/// Guild-scope audio behaviour the engine applies to a session. Pushed by HQ
+/// on join and whenever the guild settings change.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionAudioSettings {
+    #[serde(default)]
+    pub ducking: DuckingSettings,
+    #[serde(default)]
+    pub loudness: LoudnessSettings,
+    #[serde(default)]
+    pub transitions: TransitionSettings,
+    #[serde(default)]
+    pub fade_out: FadeOutSettings,
+    #[serde(default)]
+    pub recording: RecordingSettings,
+    #[serde(default)]
+    pub encoder: EncoderSettings,
+    #[serde(default)]
+    pub tts_pan: TtsPanSettings,
+}
+
+/// Attenuate music while TTS or announcements are speaking.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct DuckingSettings {
+    pub enabled: bool,
+    /// How far music is pulled down, in dB.
+    pub depth_db: f32,
+    /// Time to reach full attenuation once speech starts.
+    pub attack_ms: u32,
+    /// Time to recover once speech stops.
+    pub release_ms: u32,
+}
+
+impl Default for DuckingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            depth_db: 12.0,
+            attack_ms: 80,
+            release_ms: 400,
+        }
+    }
+}
+
+/// Per-track loudness normalization (EBU R128).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct LoudnessSettings {
+    pub enabled: bool,
+    /// Integrated loudness every track is brought to, in LUFS.
+    pub target_lufs: f32,
+}
+
+impl Default for LoudnessSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_lufs: -16.0,
+        }
+    }
+}
+
+/// How consecutive music tracks hand over to each other.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    zod_gen_derive::ZodSchema,
+)]
+pub struct TransitionSettings {
+    /// Start the next track while the current one is still playing so there
+    /// is no TapHub round trip between them.
+    pub gapless: bool,
+    /// Overlap between tracks with complementary fades, in ms. 0 disables.
+    pub crossfade_ms: u32,
+}
+
+impl TransitionSettings {
+    pub const MAX_CROSSFADE_MS: u32 = 12_000;
+
+    /// Whether the next track should be started ahead of time.
+    pub fn enabled(&self) -> bool {
+        self.gapless || self.crossfade_ms > 0
+    }
+
+    pub fn crossfade_ms(&self) -> u32 {
+        self.crossfade_ms.min(Self::MAX_CROSSFADE_MS)
+    }
+}
+
+/// Ramp tracks down when they are stopped, skipped or the bot leaves,
+/// instead of cutting them mid-sample.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct FadeOutSettings {
+    /// Fade length in ms. 0 cuts immediately.
+    pub duration_ms: u32,
+}
+
+impl Default for FadeOutSettings {
+    fn default() -> Self {
+        Self { duration_ms: 80 }
+    }
+}
+
+impl FadeOutSettings {
+    pub const MAX_DURATION_MS: u32 = 2_000;
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms.min(Self::MAX_DURATION_MS) as u64)
+    }
+}
+
+/// Spread simultaneous TTS voices across the stereo field so listeners can
+/// tell speakers apart. Each `tts_<user>` queue is given the next free
+/// position in turn and keeps it while it has tracks playing. A track panned
+/// explicitly keeps its pan, even at the centre.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct TtsPanSettings {
+    pub enabled: bool,
+    /// How far from centre the outermost voices sit, from 0 to 1.
+    pub width: f32,
+}
+
+impl Default for TtsPanSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 0.6,
+        }
+    }
+}
+
+impl TtsPanSettings {
+    /// Positions handed out across `[-width, width]`.
+    pub const POSITIONS: u64 = 5;
+
+    /// Where the voices in `slot`, out of [`Self::POSITIONS`], sit.
+    pub fn position(&self, slot: u64) -> Pan {
+        let slot = slot % Self::POSITIONS;
+        let spread = slot as f32 / (Self::POSITIONS - 1) as f32 * 2.0 - 1.0;
+        let width = if self.width.is_finite() {
+            self.width.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Pan::from(spread * width)
+    }
+}
+
+/// Record what the bot plays in a session. A rolling window of at most
+/// [`Self::MAX_RETENTION_SECS`] is always kept for clips; in
+/// [`RecordingMode::Full`] the whole session is also written to an Ogg Opus
+/// file on the engine host until recording is turned off or the session
+/// ends. Only audio the bot actually played is kept; silence between tracks
+/// is not.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct RecordingSettings {
+    pub enabled: bool,
+    /// How much audio is kept for clips, in seconds, capped at
+    /// [`Self::MAX_RETENTION_SECS`]. Older audio is dropped.
+    pub retention_secs: u32,
+    #[serde(default)]
+    pub mode: RecordingMode,
+    /// Size a full recording may grow to, in MiB, capped at
+    /// [`Self::MAX_FULL_SIZE_MIB`]. Writing stops once it is reached.
+    #[serde(default = "RecordingSettings::default_full_size_mib")]
+    pub max_full_size_mib: u32,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_secs: 120,
+            mode: RecordingMode::default(),
+            max_full_size_mib: Self::default_full_size_mib(),
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub const MAX_RETENTION_SECS: u32 = 300;
+    pub const MAX_FULL_SIZE_MIB: u32 = 1024;
+
+    fn default_full_size_mib() -> u32 {
+        256
+    }
+
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_secs.clamp(1, Self::MAX_RETENTION_SECS) as u64)
+    }
+
+    pub fn max_full_size_bytes(&self) -> u64 {
+        self.max_full_size_mib.clamp(1, Self::MAX_FULL_SIZE_MIB) as u64 * 1024 * 1024
+    }
+}
+
+/// Whether a recording is only the clip window or the whole session.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    zod_gen_derive::ZodSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingMode {
+    /// Keep only the rolling window clips are cut from.
+    #[default]
+    Clip,
+    /// Also write the whole session to a file.
+    Full,
+}
+
+/// Opus encoder parameters for the session's output. Applied live; changing
+/// `signal` between voice and non-voice restarts the encoder.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, zod_gen_derive::ZodSchema,
+)]
+pub struct EncoderSettings {
+    /// Target bitrate in kbps. 0 lets the encoder choose.
+    pub bitrate_kbps: u32,
+    /// Effort spent per frame, from 0 (cheapest) to 10 (best quality).
+    pub complexity: u32,
+    /// Send in-band forward error correction so listeners can recover a
+    /// lost packet from the one after it.
+    pub fec: bool,
+    /// Packet loss the encoder should plan for, in percent.
+    pub expected_packet_loss_pct: u32,
+    pub signal: OpusSignal,
+}
+
+impl Default for EncoderSettings {
+    fn default() -> Self {
+        Self {
+            bitrate_kbps: 0,
+            complexity: Self::MAX_COMPLEXITY,
+            fec: false,
+            expected_packet_loss_pct: 0,
+            signal: OpusSignal::Auto,
+        }
+    }
+}
+
+impl EncoderSettings {
+    pub const MIN_BITRATE_KBPS: u32 = 6;
+    pub const MAX_BITRATE_KBPS: u32 = 510;
+    pub const MAX_COMPLEXITY: u32 = 10;
+
+    /// Target bitrate in bits per second, or `None` for the encoder default.
+    pub fn bitrate_bps(&self) -> Option<u32> {
+        (self.bitrate_kbps > 0).then(|| {
+            self.bitrate_kbps
+                .clamp(Self::MIN_BITRATE_KBPS, Self::MAX_BITRATE_KBPS)
+                * 1000
+        })
+    }
+
+    pub fn complexity(&self) -> u8 {
+        self.complexity.min(Self::MAX_COMPLEXITY) as u8
+    }
+
+    pub fn expected_packet_loss_pct(&self) -> u8 {
+        self.expected_packet_loss_pct.min(100) as u8
+    }
+}
+
+/// What the Opus encoder tunes for. `Voice` suits TTS-only guilds and uses
+/// the VoIP application mode; the others use the general audio mode.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    zod_gen_derive::ZodSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum OpusSignal {
+    #[default]
+    Auto,
+    Voice,
+    Music,
+}
+
 impl SessionState {
     pub fn find_track_mut(&mut self, track_id: TrackId) -> Option<&mut Track> {
         for queue in self.queues.values_mut() {
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{TapName, hq::TapId};
+use crate::{TapCapabilities, TapName, hq::TapId};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OnlineTapState {
@@ -11,6 +11,15 @@ pub struct OnlineTapState {
     pub friendly_name: String,
     pub selection_weight: f32,
     pub connected_at: DateTime<Utc>,
+    /// zakofish protocol version agreed on in the tap's hello.
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: TapCapabilities,
+}
+
+fn legacy_protocol_version() -> u32 {
+    zakofish::types::LEGACY_PROTOCOL_VERSION
 }
 
 pub type OnlineTapStates = Vec<OnlineTapState>;
@@ -89,7 +89,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let headers = HashMap::new();
 
         // 1. Send the audio request
-        match hub.request_audio(tap_id, connection_id, ars, headers).await {
+        let request_id = hub.next_request_id();
+        match hub
+            .request_audio(tap_id, connection_id, request_id, ars, headers)
+            .await
+        {
             Ok((success_msg, recv_stream, _)) => {
                 println!(
                     "Hub: Received success response! Duration: {:?}s",
@@ -14,7 +14,7 @@
 
 use zakofish::types::message::{
     AudioMetadataRequestMessage, AudioMetadataSuccessMessage, AudioRequestMessage,
-    AudioRequestSuccessMessage, TapClientHello, TapServerReject,
+    AudioRequestSuccessMessage, CancelMessage, TapClientHello, TapServerReject,
 };
 
 #[async_trait::async_trait]
@@ -27,10 +27,12 @@ async fn on_tap_authenticate(
     async fn on_tap_disconnected(&self, tap_id: TapId, connection_id: u64);
 }
 
+#[derive(Clone)]
 pub struct ZakofishHub {
     server: Arc<protofish3::Server>,
     handler: Arc<dyn HubHandler>,
     next_connection_id: Arc<AtomicU64>,
+    next_request_id: Arc<AtomicU64>,
     sessions: SessionMap,
 }
 
@@ -45,6 +47,7 @@ pub fn new(
             server,
             handler,
             next_connection_id: Arc::new(AtomicU64::new(1)),
+            next_request_id: Arc::new(AtomicU64::new(1)),
             sessions: Arc::new(Mutex::new(HashMap::new())),
         })
     }
@@ -63,10 +66,16 @@ pub async fn run(&self) -> Result<()> {
         .await
     }
 
+    /// A fresh id for [`Self::request_audio`], unique for this hub.
+    pub fn next_request_id(&self) -> u64 {
+        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
     pub async fn request_audio(
         &self,
         tap_id: TapId,
         connection_id: u64,
+        request_id: u64,
         ars: AudioRequestString,
         headers: HashMap<String, String>,
     ) -> Result<(
@@ -80,6 +89,7 @@ pub async fn request_audio(
         let conn = self.get_session(&wire_tap_id, connection_id, &tap_id).await?;
 
         let request = AudioRequestMessage {
+            request_id,
             ars: wire_ars,
             headers,
         };
@@ -122,6 +132,26 @@ pub async fn request_audio(
         }
     }
 
+    /// Tell the tap to stop streaming the audio request `request_id`. The tap
+    /// does not answer; its stream just ends.
+    pub async fn cancel_audio_request(
+        &self,
+        tap_id: TapId,
+        connection_id: u64,
+        request_id: u64,
+    ) -> Result<()> {
+        let wire_tap_id = zakofish::types::TapId(tap_id.0.clone());
+        let conn = self.get_session(&wire_tap_id, connection_id, &tap_id).await?;
+
+        let payload =
+            zakofish::types::message::HubToTapMessage::Cancel(CancelMessage { request_id });
+        let encoded = zakofish::protocol::codec::encode_msgpack(&payload)?;
+
+        let (sender, _receiver) = conn.open_chan().await?;
+        sender.send_msg(encoded.to_vec()).await?;
+        Ok(())
+    }
+
     pub async fn request_audio_metadata(
         &self,
         tap_id: TapId,
@@ -219,6 +249,7 @@ async fn run_loop(
                 tap_id = tracing::field::Empty,
                 connection_id = tracing::field::Empty,
                 friendly_name = tracing::field::Empty,
+                protocol_version = tracing::field::Empty,
                 disconnect_reason = tracing::field::Empty,
                 remote_ip = %ip,
             );
@@ -268,6 +299,7 @@ async fn handle_connection(
     tracing::Span::current().record("connection_id", connection_id);
     tracing::Span::current()
         .record("friendly_name", tracing::field::display(&hello.friendly_name));
+    tracing::Span::current().record("protocol_version", hello.negotiated_protocol_version());
 
     match handler.on_tap_authenticate(connection_id, hello).await {
         Ok(_) => {
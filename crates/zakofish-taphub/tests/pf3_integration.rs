@@ -12,11 +12,12 @@
 use tokio::sync::mpsc;
 use zako3_types::AudioRequestString;
 use zako3_types::hq::TapId;
-use zakofish::{Timestamp, TransferMode, ZakofishTapPf3};
+use zakofish::{CancellationToken, Timestamp, TransferMode, ZakofishTapPf3};
 use zakofish::tap::TapHandler;
 use zakofish::types::message::{
     AttachedMetadata, AudioMetadataSuccessMessage, AudioRequestFailureMessage,
-    AudioRequestSuccessMessage, TapClientHello, TapServerReject,
+    AudioRequestSuccessMessage, PROTOCOL_VERSION, TapCapabilities, TapClientHello,
+    TapServerReject,
 };
 use zakofish::types::model::{AudioCachePolicy, AudioCacheType};
 use zakofish_taphub::hub::{HubHandler, ZakofishHub};
@@ -33,7 +34,7 @@ fn gen_cert() -> (
 }
 
 struct TestHubHandler {
-    tap_connected: mpsc::Sender<u64>,
+    tap_connected: mpsc::Sender<(u64, TapClientHello)>,
 }
 
 #[async_trait::async_trait]
@@ -41,9 +42,9 @@ impl HubHandler for TestHubHandler {
     async fn on_tap_authenticate(
         &self,
         connection_id: u64,
-        _hello: TapClientHello,
+        hello: TapClientHello,
     ) -> Result<(), TapServerReject> {
-        let _ = self.tap_connected.send(connection_id).await;
+        let _ = self.tap_connected.send((connection_id, hello)).await;
         Ok(())
     }
     async fn on_tap_disconnected(&self, _tap_id: TapId, _connection_id: u64) {}
@@ -100,8 +101,86 @@ async fn handle_audio_metadata_request(
     }
 }
 
-#[tokio::test]
-async fn test_zakofish_flow_pf3() {
+/// Streams until the hub cancels, then reports the cancellation.
+struct EndlessTapHandler {
+    cancelled: mpsc::Sender<()>,
+}
+
+#[async_trait::async_trait]
+impl TapHandler for EndlessTapHandler {
+    async fn handle_audio_request(
+        &self,
+        ars: zakofish::types::model::AudioRequestString,
+        headers: HashMap<String, String>,
+    ) -> Result<
+        (
+            AudioRequestSuccessMessage,
+            mpsc::Receiver<(Timestamp, Bytes)>,
+            TransferMode,
+        ),
+        AudioRequestFailureMessage,
+    > {
+        self.handle_audio_request_cancellable(ars, headers, CancellationToken::new())
+            .await
+    }
+
+    async fn handle_audio_request_cancellable(
+        &self,
+        _ars: zakofish::types::model::AudioRequestString,
+        _headers: HashMap<String, String>,
+        cancel: CancellationToken,
+    ) -> Result<
+        (
+            AudioRequestSuccessMessage,
+            mpsc::Receiver<(Timestamp, Bytes)>,
+            TransferMode,
+        ),
+        AudioRequestFailureMessage,
+    > {
+        let success_msg = AudioRequestSuccessMessage {
+            cache: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            duration_secs: None,
+            metadatas: AttachedMetadata::Metadatas(vec![]),
+        };
+
+        let (tx, rx) = mpsc::channel::<(Timestamp, Bytes)>(10);
+        let cancelled = self.cancelled.clone();
+        tokio::spawn(async move {
+            for i in 0u64.. {
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        let _ = cancelled.send(()).await;
+                        return;
+                    }
+                    _ = tokio::time::sleep(Duration::from_millis(10)) => {}
+                }
+                let chunk = Bytes::from_static(b"chunk");
+                if tx.send((Timestamp(i * 20), chunk)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok((success_msg, rx, TransferMode::Dual))
+    }
+
+    async fn handle_audio_metadata_request(
+        &self,
+        _ars: zakofish::types::model::AudioRequestString,
+        _headers: HashMap<String, String>,
+    ) -> Result<AudioMetadataSuccessMessage, AudioRequestFailureMessage> {
+        Err(AudioRequestFailureMessage {
+            reason: "Not found".to_string(),
+            try_others: true,
+        })
+    }
+}
+
+/// Start a hub and connect a tap served by `tap_handler` to it.
+async fn connect_tap(tap_handler: Arc<dyn TapHandler>) -> (Arc<ZakofishHub>, TapId, u64) {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::INFO)
         .try_init()
@@ -136,7 +215,6 @@ async fn test_zakofish_flow_pf3() {
     client_config.protofish = zakofish::default_protofish3_config();
 
     let tap = Arc::new(ZakofishTapPf3::new(client_config).unwrap());
-    let tap_handler = Arc::new(TestTapHandler);
 
     let tap_id_wire = zakofish::types::TapId("343456".to_string());
     let tap_id = TapId("343456".to_string());
@@ -146,6 +224,12 @@ async fn test_zakofish_flow_pf3() {
         friendly_name: "Test Pf3 Tap".to_string(),
         api_token: "secret".to_string(),
         selection_weight: 1.0,
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: TapCapabilities {
+            max_concurrent_requests: Some(4),
+            supports_cancel: true,
+            ..Default::default()
+        },
     };
 
     let tap_clone = tap.clone();
@@ -155,16 +239,32 @@ async fn test_zakofish_flow_pf3() {
             .await;
     });
 
-    let connection_id = tap_connected_rx
+    let (connection_id, hello) = tap_connected_rx
         .recv()
         .await
         .expect("Failed to get connection_id");
+    assert_eq!(hello.negotiated_protocol_version(), PROTOCOL_VERSION);
+    assert_eq!(hello.capabilities.max_concurrent_requests, Some(4));
+    assert!(hello.capabilities.supports_cancel);
 
     tokio::time::sleep(Duration::from_millis(100)).await;
 
+    (hub, tap_id, connection_id)
+}
+
+#[tokio::test]
+async fn test_zakofish_flow_pf3() {
+    let (hub, tap_id, connection_id) = connect_tap(Arc::new(TestTapHandler)).await;
+
     let ars = AudioRequestString::from("test:audio".to_string());
     let (success_msg, rel, mut unrel) = hub
-        .request_audio(tap_id, connection_id, ars, HashMap::new())
+        .request_audio(
+            tap_id,
+            connection_id,
+            hub.next_request_id(),
+            ars,
+            HashMap::new(),
+        )
         .await
         .expect("Failed to request audio");
 
@@ -206,3 +306,43 @@ async fn test_zakofish_flow_pf3() {
         assert_eq!(*chunk, Bytes::from(format!("chunk {}", i)));
     }
 }
+
+#[tokio::test]
+async fn test_cancel_stops_tap_stream_pf3() {
+    let (cancelled_tx, mut cancelled_rx) = mpsc::channel(1);
+    let (hub, tap_id, connection_id) = connect_tap(Arc::new(EndlessTapHandler {
+        cancelled: cancelled_tx,
+    }))
+    .await;
+
+    let request_id = hub.next_request_id();
+    let ars = AudioRequestString::from("test:endless".to_string());
+    let (_, _rel, mut unrel) = hub
+        .request_audio(
+            tap_id.clone(),
+            connection_id,
+            request_id,
+            ars,
+            HashMap::new(),
+        )
+        .await
+        .expect("Failed to request audio");
+
+    for _ in 0..3 {
+        unrel.recv().await.expect("stream should be running");
+    }
+
+    hub.cancel_audio_request(tap_id, connection_id, request_id)
+        .await
+        .expect("Failed to cancel");
+
+    tokio::time::timeout(Duration::from_secs(5), cancelled_rx.recv())
+        .await
+        .expect("tap handler was not cancelled")
+        .expect("tap handler went away");
+    let drained = tokio::time::timeout(Duration::from_secs(5), async {
+        while unrel.recv().await.is_some() {}
+    })
+    .await;
+    assert!(drained.is_ok(), "stream kept running after cancel");
+}
@@ -7,7 +7,7 @@
 use tokio::sync::mpsc;
 use zakofish::config::load_certs;
 use zakofish::tap_pf3::ZakofishTapPf3;
-use zakofish::types::message::TapClientHello;
+use zakofish::types::message::{PROTOCOL_VERSION, TapCapabilities, TapClientHello};
 use zakofish::types::model::TapId;
 
 use crate::error::SdkError;
@@ -37,6 +37,7 @@ pub struct TapBuilder {
     friendly_name: Option<String>,
     api_token: Option<String>,
     selection_weight: f32,
+    capabilities: TapCapabilities,
     transport: Transport,
     #[cfg(feature = "healthcheck")]
     healthcheck_port: Option<u16>,
@@ -81,6 +82,21 @@ pub fn selection_weight(mut self, weight: f32) -> Self {
         self
     }
 
+    /// Most audio and metadata requests the Hub sends this connection at
+    /// once. Unlimited by default.
+    pub fn max_concurrent_requests(mut self, max: u32) -> Self {
+        self.capabilities.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Whether `handle_audio_metadata_request` answers on its own. Set to
+    /// `false` to have the Hub send metadata requests to other connections.
+    /// Defaults to `true`.
+    pub fn supports_metadata_only(mut self, supported: bool) -> Self {
+        self.capabilities.supports_metadata_only = supported;
+        self
+    }
+
     /// Select the wire transport. Defaults to [`Transport::Pf2`].
     pub fn transport(mut self, transport: Transport) -> Self {
         self.transport = transport;
@@ -147,6 +163,13 @@ pub async fn run(self, handler: Arc<dyn TapHandler>) -> Result<(), SdkError> {
             friendly_name: self.friendly_name.unwrap_or_default(),
             api_token: self.api_token.unwrap_or_default(),
             selection_weight: self.selection_weight,
+            protocol_version: PROTOCOL_VERSION,
+            // zakofish stops the stream itself when the Hub cancels, whether
+            // or not the handler watches its token.
+            capabilities: TapCapabilities {
+                supports_cancel: true,
+                ..self.capabilities
+            },
         };
 
         #[cfg(feature = "healthcheck")]
@@ -190,9 +213,26 @@ async fn handle_audio_metadata_request(
     }
 
     async fn handle_audio_request(
+        &self,
+        ars: zakofish::types::model::AudioRequestString,
+        headers: HashMap<String, String>,
+    ) -> std::result::Result<
+        (
+            zakofish::types::message::AudioRequestSuccessMessage,
+            mpsc::Receiver<(zakofish::Timestamp, bytes::Bytes)>,
+            zakofish::TransferMode,
+        ),
+        zakofish::types::message::AudioRequestFailureMessage,
+    > {
+        self.handle_audio_request_cancellable(ars, headers, zakofish::CancellationToken::new())
+            .await
+    }
+
+    async fn handle_audio_request_cancellable(
         &self,
         ars: zakofish::types::model::AudioRequestString,
         _headers: HashMap<String, String>,
+        cancel: zakofish::CancellationToken,
     ) -> std::result::Result<
         (
             zakofish::types::message::AudioRequestSuccessMessage,
@@ -210,7 +250,7 @@ async fn handle_audio_request(
         let source = AudioSource::from(ars);
 
         self.0
-            .handle_audio_request(source, sender)
+            .handle_audio_request_cancellable(source, sender, cancel)
             .await
             .map(|success| {
                 let mode = transfer_mode
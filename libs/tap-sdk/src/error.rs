@@ -3,7 +3,8 @@
 
 #[derive(Debug, thiserror::Error)]
 pub enum TapError {
-    /// Transient failure. The Hub will try another tap for this request.
+    /// Transient failure. The Hub retries the request on another connection
+    /// of the same tap, if one is online.
     /// Use for: network errors, rate limits, timeouts, yt-dlp crashes.
     #[error("{0}")]
     Retriable(String),
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use zakofish::CancellationToken;
 use zakofish::types::message::{AudioMetadataSuccessMessage, AudioRequestSuccessMessage};
 
 use crate::error::TapError;
@@ -31,4 +32,21 @@ async fn handle_audio_request(
         source: AudioSource,
         stream: AudioStreamSender,
     ) -> Result<AudioRequestSuccessMessage, TapError>;
+
+    /// Like [`Self::handle_audio_request`], and what the SDK actually calls.
+    /// `cancel` fires when the Hub no longer wants the stream, e.g. the user
+    /// skipped the track. Stop decoding or downloading when it does; frames
+    /// sent afterwards are discarded.
+    ///
+    /// Defaults to `handle_audio_request`, which streams until `stream`
+    /// reports the Hub gone. Override it to stop early.
+    async fn handle_audio_request_cancellable(
+        &self,
+        source: AudioSource,
+        stream: AudioStreamSender,
+        cancel: CancellationToken,
+    ) -> Result<AudioRequestSuccessMessage, TapError> {
+        let _ = cancel;
+        self.handle_audio_request(source, stream).await
+    }
 }
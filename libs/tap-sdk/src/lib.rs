@@ -14,7 +14,7 @@
 pub use error::{SdkError, TapError};
 pub use handler::TapHandler;
 pub use source::AudioSource;
-pub use zakofish::{Timestamp, TransferMode};
+pub use zakofish::{CancellationToken, Timestamp, TransferMode};
 pub use stream::AudioStreamSender;
 
 // Re-export message types for SDK users
@@ -9,7 +9,7 @@
 
 use zakofish::types::message::{
     AttachedMetadata, AudioMetadataSuccessMessage, AudioRequestFailureMessage,
-    AudioRequestSuccessMessage, TapClientHello,
+    AudioRequestSuccessMessage, PROTOCOL_VERSION, TapCapabilities, TapClientHello,
 };
 use zakofish::types::model::{AudioCachePolicy, AudioCacheType, AudioRequestString, TapId};
 use zakofish::{TapHandler, Timestamp, TransferMode, ZakofishTapPf3, default_protofish3_config};
@@ -97,6 +97,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         friendly_name: "Simple Tap Example".to_string(),
         api_token: "secret_token".to_string(),
         selection_weight: 1.0,
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: TapCapabilities {
+            supports_cancel: true,
+            ..Default::default()
+        },
     };
 
     println!("Tap: Connecting to Hub...");
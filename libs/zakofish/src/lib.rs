@@ -11,4 +11,5 @@
 pub use tap::TapHandler;
 pub use tap_pf3::ZakofishTapPf3;
 pub use tap_streams::{RelChunkStream, UnrelChunkStream, encode_pf3_chunk};
+pub use tokio_util::sync::CancellationToken;
 pub use types::{Timestamp, TransferMode};
@@ -1,6 +1,7 @@
 use bytes::Bytes;
 use std::collections::HashMap;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use crate::types::message::{
     AudioMetadataSuccessMessage, AudioRequestFailureMessage, AudioRequestSuccessMessage,
@@ -29,6 +30,29 @@ async fn handle_audio_request(
         AudioRequestFailureMessage,
     >;
 
+    /// What the tap actually calls for an audio request. `cancel` fires when
+    /// the hub no longer wants the stream; the handler should stop producing
+    /// chunks. Anything still sent is discarded.
+    ///
+    /// Defaults to [`Self::handle_audio_request`], which keeps producing
+    /// until the receiver is dropped. Override it to stop early.
+    async fn handle_audio_request_cancellable(
+        &self,
+        ars: AudioRequestString,
+        headers: HashMap<String, String>,
+        cancel: CancellationToken,
+    ) -> std::result::Result<
+        (
+            AudioRequestSuccessMessage,
+            mpsc::Receiver<(Timestamp, Bytes)>,
+            TransferMode,
+        ),
+        AudioRequestFailureMessage,
+    > {
+        let _ = cancel;
+        self.handle_audio_request(ars, headers).await
+    }
+
     /// Handle an incoming audio metadata request.
     /// If successful, returns the success message with metadata.
     /// If failed, returns the failure message.
@@ -5,8 +5,9 @@
     ChanReceiver, ChanSender, Client, ClientConfig, ReconnectConfig, ReconnectingClient, XferMode,
 };
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 use crate::error::{Result, ZakofishError};
 use crate::tap::TapHandler;
@@ -14,6 +15,9 @@
 use crate::types::TransferMode;
 use crate::types::message::{HubToTapMessage, TapClientHello, TapToHubMessage};
 
+/// Cancellation tokens of the audio requests being served, by request id.
+type InFlightRequests = Arc<Mutex<HashMap<u64, CancellationToken>>>;
+
 pub struct ZakofishTapPf3 {
     client: Arc<Client>,
 }
@@ -50,6 +54,7 @@ pub async fn connect_and_run(
         .await?;
 
         let mut reconnect_rx = conn.subscribe_reconnect();
+        let in_flight = InFlightRequests::default();
 
         do_handshake(&conn, &hello_info).await?;
 
@@ -66,8 +71,9 @@ pub async fn connect_and_run(
                     match chan_result {
                         Ok((sender, receiver)) => {
                             let handler_clone = handler.clone();
+                            let in_flight = in_flight.clone();
                             tokio::spawn(async move {
-                                if let Err(e) = handle_incoming_chan(sender, receiver, handler_clone).await {
+                                if let Err(e) = handle_incoming_chan(sender, receiver, handler_clone, in_flight).await {
                                     tracing::error!("Error handling incoming chan: {:?}", e);
                                 }
                             });
@@ -114,14 +120,17 @@ async fn handle_incoming_chan(
     sender: ChanSender,
     mut receiver: ChanReceiver,
     handler: Arc<dyn TapHandler>,
+    in_flight: InFlightRequests,
 ) -> Result<()> {
     let payload_bytes = receiver.recv_msg().await?;
     let msg: HubToTapMessage = crate::protocol::codec::decode_msgpack(&payload_bytes)?;
 
     match msg {
         HubToTapMessage::AudioRequest(request) => {
+            let cancel = CancellationToken::new();
+            let _registered = Registered::new(&in_flight, request.request_id, cancel.clone());
             match handler
-                .handle_audio_request(request.ars, request.headers)
+                .handle_audio_request_cancellable(request.ars, request.headers, cancel.clone())
                 .await
             {
                 Ok((success_msg, mut chunk_receiver, transfer_mode)) => {
@@ -132,7 +141,23 @@ async fn handle_incoming_chan(
 
                     let mut send_xfer = sender.start_xfer(map_mode(transfer_mode)).await?;
 
-                    while let Some((timestamp, bytes)) = chunk_receiver.recv().await {
+                    loop {
+                        let chunk = tokio::select! {
+                            biased;
+                            _ = cancel.cancelled() => {
+                                // Dropped without `end`, so the hub does not
+                                // take what it got for the complete stream.
+                                tracing::debug!(
+                                    request_id = request.request_id,
+                                    "Audio request cancelled by Hub"
+                                );
+                                return Ok(());
+                            }
+                            chunk = chunk_receiver.recv() => chunk,
+                        };
+                        let Some((timestamp, bytes)) = chunk else {
+                            break;
+                        };
                         tracing::trace!(
                             "Sending pf3 chunk timestamp={} size={}",
                             timestamp.0,
@@ -171,6 +196,16 @@ async fn handle_incoming_chan(
                 }
             }
         }
+        HubToTapMessage::Cancel(cancel) => {
+            let token = in_flight.lock().unwrap().get(&cancel.request_id).cloned();
+            match token {
+                Some(token) => token.cancel(),
+                None => tracing::debug!(
+                    request_id = cancel.request_id,
+                    "Cancel for an audio request that already ended"
+                ),
+            }
+        }
         _ => {
             tracing::warn!("Received unexpected message on pf3 data chan: {:?}", msg);
         }
@@ -179,6 +214,29 @@ async fn handle_incoming_chan(
     Ok(())
 }
 
+/// Keeps a request's cancellation token reachable by its id while it is
+/// being served.
+struct Registered<'a> {
+    in_flight: &'a InFlightRequests,
+    request_id: u64,
+}
+
+impl<'a> Registered<'a> {
+    fn new(in_flight: &'a InFlightRequests, request_id: u64, cancel: CancellationToken) -> Self {
+        in_flight.lock().unwrap().insert(request_id, cancel);
+        Self {
+            in_flight,
+            request_id,
+        }
+    }
+}
+
+impl Drop for Registered<'_> {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(&self.request_id);
+    }
+}
+
 fn map_mode(mode: TransferMode) -> XferMode {
     match mode {
         TransferMode::Dual => XferMode::Dual,
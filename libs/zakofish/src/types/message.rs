@@ -4,6 +4,19 @@
     AudioCachePolicy, AudioMetadata, AudioRequestString, HubRejectReasonType, TapId,
 };
 
+/// Revision of the zakofish protocol this crate speaks, sent in
+/// [`TapClientHello`]. Bumped whenever a message gains meaning an older peer
+/// would not understand.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// What a hello without a version is taken to speak: the protocol as it was
+/// before taps announced one, without cancellation.
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+fn legacy_protocol_version() -> u32 {
+    LEGACY_PROTOCOL_VERSION
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum AttachedMetadata {
@@ -15,11 +28,20 @@ pub enum AttachedMetadata {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioRequestMessage {
+    /// Chosen by the hub, unique per connection. A later [`CancelMessage`]
+    /// names the request it stops by this id.
+    #[serde(default)]
+    pub request_id: u64,
     pub ars: AudioRequestString,
     #[serde(default)]
     pub headers: std::collections::HashMap<String, String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelMessage {
+    pub request_id: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioMetadataRequestMessage {
     pub ars: AudioRequestString,
@@ -46,12 +68,50 @@ pub struct AudioRequestFailureMessage {
     pub try_others: bool,
 }
 
+/// What a tap connection can serve, announced in its hello.
+///
+/// The default is what a tap from before capability negotiation can do, so a
+/// hello that leaves out some or all fields is read conservatively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TapCapabilities {
+    /// Audio and metadata requests the connection takes at once; `None` for
+    /// no limit.
+    pub max_concurrent_requests: Option<u32>,
+    /// Answers [`HubToTapMessage::AudioMetadataRequest`].
+    pub supports_metadata_only: bool,
+    /// Stops streaming on [`HubToTapMessage::Cancel`].
+    pub supports_cancel: bool,
+}
+
+impl Default for TapCapabilities {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: None,
+            supports_metadata_only: true,
+            supports_cancel: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TapClientHello {
     pub tap_id: TapId,
     pub friendly_name: String,
     pub api_token: String,
     pub selection_weight: f32,
+    /// The tap's [`PROTOCOL_VERSION`].
+    #[serde(default = "legacy_protocol_version")]
+    pub protocol_version: u32,
+    #[serde(default)]
+    pub capabilities: TapCapabilities,
+}
+
+impl TapClientHello {
+    /// The version both ends speak: the older of the tap's and ours.
+    pub fn negotiated_protocol_version(&self) -> u32 {
+        self.protocol_version.min(PROTOCOL_VERSION)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +128,9 @@ pub enum HubToTapMessage {
     Reject(TapServerReject),
     AudioRequest(AudioRequestMessage),
     AudioMetadataRequest(AudioMetadataRequestMessage),
+    /// Nobody is listening to the stream of an earlier audio request any
+    /// more; the tap should stop producing it.
+    Cancel(CancelMessage),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
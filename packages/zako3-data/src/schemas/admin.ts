@@ -19,9 +19,27 @@ export const adminActivitySchema = z.object({
   details: z.string().optional(),
 });
 
+export const announcementSchema = z.object({
+  guildId: z.string(),
+  message: z.string().min(1),
+  tapId: z.string().optional(),
+});
+
+export const announcementFailureSchema = z.object({
+  channelId: z.string(),
+  error: z.string(),
+});
+
+export const announcementResultSchema = z.object({
+  sessions: z.number(),
+  failures: z.array(announcementFailureSchema),
+});
+
 // ============================================================================
 // Type Exports
 // ============================================================================
 
 export type AdminTargetType = z.infer<typeof adminTargetTypeSchema>;
 export type AdminActivity = z.infer<typeof adminActivitySchema>;
+export type Announcement = z.infer<typeof announcementSchema>;
+export type AnnouncementResult = z.infer<typeof announcementResultSchema>;
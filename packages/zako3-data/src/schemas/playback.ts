@@ -14,6 +14,8 @@ export const trackSchema = z.object({
     requestedBy: z.string(),
     volume: z.number(),
     paused: z.boolean(),
+    durationMs: z.number().nullable().optional(),
+    positionMs: z.number().default(0),
 });
 
 export const discordUserInfoSchema = z.object({
@@ -26,6 +28,68 @@ export const queueMetaSchema = z.object({
     user: discordUserInfoSchema.optional(),
 });
 
+export const duckingSettingsSchema = z.object({
+    enabled: z.boolean(),
+    depth_db: z.number(),
+    attack_ms: z.number(),
+    release_ms: z.number(),
+});
+
+export const loudnessSettingsSchema = z.object({
+    enabled: z.boolean(),
+    target_lufs: z.number(),
+});
+
+export const transitionSettingsSchema = z.object({
+    gapless: z.boolean(),
+    crossfade_ms: z.number().min(0).max(12000),
+});
+
+export const fadeOutSettingsSchema = z.object({
+    duration_ms: z.number().min(0).max(2000),
+});
+
+export const recordingModeSchema = z.enum(['clip', 'full']);
+
+export const recordingSettingsSchema = z.object({
+    enabled: z.boolean(),
+    retention_secs: z.number().min(1).max(300),
+    mode: recordingModeSchema.default('clip'),
+    max_full_size_mib: z.number().min(1).max(1024).default(256),
+});
+
+export const opusSignalSchema = z.enum(['auto', 'voice', 'music']);
+
+export const encoderSettingsSchema = z.object({
+    bitrate_kbps: z.number().min(0).max(510),
+    complexity: z.number().min(0).max(10),
+    fec: z.boolean(),
+    expected_packet_loss_pct: z.number().min(0).max(100),
+    signal: opusSignalSchema,
+});
+
+export const ttsPanSettingsSchema = z.object({
+    enabled: z.boolean(),
+    width: z.number().min(0).max(1),
+});
+
+export const sessionAudioSettingsSchema = z.object({
+    ducking: duckingSettingsSchema.optional(),
+    loudness: loudnessSettingsSchema.optional(),
+    transitions: transitionSettingsSchema.optional(),
+    fade_out: fadeOutSettingsSchema.optional(),
+    recording: recordingSettingsSchema.optional(),
+    encoder: encoderSettingsSchema.optional(),
+    tts_pan: ttsPanSettingsSchema.optional(),
+});
+
+export const repeatModeSchema = z.enum(['off', 'one', 'queue']);
+
+export const queueModeSchema = z.object({
+    repeat: repeatModeSchema,
+    shuffle: z.boolean(),
+});
+
 export const guildPlaybackStateSchema = z.object({
     guildId: z.string(),
     guildName: z.string().default(''),
@@ -34,6 +98,8 @@ export const guildPlaybackStateSchema = z.object({
     channelName: z.string().default(''),
     queues: z.record(z.string(), z.array(trackSchema)),
     queueMeta: z.record(z.string(), queueMetaSchema).default({}),
+    settings: sessionAudioSettingsSchema.optional(),
+    queueMode: queueModeSchema.optional(),
 });
 
 export const playbackActionSchema = z.object({
@@ -58,11 +124,21 @@ export const skipSchema = z.object({
     channelId: z.string(),
 });
 
+export const setQueueModeSchema = z.object({
+    guildId: z.string(),
+    channelId: z.string(),
+    repeat: repeatModeSchema.optional(),
+    shuffle: z.boolean().optional(),
+});
+
 export const queueOperationSchema = z.object({
-    op: z.enum(['remove', 'set_volume']),
-    trackId: z.string(),
+    op: z.enum(['remove', 'set_volume', 'move', 'play_next', 'insert_at']),
+    trackId: z.string().optional(),
     targetIndex: z.number().optional(),
     volume: z.number().optional(),
+    queueName: z.string().optional(),
+    tapId: z.string().optional(),
+    audioRequest: z.string().optional(),
 });
 
 export const editQueueSchema = z.object({
@@ -87,10 +163,22 @@ export type AudioMetadataDto = z.infer<typeof audioMetadataSchema>;
 export type TrackDto = z.infer<typeof trackSchema>;
 export type DiscordUserInfoDto = z.infer<typeof discordUserInfoSchema>;
 export type QueueMetaDto = z.infer<typeof queueMetaSchema>;
+export type DuckingSettingsDto = z.infer<typeof duckingSettingsSchema>;
+export type LoudnessSettingsDto = z.infer<typeof loudnessSettingsSchema>;
+export type TransitionSettingsDto = z.infer<typeof transitionSettingsSchema>;
+export type FadeOutSettingsDto = z.infer<typeof fadeOutSettingsSchema>;
+export type RecordingSettingsDto = z.infer<typeof recordingSettingsSchema>;
+export type OpusSignalDto = z.infer<typeof opusSignalSchema>;
+export type EncoderSettingsDto = z.infer<typeof encoderSettingsSchema>;
+export type TtsPanSettingsDto = z.infer<typeof ttsPanSettingsSchema>;
+export type SessionAudioSettingsDto = z.infer<typeof sessionAudioSettingsSchema>;
 export type GuildPlaybackStateDto = z.infer<typeof guildPlaybackStateSchema>;
 export type PlaybackActionDto = z.infer<typeof playbackActionSchema>;
 export type StopTrackDto = z.infer<typeof stopTrackSchema>;
 export type SkipDto = z.infer<typeof skipSchema>;
+export type RepeatModeDto = z.infer<typeof repeatModeSchema>;
+export type QueueModeDto = z.infer<typeof queueModeSchema>;
+export type SetQueueModeDto = z.infer<typeof setQueueModeSchema>;
 export type QueueOperationDto = z.infer<typeof queueOperationSchema>;
 export type EditQueueDto = z.infer<typeof editQueueSchema>;
 export type PauseTrackDto = z.infer<typeof pauseTrackSchema>;
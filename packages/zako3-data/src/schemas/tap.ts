@@ -42,6 +42,15 @@ export const timeSeriesPointSchema = z.object({
     value: z.number(),
 });
 
+export const tapConnectionSchema = z.object({
+    friendlyName: z.string(),
+    connectedAt: z.string(),
+    protocolVersion: z.number().int().nonnegative(),
+    maxConcurrentRequests: z.number().int().positive().nullable(),
+    supportsMetadataOnly: z.boolean(),
+    supportsCancel: z.boolean(),
+});
+
 export const tapStatsSchema = z.object({
     tapId: z.string(),
     currentlyActive: z.number().int().nonnegative(),
@@ -51,6 +60,7 @@ export const tapStatsSchema = z.object({
     uptimePercent: z.number().min(0).max(100),
     useRateHistory: z.array(timeSeriesPointSchema),
     cacheHitRateHistory: z.array(timeSeriesPointSchema),
+    connections: z.array(tapConnectionSchema),
 });
 
 export const tapBaseSchema = z.object({
@@ -261,6 +271,7 @@ export type TapBase = z.infer<typeof tapBaseSchema>;
 export type Tap = z.infer<typeof tapSchema>;
 export type TapWithAccess = z.infer<typeof tapWithAccessSchema>;
 export type TimeSeriesPoint = z.infer<typeof timeSeriesPointSchema>;
+export type TapConnection = z.infer<typeof tapConnectionSchema>;
 export type TapStats = z.infer<typeof tapStatsSchema>;
 export type TapFilters = z.infer<typeof tapFiltersSchema>;
 export type TapSort = z.infer<typeof tapSortSchema>;
@@ -2,6 +2,13 @@
 pub const RINGBUFFER_SIZE: usize = BUFFER_SIZE * 8;
 pub const SAMPLE_RATE: u32 = 48000;
 pub const CHANNELS: u32 = 2;
+/// Audio a new source buffers before it is handed to the mixer.
+pub const DEFAULT_PREROLL: std::time::Duration = std::time::Duration::from_millis(60);
+/// Longest a source waits for its pre-roll before it is handed over anyway.
+pub const MAX_PREROLL_WAIT: std::time::Duration = std::time::Duration::from_secs(2);
+/// Length of the fade that covers a source running dry, and of the fade-in
+/// once it recovers.
+pub const UNDERRUN_FADE_MS: u64 = 5;
 
 pub fn frame_duration() -> std::time::Duration {
     const SECS: f64 = BUFFER_SIZE as f64 / SAMPLE_RATE as f64 / CHANNELS as f64;
@@ -0,0 +1,82 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::CHANNELS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    In,
+    Out,
+}
+
+/// One side of an equal-power crossfade. The fade-in and fade-out of a
+/// transition use the same length so their gains always sum to unit power.
+pub struct Fade {
+    direction: Direction,
+    /// Length in frames (one sample per channel).
+    len: u64,
+    pos: u64,
+}
+
+impl Fade {
+    pub fn fade_in(frames: u64) -> Self {
+        Self::new(Direction::In, frames)
+    }
+
+    pub fn fade_out(frames: u64) -> Self {
+        Self::new(Direction::Out, frames)
+    }
+
+    /// A fade-out that starts at `gain` rather than full level, so a source
+    /// interrupted mid-fade doesn't jump back up first.
+    pub fn fade_out_from(frames: u64, gain: f32) -> Self {
+        let mut fade = Self::new(Direction::Out, frames);
+        let t = gain.clamp(0.0, 1.0).acos() / FRAC_PI_2;
+        fade.pos = (t * fade.len as f32) as u64;
+        fade
+    }
+
+    fn new(direction: Direction, frames: u64) -> Self {
+        Self {
+            direction,
+            len: frames.max(1),
+            pos: 0,
+        }
+    }
+
+    /// The gain the next frame will be scaled by.
+    pub fn gain(&self) -> f32 {
+        let t = self.pos.min(self.len) as f32 / self.len as f32;
+        match self.direction {
+            Direction::In => (t * FRAC_PI_2).sin(),
+            Direction::Out => (t * FRAC_PI_2).cos(),
+        }
+    }
+
+    pub fn is_fade_out(&self) -> bool {
+        self.direction == Direction::Out
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pos >= self.len
+    }
+
+    /// Apply the envelope to an interleaved buffer and advance it. Once a
+    /// fade-out is done the rest of the buffer is silenced.
+    pub fn apply(&mut self, buf: &mut [f32]) {
+        for frame in buf.chunks_mut(CHANNELS as usize) {
+            let gain = if self.is_done() {
+                match self.direction {
+                    Direction::In => 1.0,
+                    Direction::Out => 0.0,
+                }
+            } else {
+                let gain = self.gain();
+                self.pos += 1;
+                gain
+            };
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+    }
+}
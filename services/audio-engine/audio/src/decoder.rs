@@ -1,18 +1,36 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 use mockall::automock;
 use ringbuf::traits::{Observer, Producer};
 use serenity::async_trait;
-use tokio::sync::watch;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{oneshot, watch};
 use tracing::instrument;
 
-use crate::{RingCons, RingProd, create_ringbuf_pair, speed_control};
-use crate::{error::ZakoResult, types::TrackId};
+use crate::loudness::LoudnessNormalizer;
+use crate::speed_control::{SpeedControlConfig, SpeedController};
+use crate::{
+    BUFFER_SIZE, CHANNELS, DEFAULT_PREROLL, MAX_PREROLL_WAIT, RingCons, RingProd, SAMPLE_RATE,
+    create_ringbuf_pair, metrics,
+};
+use crate::{
+    error::ZakoResult,
+    types::{LoudnessSettings, TrackId},
+};
 
 pub type ArcDecoder = Arc<dyn Decoder>;
 
+/// Loudness inputs for a single decode task.
+#[derive(Debug, Default)]
+pub struct TrackLoudness {
+    /// Integrated loudness measured on an earlier play, if known.
+    pub known_lufs: Option<f32>,
+    /// Receives the integrated loudness once the stream has been fully decoded.
+    pub report_tx: Option<oneshot::Sender<f32>>,
+}
+
 #[automock]
 #[async_trait]
 pub trait Decoder: Send + Sync + 'static {
@@ -20,21 +38,97 @@ async fn start_decoding(
         &self,
         track_id: TrackId,
         stream: tokio::sync::mpsc::Receiver<Vec<f32>>,
-    ) -> ZakoResult<RingCons>;
+        loudness: TrackLoudness,
+    ) -> ZakoResult<Preroll>;
 
+    fn set_loudness(&self, settings: LoudnessSettings);
     fn pause_track(&self, track_id: TrackId);
     fn resume_track(&self, track_id: TrackId);
     fn stop_track(&self, track_id: TrackId);
 }
 
+/// Buffering and pacing for every track a decoder plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecoderConfig {
+    /// Audio buffered before a source is handed to the mixer.
+    pub preroll: Duration,
+    /// Gains every decode task's pacing starts from.
+    pub speed_control: SpeedControlConfig,
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self {
+            preroll: DEFAULT_PREROLL,
+            speed_control: SpeedControlConfig::default(),
+        }
+    }
+}
+
+/// A started track's ring buffer, which may still be buffering its pre-roll.
+/// Returned right away so the caller is not held up by a slow tap.
+pub struct Preroll {
+    consumer: RingCons,
+    ready_rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Preroll {
+    /// `ready_rx` fires, or is dropped, once the pre-roll is buffered or the
+    /// stream has ended short of it.
+    pub fn new(consumer: RingCons, ready_rx: oneshot::Receiver<()>) -> Self {
+        Self {
+            consumer,
+            ready_rx: Some(ready_rx),
+        }
+    }
+
+    /// A source with nothing left to wait for.
+    pub fn ready(consumer: RingCons) -> Self {
+        Self {
+            consumer,
+            ready_rx: None,
+        }
+    }
+
+    /// The consumer if the pre-roll is already buffered, otherwise `self`
+    /// back to [`wait`](Self::wait) on.
+    pub fn try_ready(mut self) -> Result<RingCons, Self> {
+        match self.ready_rx.as_mut().map(|rx| rx.try_recv()) {
+            Some(Err(TryRecvError::Empty)) => Err(self),
+            _ => Ok(self.consumer),
+        }
+    }
+
+    /// Wait for the pre-roll, but no longer than [`MAX_PREROLL_WAIT`].
+    pub async fn wait(self) -> RingCons {
+        if let Some(ready_rx) = self.ready_rx
+            && tokio::time::timeout(MAX_PREROLL_WAIT, ready_rx)
+                .await
+                .is_err()
+        {
+            metrics::record_preroll_timeout();
+            tracing::debug!("Pre-roll timed out, handing source over early");
+        }
+        self.consumer
+    }
+}
+
 pub struct PcmDecoder {
     pause_txs: Arc<DashMap<TrackId, watch::Sender<bool>>>,
+    loudness_tx: watch::Sender<LoudnessSettings>,
+    config: DecoderConfig,
 }
 
 impl PcmDecoder {
     pub fn new() -> Self {
+        Self::with_config(DecoderConfig::default())
+    }
+
+    pub fn with_config(config: DecoderConfig) -> Self {
         PcmDecoder {
             pause_txs: Arc::new(DashMap::new()),
+            loudness_tx: watch::Sender::new(LoudnessSettings::default()),
+            config,
         }
     }
 }
@@ -47,31 +141,60 @@ fn default() -> Self {
 
 #[async_trait]
 impl Decoder for PcmDecoder {
-    #[instrument(skip(self, stream))]
+    #[instrument(skip(self, stream, loudness))]
     async fn start_decoding(
         &self,
         track_id: TrackId,
         stream: tokio::sync::mpsc::Receiver<Vec<f32>>,
-    ) -> ZakoResult<RingCons> {
+        loudness: TrackLoudness,
+    ) -> ZakoResult<Preroll> {
         let (prod, cons) = create_ringbuf_pair();
+        let preroll =
+            self.config.preroll.as_millis() as usize * (SAMPLE_RATE * CHANNELS) as usize / 1000;
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let pacing = Pacing {
+            preroll: preroll.min(prod.capacity().into()),
+            ready_tx,
+            speed_control: self.config.speed_control,
+        };
 
         let (pause_tx, pause_rx) = watch::channel(false);
 
         self.pause_txs.insert(track_id, pause_tx);
 
         let pause_txs = self.pause_txs.clone();
+        let own_pause_rx = pause_rx.clone();
+        let loudness_rx = self.loudness_tx.subscribe();
 
         tokio::spawn(async move {
-            let result = spawn_decode_task(track_id, stream, prod, pause_rx).await;
+            let result = spawn_decode_task(
+                track_id,
+                stream,
+                prod,
+                pause_rx,
+                loudness_rx,
+                loudness,
+                pacing,
+            )
+            .await;
             if let Err(e) = result {
                 tracing::error!(track_id = %track_id, error = %e, "Decoding task failed");
             }
 
-            // Clean up pause_txs entry on task exit
-            pause_txs.remove(&track_id);
+            // Clean up pause_txs entry on task exit, unless a seek has
+            // already started a new task for the track.
+            pause_txs.remove_if(&track_id, |_, tx| {
+                own_pause_rx.same_channel(&tx.subscribe())
+            });
         });
 
-        Ok(cons)
+        // A short stream ends before filling the pre-roll, which drops the
+        // sender and counts as ready too.
+        Ok(Preroll::new(cons, ready_rx))
+    }
+
+    fn set_loudness(&self, settings: LoudnessSettings) {
+        self.loudness_tx.send_replace(settings);
     }
 
     fn pause_track(&self, track_id: TrackId) {
@@ -91,21 +214,42 @@ fn stop_track(&self, track_id: TrackId) {
     }
 }
 
+/// How a decode task feeds its ring buffer.
+struct Pacing {
+    /// Samples to buffer before `ready_tx` fires.
+    preroll: usize,
+    ready_tx: oneshot::Sender<()>,
+    speed_control: SpeedControlConfig,
+}
+
 async fn spawn_decode_task(
     track_id: TrackId,
     mut stream: tokio::sync::mpsc::Receiver<Vec<f32>>,
     mut producer: RingProd,
     mut pause_rx: watch::Receiver<bool>,
+    loudness_rx: watch::Receiver<LoudnessSettings>,
+    loudness: TrackLoudness,
+    pacing: Pacing,
 ) -> ZakoResult<()> {
     tracing::debug!(track_id = %track_id, "Starting PCM decode task");
 
-    let speed_control_config = speed_control::SpeedControlConfig {
-        min_delay: Duration::from_millis(1),
-        max_delay: Duration::from_millis(100),
-        target_fill_ratio: 0.5,
+    let mut ready_tx = Some(pacing.ready_tx);
+
+    let mut normalizer = LoudnessNormalizer::new(loudness.known_lufs);
+
+    let mut speed_control = SpeedController::new(pacing.speed_control);
+    let mut last_write = Instant::now();
+    let mut next_delay = |producer: &RingProd| {
+        let delay = speed_control.next_delay(
+            producer.occupied_len(),
+            producer.capacity().into(),
+            last_write.elapsed(),
+        );
+        last_write = Instant::now();
+        delay
     };
 
-    while let Some(chunk) = stream.recv().await {
+    while let Some(mut chunk) = stream.recv().await {
         // Pause gate: wait until resume or stop_track
         while *pause_rx.borrow() {
             match pause_rx.changed().await {
@@ -118,6 +262,9 @@ async fn spawn_decode_task(
             }
         }
 
+        let settings = *loudness_rx.borrow();
+        normalizer.process(&mut chunk, &settings);
+
         let mut idx = 0;
         while idx < chunk.len() {
             if !producer.read_is_held() {
@@ -127,16 +274,13 @@ async fn spawn_decode_task(
 
             let vacant = producer.vacant_len();
             if vacant == 0 {
-                let delay = speed_control::calculate_delay(
-                    &speed_control_config,
-                    producer.occupied_len(),
-                    producer.capacity().into(),
-                );
-                tokio::time::sleep(delay).await;
+                let delay = next_delay(&producer);
+                tokio::time::sleep(delay.max(pacing.speed_control.min_delay)).await;
                 continue;
             }
 
-            let take = vacant.min(chunk.len() - idx);
+            // At most a frame per write, so the pacing has a say in the rate.
+            let take = vacant.min(chunk.len() - idx).min(BUFFER_SIZE);
 
             for _ in 0..take {
                 let sample = chunk[idx];
@@ -146,17 +290,179 @@ async fn spawn_decode_task(
                 idx += 1;
             }
 
-            let delay = speed_control::calculate_delay(
-                &speed_control_config,
-                producer.occupied_len(),
-                producer.capacity().into(),
-            );
+            if producer.occupied_len() >= pacing.preroll
+                && let Some(tx) = ready_tx.take()
+            {
+                let _ = tx.send(());
+            }
+
+            let delay = next_delay(&producer);
             if delay > Duration::from_millis(0) {
                 tokio::time::sleep(delay).await;
             }
         }
     }
 
-    tracing::debug!(track_id = %track_id, "Decoding complete");
+    let measured = normalizer.measured_lufs();
+    tracing::debug!(track_id = %track_id, loudness_lufs = ?measured, "Decoding complete");
+    if let (Some(tx), Some(lufs)) = (loudness.report_tx, measured) {
+        let _ = tx.send(lufs);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREROLL_SAMPLES: usize =
+        DEFAULT_PREROLL.as_millis() as usize * (SAMPLE_RATE * CHANNELS) as usize / 1000;
+
+    fn start(
+        decoder: &Arc<PcmDecoder>,
+        stream: tokio::sync::mpsc::Receiver<Vec<f32>>,
+    ) -> tokio::task::JoinHandle<ZakoResult<RingCons>> {
+        let decoder = decoder.clone();
+        tokio::spawn(async move {
+            let preroll = decoder
+                .start_decoding(TrackId::from(1), stream, TrackLoudness::default())
+                .await?;
+            Ok(preroll.wait().await)
+        })
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_preroll_before_handing_over() {
+        let decoder = Arc::new(PcmDecoder::with_config(DecoderConfig::default()));
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let handle = start(&decoder, rx);
+
+        tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!handle.is_finished());
+
+        for _ in 0..PREROLL_SAMPLES / BUFFER_SIZE {
+            tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        }
+        let consumer = tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("pre-roll was buffered")
+            .unwrap()
+            .unwrap();
+        assert!(consumer.occupied_len() >= PREROLL_SAMPLES);
+    }
+
+    #[tokio::test]
+    async fn short_stream_is_handed_over_when_it_ends() {
+        let decoder = Arc::new(PcmDecoder::with_config(DecoderConfig::default()));
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let handle = start(&decoder, rx);
+
+        tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        drop(tx);
+        let consumer = tokio::time::timeout(MAX_PREROLL_WAIT / 2, handle)
+            .await
+            .expect("ended stream counts as ready")
+            .unwrap()
+            .unwrap();
+        assert_eq!(consumer.occupied_len(), BUFFER_SIZE);
+    }
+
+    #[tokio::test]
+    async fn stalled_stream_is_handed_over_after_the_max_wait() {
+        let decoder = Arc::new(PcmDecoder::with_config(DecoderConfig::default()));
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let started = Instant::now();
+        let handle = start(&decoder, rx);
+
+        tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        let consumer = handle.await.unwrap().unwrap();
+        assert!(started.elapsed() >= MAX_PREROLL_WAIT);
+        assert_eq!(consumer.occupied_len(), BUFFER_SIZE);
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn pacing_follows_the_configured_gains() {
+        // Aiming at an empty ring with a high gain, every write waits out the
+        // whole max delay.
+        let slow = DecoderConfig {
+            preroll: Duration::ZERO,
+            speed_control: SpeedControlConfig {
+                max_delay: Duration::from_millis(500),
+                target_fill_ratio: 0.0,
+                kp: 10.0,
+                ..SpeedControlConfig::default()
+            },
+        };
+        for (config, writes_ahead) in [(DecoderConfig::default(), true), (slow, false)] {
+            let decoder = PcmDecoder::with_config(config);
+            let (tx, rx) = tokio::sync::mpsc::channel(16);
+            for _ in 0..4 {
+                tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+            }
+            let consumer = decoder
+                .start_decoding(TrackId::from(1), rx, TrackLoudness::default())
+                .await
+                .unwrap()
+                .wait()
+                .await;
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            assert_eq!(consumer.occupied_len() > BUFFER_SIZE, writes_ahead);
+        }
+    }
+
+    #[tokio::test]
+    async fn pause_right_after_a_seek_reaches_the_new_task() {
+        let decoder = PcmDecoder::with_config(DecoderConfig {
+            preroll: Duration::ZERO,
+            ..DecoderConfig::default()
+        });
+        let track_id = TrackId::from(1);
+        let (old_tx, old_rx) = tokio::sync::mpsc::channel(16);
+        let _old = decoder
+            .start_decoding(track_id, old_rx, TrackLoudness::default())
+            .await
+            .unwrap();
+
+        // A seek starts over while the old task still waits on its tap.
+        decoder.stop_track(track_id);
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let preroll = decoder
+            .start_decoding(track_id, rx, TrackLoudness::default())
+            .await
+            .unwrap();
+        tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        drop(old_tx);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        decoder.pause_track(track_id);
+        tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let consumer = preroll.wait().await;
+        assert_eq!(consumer.occupied_len(), BUFFER_SIZE);
+    }
+
+    #[tokio::test]
+    async fn start_returns_before_the_preroll() {
+        let decoder = PcmDecoder::with_config(DecoderConfig::default());
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+
+        let preroll = tokio::time::timeout(
+            Duration::from_millis(100),
+            decoder.start_decoding(TrackId::from(1), rx, TrackLoudness::default()),
+        )
+        .await
+        .expect("start does not wait for audio")
+        .unwrap();
+        let preroll = preroll.try_ready().err().expect("nothing buffered yet");
+
+        for _ in 0..=PREROLL_SAMPLES / BUFFER_SIZE {
+            tx.send(vec![0.1; BUFFER_SIZE]).await.unwrap();
+        }
+        let consumer = tokio::time::timeout(Duration::from_secs(1), preroll.wait())
+            .await
+            .expect("pre-roll was buffered");
+        assert!(consumer.occupied_len() >= PREROLL_SAMPLES);
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::VecDeque;
+
+use ringbuf::traits::{Consumer, Observer};
+
+use crate::{BUFFER_SIZE, CHANNELS, RingCons, types::TrackDsp};
+
+const CH: usize = CHANNELS as usize;
+
+/// A processing stage operating on interleaved stereo samples.
+///
+/// Nodes may consume and produce a different number of samples than they
+/// are given (time-stretching, resampling), so each call appends whatever
+/// output is ready and keeps the rest as internal state.
+pub trait DspNode: Send {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>);
+
+    /// Emit any buffered samples once the input has ended.
+    fn flush(&mut self, _output: &mut Vec<f32>) {}
+}
+
+/// Ordered chain of [`DspNode`]s attached to a mixer source.
+pub struct DspChain {
+    nodes: Vec<Box<dyn DspNode>>,
+    pending: VecDeque<f32>,
+    flushed: bool,
+    input: Vec<f32>,
+    scratch: [Vec<f32>; 2],
+    consumed: u64,
+}
+
+impl DspChain {
+    pub fn new(params: TrackDsp) -> Self {
+        Self {
+            nodes: build_nodes(params),
+            pending: VecDeque::with_capacity(BUFFER_SIZE * 2),
+            flushed: false,
+            input: vec![0f32; BUFFER_SIZE],
+            scratch: [Vec::new(), Vec::new()],
+            consumed: 0,
+        }
+    }
+
+    /// Replace the nodes with ones built from `params`. Samples still held
+    /// inside the old nodes are flushed out first so a live change does not
+    /// drop audio.
+    pub fn reconfigure(&mut self, params: TrackDsp) {
+        self.drain_nodes();
+        self.nodes = build_nodes(params);
+    }
+
+    /// `true` once the input has ended and every processed sample was read.
+    pub fn is_drained(&self) -> bool {
+        self.flushed && self.pending.is_empty()
+    }
+
+    /// Total input samples pulled from the source so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Pull from `consumer` through the chain until `out` can be filled or
+    /// the source runs dry. Returns the number of samples written.
+    pub fn fill(&mut self, consumer: &mut RingCons, out: &mut [f32]) -> usize {
+        while self.pending.len() < out.len() {
+            let n = consumer.pop_slice(&mut self.input);
+            if n == 0 {
+                if !consumer.write_is_held() && !self.flushed {
+                    self.flush();
+                }
+                break;
+            }
+            self.consumed += n as u64;
+            let input = std::mem::take(&mut self.input);
+            self.push(&input[..n]);
+            self.input = input;
+        }
+
+        let c = out.len().min(self.pending.len());
+        for (dst, src) in out[..c].iter_mut().zip(self.pending.drain(..c)) {
+            *dst = src;
+        }
+        c
+    }
+
+    fn push(&mut self, input: &[f32]) {
+        let [a, b] = &mut self.scratch;
+        a.clear();
+        a.extend_from_slice(input);
+        for node in self.nodes.iter_mut() {
+            b.clear();
+            node.process(a, b);
+            std::mem::swap(a, b);
+        }
+        self.pending.extend(a.iter());
+    }
+
+    fn flush(&mut self) {
+        self.flushed = true;
+        self.drain_nodes();
+    }
+
+    /// Push every sample buffered inside the nodes through to `pending`.
+    fn drain_nodes(&mut self) {
+        let [a, b] = &mut self.scratch;
+        a.clear();
+        for node in self.nodes.iter_mut() {
+            b.clear();
+            node.process(a, b);
+            node.flush(b);
+            std::mem::swap(a, b);
+        }
+        self.pending.extend(a.iter());
+    }
+}
+
+fn build_nodes(params: TrackDsp) -> Vec<Box<dyn DspNode>> {
+    let params = params.clamped();
+    let pitch_ratio = params.pitch_ratio() as f64;
+
+    // Pitch shift = stretch by the pitch ratio, then resample it back to the
+    // original duration. Folding it into the speed/tempo stages keeps the
+    // chain at most two nodes deep.
+    let stretch = params.speed as f64 / pitch_ratio;
+    let rate = params.tempo as f64 * pitch_ratio;
+
+    let mut nodes: Vec<Box<dyn DspNode>> = Vec::new();
+    if (stretch - 1.0).abs() > 1e-3 {
+        nodes.push(Box::new(TimeStretch::new(stretch)));
+    }
+    if (rate - 1.0).abs() > 1e-3 {
+        nodes.push(Box::new(Resampler::new(rate)));
+    }
+    nodes
+}
+
+/// Linear-interpolating resampler. Reads input at `rate` frames per output
+/// frame, which shifts pitch and duration together.
+pub struct Resampler {
+    rate: f64,
+    pos: f64,
+    buf: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            pos: 0.0,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl DspNode for Resampler {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.buf.extend_from_slice(input);
+        let frames = self.buf.len() / CH;
+
+        while self.pos + 1.0 < frames as f64 {
+            let i = self.pos as usize;
+            let frac = (self.pos - i as f64) as f32;
+            for ch in 0..CH {
+                let a = self.buf[i * CH + ch];
+                let b = self.buf[(i + 1) * CH + ch];
+                output.push(a + (b - a) * frac);
+            }
+            self.pos += self.rate;
+        }
+
+        let consumed = (self.pos as usize).min(frames);
+        self.buf.drain(..consumed * CH);
+        self.pos -= consumed as f64;
+    }
+}
+
+/// WSOLA time-stretcher: changes duration while keeping pitch.
+///
+/// Each step takes a window near the nominal analysis position, nudged
+/// within `SEARCH` frames to best line up with the tail of the previous
+/// window, and cross-fades the two over `HOP` frames.
+pub struct TimeStretch {
+    ratio: f64,
+    pos: f64,
+    buf: Vec<f32>,
+    tail: Vec<f32>,
+    primed: bool,
+    fade: Vec<f32>,
+}
+
+impl TimeStretch {
+    const WINDOW: usize = 1536;
+    const HOP: usize = Self::WINDOW / 2;
+    const SEARCH: usize = 256;
+    /// Correlation is computed on every n-th frame to bound the search cost.
+    const CORR_STRIDE: usize = 4;
+
+    pub fn new(ratio: f64) -> Self {
+        let fade = (0..Self::HOP)
+            .map(|k| {
+                let x = (k as f32 + 0.5) / Self::HOP as f32;
+                0.5 - 0.5 * (std::f32::consts::PI * x).cos()
+            })
+            .collect();
+        Self {
+            ratio,
+            pos: Self::SEARCH as f64,
+            buf: vec![0f32; Self::SEARCH * CH],
+            tail: vec![0f32; Self::HOP * CH],
+            primed: false,
+            fade,
+        }
+    }
+
+    fn best_offset(&self, lo: usize, hi: usize) -> usize {
+        let mut best = lo;
+        let mut best_score = f32::MIN;
+        for start in lo..=hi {
+            let mut corr = 0f32;
+            let mut energy = 0f32;
+            let mut k = 0;
+            while k < Self::HOP {
+                let c = (self.buf[(start + k) * CH] + self.buf[(start + k) * CH + 1]) * 0.5;
+                let t = (self.tail[k * CH] + self.tail[k * CH + 1]) * 0.5;
+                corr += c * t;
+                energy += c * c;
+                k += Self::CORR_STRIDE;
+            }
+            let score = corr / (energy + 1e-9).sqrt();
+            if score > best_score {
+                best_score = score;
+                best = start;
+            }
+        }
+        best
+    }
+}
+
+impl DspNode for TimeStretch {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.buf.extend_from_slice(input);
+
+        loop {
+            let frames = self.buf.len() / CH;
+            let nominal = self.pos.round() as usize;
+            let lo = nominal.saturating_sub(Self::SEARCH);
+            let hi = nominal + Self::SEARCH;
+            if hi + Self::WINDOW > frames {
+                break;
+            }
+
+            let start = if self.primed {
+                self.best_offset(lo, hi)
+            } else {
+                nominal
+            };
+            let window = &self.buf[start * CH..(start + Self::WINDOW) * CH];
+
+            if self.primed {
+                for k in 0..Self::HOP {
+                    let w = self.fade[k];
+                    for ch in 0..CH {
+                        let i = k * CH + ch;
+                        output.push(self.tail[i] * (1.0 - w) + window[i] * w);
+                    }
+                }
+            } else {
+                output.extend_from_slice(&window[..Self::HOP * CH]);
+                self.primed = true;
+            }
+            self.tail
+                .copy_from_slice(&window[Self::HOP * CH..Self::WINDOW * CH]);
+
+            self.pos += Self::HOP as f64 * self.ratio;
+            let keep_from = (self.pos as usize)
+                .saturating_sub(Self::SEARCH)
+                .min(frames);
+            if keep_from > 0 {
+                self.buf.drain(..keep_from * CH);
+                self.pos -= keep_from as f64;
+            }
+        }
+    }
+
+    fn flush(&mut self, output: &mut Vec<f32>) {
+        if self.primed {
+            output.extend_from_slice(&self.tail);
+            self.primed = false;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{BUFFER_SIZE, CHANNELS, SAMPLE_RATE, types::DuckingSettings};
+
+const FRAME_MS: f32 = (BUFFER_SIZE as u32 / CHANNELS) as f32 * 1000.0 / SAMPLE_RATE as f32;
+const MAX_DEPTH_DB: f32 = 60.0;
+
+/// Role of a mixer source for ducking purposes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SourceClass {
+    /// Pulled down while speech is active.
+    Music,
+    /// Triggers ducking of music sources.
+    Speech,
+    #[default]
+    Other,
+}
+
+/// Per-mixer ducking envelope. Advanced once per mixer frame.
+pub struct Ducker {
+    settings: DuckingSettings,
+    gain: f32,
+}
+
+impl Ducker {
+    pub fn new(settings: DuckingSettings) -> Self {
+        Self { settings, gain: 1.0 }
+    }
+
+    pub fn configure(&mut self, settings: DuckingSettings) {
+        self.settings = settings;
+    }
+
+    /// Move the envelope one frame towards its target and return the gain
+    /// at the start and end of that frame, to be fed into the volume ramp.
+    pub fn step(&mut self, speech_active: bool) -> (f32, f32) {
+        let start = self.gain;
+        let floor = db_to_gain(-self.settings.depth_db.clamp(0.0, MAX_DEPTH_DB));
+        let target = if self.settings.enabled && speech_active {
+            floor
+        } else {
+            1.0
+        };
+
+        let ramp_ms = if target < self.gain {
+            self.settings.attack_ms
+        } else {
+            self.settings.release_ms
+        };
+        let max_delta = if ramp_ms == 0 {
+            f32::INFINITY
+        } else {
+            (1.0 - floor) * FRAME_MS / ramp_ms as f32
+        };
+
+        let diff = target - self.gain;
+        self.gain += diff.clamp(-max_delta, max_delta);
+        (start, self.gain)
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
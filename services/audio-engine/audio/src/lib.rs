@@ -1,6 +1,15 @@
 pub mod mixer;
 pub use mixer::*;
 pub mod decoder;
+pub mod crossfade;
+pub mod dsp;
+pub mod ducking;
+pub mod limiter;
+pub mod loudness;
+pub mod ogg_opus;
+pub mod pan;
+pub mod recorder;
+pub use ducking::SourceClass;
 pub use decoder::*;
 pub mod constant;
 pub use constant::*;
@@ -12,4 +21,4 @@
 pub mod metrics;
 pub use ringbuf;
 
-mod speed_control;
+pub mod speed_control;
@@ -0,0 +1,158 @@
+//! Look-ahead true-peak limiter for the mixer output.
+
+use std::collections::VecDeque;
+
+use crate::{CHANNELS, SAMPLE_RATE};
+
+/// Output ceiling, -1 dBTP.
+const CEILING: f32 = 0.891_251;
+/// Oversampling factor used to estimate inter-sample peaks.
+const OVERSAMPLE: usize = 4;
+/// Taps per polyphase branch of the interpolator.
+const TAPS: usize = 8;
+/// The interpolator looks at the two samples `TP_DELAY` and `TP_DELAY - 1`
+/// frames in the past.
+const TP_DELAY: usize = TAPS / 2;
+const LOOKAHEAD_MS: u32 = 2;
+const RELEASE_MS: f32 = 80.0;
+
+const CH: usize = CHANNELS as usize;
+
+/// Stereo-linked limiter that keeps the 4x-oversampled (true) peak of the
+/// output below -1 dBTP.
+///
+/// The required gain of each frame is held for the look-ahead window and
+/// smoothed with a box filter of the same length, so gain reduction is fully
+/// in place by the time the peak leaves the delay line and never steps.
+pub struct TruePeakLimiter {
+    phases: [[f32; TAPS]; OVERSAMPLE - 1],
+    history: [[f32; TAPS]; CH],
+    lookahead: usize,
+    delay: VecDeque<[f32; CH]>,
+    /// Monotonic queue of (frame index, required gain) for the sliding minimum.
+    hold: VecDeque<(u64, f32)>,
+    box_window: VecDeque<f32>,
+    box_sum: f64,
+    envelope: f32,
+    release_coef: f32,
+    frame: u64,
+}
+
+impl Default for TruePeakLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TruePeakLimiter {
+    pub fn new() -> Self {
+        let lookahead = (SAMPLE_RATE * LOOKAHEAD_MS / 1000) as usize;
+        let release_coef = 1.0 - (-1.0 / (RELEASE_MS * SAMPLE_RATE as f32 / 1000.0)).exp();
+
+        let mut delay = VecDeque::with_capacity(lookahead + TP_DELAY);
+        delay.extend(std::iter::repeat_n([0.0; CH], lookahead - 1 + TP_DELAY));
+        let mut box_window = VecDeque::with_capacity(lookahead);
+        box_window.extend(std::iter::repeat_n(1.0, lookahead));
+
+        Self {
+            phases: interpolator_phases(),
+            history: [[0.0; TAPS]; CH],
+            lookahead,
+            delay,
+            hold: VecDeque::with_capacity(lookahead),
+            box_window,
+            box_sum: lookahead as f64,
+            envelope: 1.0,
+            release_coef,
+            frame: 0,
+        }
+    }
+
+    /// Limit an interleaved buffer in place. Output is delayed by the
+    /// look-ahead (about 2 ms).
+    pub fn process(&mut self, buf: &mut [f32]) {
+        for frame in buf.chunks_exact_mut(CH) {
+            let input: [f32; CH] = frame.try_into().expect("chunk is CH wide");
+
+            let peak = self.true_peak(&input);
+            let required = if peak > CEILING { CEILING / peak } else { 1.0 };
+
+            // Sliding minimum of the required gain. The window is one frame
+            // longer than the box so both samples of the pair are covered.
+            while self.hold.back().is_some_and(|&(_, g)| g >= required) {
+                self.hold.pop_back();
+            }
+            self.hold.push_back((self.frame, required));
+            while self
+                .hold
+                .front()
+                .is_some_and(|&(i, _)| i + (self.lookahead as u64) < self.frame)
+            {
+                self.hold.pop_front();
+            }
+            let held = self.hold.front().map_or(1.0, |&(_, g)| g);
+
+            // Instant attack (the box filter below shapes it), exponential release.
+            if held < self.envelope {
+                self.envelope = held;
+            } else {
+                self.envelope += (held - self.envelope) * self.release_coef;
+            }
+
+            let oldest = self.box_window.pop_front().unwrap_or(1.0);
+            self.box_window.push_back(self.envelope);
+            self.box_sum += self.envelope as f64 - oldest as f64;
+            let gain = (self.box_sum / self.lookahead as f64) as f32;
+
+            self.delay.push_back(input);
+            let delayed = self.delay.pop_front().unwrap_or([0.0; CH]);
+            for (out, s) in frame.iter_mut().zip(delayed) {
+                *out = s * gain;
+            }
+
+            self.frame += 1;
+        }
+    }
+
+    /// Largest absolute value of the sample pair `TP_DELAY - 1..=TP_DELAY`
+    /// frames back and the interpolated points between them.
+    fn true_peak(&mut self, input: &[f32; CH]) -> f32 {
+        let mut peak = 0f32;
+        for (ch, &s) in input.iter().enumerate() {
+            let hist = &mut self.history[ch];
+            hist.copy_within(1.., 0);
+            hist[TAPS - 1] = s;
+
+            peak = peak.max(hist[TAPS / 2 - 1].abs()).max(hist[TAPS / 2].abs());
+            for phase in &self.phases {
+                let v: f32 = phase.iter().zip(hist.iter()).map(|(c, x)| c * x).sum();
+                peak = peak.max(v.abs());
+            }
+        }
+        peak
+    }
+}
+
+/// Hann-windowed sinc interpolator branches for fractional offsets 1/4, 2/4,
+/// 3/4, each normalized to unity DC gain.
+fn interpolator_phases() -> [[f32; TAPS]; OVERSAMPLE - 1] {
+    let mut phases = [[0f32; TAPS]; OVERSAMPLE - 1];
+    let half = TAPS as f64 / 2.0;
+    for (p, taps) in phases.iter_mut().enumerate() {
+        let frac = (p + 1) as f64 / OVERSAMPLE as f64;
+        for (k, tap) in taps.iter_mut().enumerate() {
+            // Distance from tap k to the interpolated point between k = half - 1 and k = half.
+            let t = k as f64 - (half - 1.0) - frac;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t)
+            };
+            let window = 0.5 * (1.0 + (std::f64::consts::PI * t / half).cos());
+            *tap = (sinc * window) as f32;
+        }
+        let sum: f32 = taps.iter().sum();
+        taps.iter_mut().for_each(|c| *c /= sum);
+    }
+    phases
+}
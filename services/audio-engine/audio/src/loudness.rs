@@ -0,0 +1,231 @@
+//! EBU R128 / ITU-R BS.1770 integrated loudness measurement and per-track
+//! normalization gain.
+
+use crate::{CHANNELS, SAMPLE_RATE, types::LoudnessSettings};
+
+/// 100 ms step; a gating block is four steps (400 ms, 75% overlap).
+const STEP_FRAMES: usize = SAMPLE_RATE as usize / 10;
+const STEPS_PER_BLOCK: usize = 4;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+// Gated block loudness is binned at 0.1 LU between -70 and +10 LUFS so the
+// integrated value can be recomputed cheaply at any point of the stream.
+const HIST_MIN_LUFS: f64 = ABSOLUTE_GATE_LUFS;
+const HIST_BIN_LU: f64 = 0.1;
+const HIST_BINS: usize = 800;
+
+const MAX_BOOST_DB: f32 = 12.0;
+const MAX_CUT_DB: f32 = 24.0;
+/// How fast the gain follows a still-converging measurement.
+const GAIN_SLEW_DB_PER_SEC: f32 = 6.0;
+
+#[derive(Clone, Copy, Default)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    const fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting at 48 kHz: high-shelf pre-filter followed by the RLB
+/// high-pass.
+fn k_weighting() -> [Biquad; 2] {
+    [
+        Biquad::new(
+            1.535_124_859_586_97,
+            -2.691_696_189_406_38,
+            1.198_392_810_852_85,
+            -1.690_659_293_182_41,
+            0.732_480_774_215_85,
+        ),
+        Biquad::new(1.0, -2.0, 1.0, -1.990_047_454_833_98, 0.990_072_250_366_21),
+    ]
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Streaming integrated loudness meter for interleaved stereo at 48 kHz.
+pub struct LoudnessMeter {
+    filters: [[Biquad; 2]; CHANNELS as usize],
+    step_sum: f64,
+    step_frames: usize,
+    steps: [f64; STEPS_PER_BLOCK],
+    steps_seen: usize,
+    hist_count: Box<[u64; HIST_BINS]>,
+    hist_energy: Box<[f64; HIST_BINS]>,
+}
+
+impl Default for LoudnessMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoudnessMeter {
+    pub fn new() -> Self {
+        Self {
+            filters: [k_weighting(), k_weighting()],
+            step_sum: 0.0,
+            step_frames: 0,
+            steps: [0.0; STEPS_PER_BLOCK],
+            steps_seen: 0,
+            hist_count: Box::new([0; HIST_BINS]),
+            hist_energy: Box::new([0.0; HIST_BINS]),
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(CHANNELS as usize) {
+            for (ch, &s) in frame.iter().enumerate() {
+                let [pre, rlb] = &mut self.filters[ch];
+                let y = rlb.process(pre.process(s as f64));
+                self.step_sum += y * y;
+            }
+            self.step_frames += 1;
+            if self.step_frames == STEP_FRAMES {
+                self.finish_step();
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        self.steps[self.steps_seen % STEPS_PER_BLOCK] = self.step_sum;
+        self.steps_seen += 1;
+        self.step_sum = 0.0;
+        self.step_frames = 0;
+
+        if self.steps_seen < STEPS_PER_BLOCK {
+            return;
+        }
+        let energy = self.steps.iter().sum::<f64>() / (STEP_FRAMES * STEPS_PER_BLOCK) as f64;
+        let lufs = energy_to_lufs(energy);
+        if lufs <= ABSOLUTE_GATE_LUFS {
+            return;
+        }
+        let bin = (((lufs - HIST_MIN_LUFS) / HIST_BIN_LU) as usize).min(HIST_BINS - 1);
+        self.hist_count[bin] += 1;
+        self.hist_energy[bin] += energy;
+    }
+
+    /// Gated integrated loudness of everything pushed so far, or `None` until
+    /// at least one block passes the absolute gate.
+    pub fn integrated(&self) -> Option<f32> {
+        let (count, energy) = self.sum_bins(0);
+        if count == 0 {
+            return None;
+        }
+        let threshold = energy_to_lufs(energy / count as f64) + RELATIVE_GATE_LU;
+        let first = ((threshold - HIST_MIN_LUFS) / HIST_BIN_LU).max(0.0) as usize;
+        let (count, energy) = self.sum_bins(first.min(HIST_BINS - 1));
+        if count == 0 {
+            return None;
+        }
+        Some(energy_to_lufs(energy / count as f64) as f32)
+    }
+
+    fn sum_bins(&self, from: usize) -> (u64, f64) {
+        self.hist_count[from..]
+            .iter()
+            .zip(&self.hist_energy[from..])
+            .fold((0, 0.0), |(c, e), (bc, be)| (c + bc, e + be))
+    }
+}
+
+/// Measures a track as it is decoded and applies the gain that brings it to
+/// the configured target.
+///
+/// When the loudness is already known (measured on an earlier play) the gain
+/// is applied from the first sample. Otherwise it follows the running
+/// integrated measurement, slewed so the level does not jump around while the
+/// measurement converges.
+pub struct LoudnessNormalizer {
+    meter: LoudnessMeter,
+    known_lufs: Option<f32>,
+    gain_db: Option<f32>,
+}
+
+impl LoudnessNormalizer {
+    pub fn new(known_lufs: Option<f32>) -> Self {
+        Self {
+            meter: LoudnessMeter::new(),
+            known_lufs,
+            gain_db: None,
+        }
+    }
+
+    /// Integrated loudness measured over everything processed so far.
+    pub fn measured_lufs(&self) -> Option<f32> {
+        self.meter.integrated()
+    }
+
+    /// Measure `samples` and apply the normalization gain in place.
+    pub fn process(&mut self, samples: &mut [f32], settings: &LoudnessSettings) {
+        self.meter.push(samples);
+
+        let target_db = if settings.enabled {
+            self.known_lufs
+                .or_else(|| self.meter.integrated())
+                .map(|lufs| (settings.target_lufs - lufs).clamp(-MAX_CUT_DB, MAX_BOOST_DB))
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        };
+
+        let start_db = match self.gain_db {
+            Some(db) => db,
+            None if self.known_lufs.is_some() => target_db,
+            None => 0.0,
+        };
+        let frames = samples.len() / CHANNELS as usize;
+        let max_step = GAIN_SLEW_DB_PER_SEC * frames as f32 / SAMPLE_RATE as f32;
+        let end_db = start_db + (target_db - start_db).clamp(-max_step, max_step);
+        self.gain_db = Some(end_db);
+
+        let start = db_to_gain(start_db);
+        let end = db_to_gain(end_db);
+        if start == end {
+            if start != 1.0 {
+                samples.iter_mut().for_each(|s| *s *= start);
+            }
+            return;
+        }
+        let diff = (end - start) / frames.max(1) as f32;
+        for (i, frame) in samples.chunks_exact_mut(CHANNELS as usize).enumerate() {
+            let g = start + diff * i as f32;
+            frame.iter_mut().for_each(|s| *s *= g);
+        }
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
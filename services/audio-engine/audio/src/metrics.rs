@@ -12,6 +12,8 @@
 
 pub struct AudioMetrics {
     pub mixer_underruns: AtomicU64,
+    pub mixer_starved_frames: AtomicU64,
+    pub preroll_timeouts: AtomicU64,
     pub mixer_loops: AtomicU64,
     pub decoder_stalls: AtomicU64,
     pub stream_underruns: AtomicU64,
@@ -22,6 +24,8 @@ impl AudioMetrics {
     fn new() -> Self {
         Self {
             mixer_underruns: AtomicU64::new(0),
+            mixer_starved_frames: AtomicU64::new(0),
+            preroll_timeouts: AtomicU64::new(0),
             mixer_loops: AtomicU64::new(0),
             decoder_stalls: AtomicU64::new(0),
             stream_underruns: AtomicU64::new(0),
@@ -36,10 +40,14 @@ fn new() -> Self {
 #[cfg(feature = "telemetry")]
 struct AudioOtelMetrics {
     mixer_underruns: Counter<u64>,
+    mixer_starved_frames: Counter<u64>,
+    preroll_timeouts: Counter<u64>,
     decoder_stalls: Counter<u64>,
     stream_underruns: Counter<u64>,
     mixer_active_sources: UpDownCounter<i64>,
     mixer_processing_duration: Histogram<f64>,
+    mixer_worker_sessions: UpDownCounter<i64>,
+    mixer_worker_tick_duration: Histogram<f64>,
     decode_errors: Counter<u64>,
     session_active: UpDownCounter<i64>,
     track_lifecycle: Counter<u64>,
@@ -74,6 +82,14 @@ fn otel() -> &'static AudioOtelMetrics {
                 .u64_counter("audio_mixer_underruns_total")
                 .with_description("Total number of mixer underruns (starvation)")
                 .build(),
+            mixer_starved_frames: meter
+                .u64_counter("audio_mixer_starved_frames_total")
+                .with_description("Total number of frames a source spent starving")
+                .build(),
+            preroll_timeouts: meter
+                .u64_counter("audio_preroll_timeouts_total")
+                .with_description("Sources handed to the mixer before their pre-roll filled")
+                .build(),
             decoder_stalls: meter
                 .u64_counter("audio_decoder_stalls_total")
                 .with_description("Total number of decoder stalls (buffer full)")
@@ -94,6 +110,18 @@ fn otel() -> &'static AudioOtelMetrics {
                     0.001, 0.005, 0.010, 0.015, 0.020, 0.025, 0.030, 0.050, 0.100,
                 ])
                 .build(),
+            mixer_worker_sessions: meter
+                .i64_up_down_counter("audio_mixer_worker_sessions")
+                .with_description("Current number of sessions ticked by each mixer worker")
+                .build(),
+            mixer_worker_tick_duration: meter
+                .f64_histogram("audio_mixer_worker_tick_duration_seconds")
+                .with_description("Time a mixer worker takes to tick all of its sessions")
+                .with_unit("s")
+                .with_boundaries(vec![
+                    0.001, 0.005, 0.010, 0.015, 0.020, 0.025, 0.030, 0.050, 0.100,
+                ])
+                .build(),
             decode_errors: meter
                 .u64_counter("audio_decode_errors_total")
                 .with_description("Total number of audio decoding errors")
@@ -135,6 +163,22 @@ pub fn record_mixer_underrun() {
     otel().mixer_underruns.add(1, &[]);
 }
 
+pub fn record_mixer_starved_frame() {
+    AUDIO_METRICS
+        .mixer_starved_frames
+        .fetch_add(1, Ordering::Relaxed);
+    #[cfg(feature = "telemetry")]
+    otel().mixer_starved_frames.add(1, &[]);
+}
+
+pub fn record_preroll_timeout() {
+    AUDIO_METRICS
+        .preroll_timeouts
+        .fetch_add(1, Ordering::Relaxed);
+    #[cfg(feature = "telemetry")]
+    otel().preroll_timeouts.add(1, &[]);
+}
+
 pub fn record_mixer_buffer_depth(depth: usize) {
     AUDIO_METRICS
         .mixer_buffer_depth
@@ -172,6 +216,27 @@ pub fn record_mixer_processing_duration(duration_secs: f64) {
     otel().mixer_processing_duration.record(duration_secs, &[]);
 }
 
+pub fn inc_mixer_worker_sessions(worker: usize) {
+    #[cfg(feature = "telemetry")]
+    otel()
+        .mixer_worker_sessions
+        .add(1, &[KeyValue::new("worker", worker as i64)]);
+}
+
+pub fn dec_mixer_worker_sessions(worker: usize) {
+    #[cfg(feature = "telemetry")]
+    otel()
+        .mixer_worker_sessions
+        .add(-1, &[KeyValue::new("worker", worker as i64)]);
+}
+
+pub fn record_mixer_worker_tick_duration(worker: usize, duration_secs: f64) {
+    #[cfg(feature = "telemetry")]
+    otel()
+        .mixer_worker_tick_duration
+        .record(duration_secs, &[KeyValue::new("worker", worker as i64)]);
+}
+
 pub fn record_decode_error(error_type: &str) {
     #[cfg(feature = "telemetry")]
     otel()
@@ -1,26 +1,56 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
 use async_trait::async_trait;
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
 use mockall::automock;
-use opus::{Application, Channels, Encoder};
+use opus::{Application, Bitrate, Channels, Encoder, Signal};
 use ringbuf::traits::{Consumer, Observer, Producer};
 use tokio::sync::mpsc::Sender as TokioSender;
 
 use crate::{
     OpusProd, RingCons,
-    constant::{BUFFER_SIZE, SAMPLE_RATE},
-    frame_duration, metrics,
-    types::TrackId,
+    constant::{BUFFER_SIZE, CHANNELS, SAMPLE_RATE, UNDERRUN_FADE_MS},
+    crossfade::Fade,
+    dsp::DspChain,
+    ducking::{Ducker, SourceClass},
+    frame_duration,
+    limiter::TruePeakLimiter,
+    metrics, pan,
+    recorder::Recorder,
+    types::{DuckingSettings, EncoderSettings, OpusSignal, TrackDsp, TrackId},
 };
 
 pub enum MixerCommand {
     AddSource(TrackId, RingCons, TokioSender<TrackId>),
+    QueueSource(TrackId, RingCons, TokioSender<TrackId>, Follow),
+    SetRemaining(TrackId, u64),
     RemoveSource(TrackId),
+    FadeOutAndRemove(TrackId, Duration),
     SetVolume(TrackId, f32),
+    SetPan(TrackId, f32),
+    SetDsp(TrackId, TrackDsp),
+    SetSourceClass(TrackId, SourceClass),
+    SetPaused(TrackId, bool),
+    SetDucking(DuckingSettings),
+    SetRecorder(Option<Arc<Recorder>>),
+    SetEncoder(EncoderSettings),
     HasSource(TrackId, tokio::sync::oneshot::Sender<bool>),
     HasSources(Vec<TrackId>, tokio::sync::oneshot::Sender<HashSet<TrackId>>),
+    Positions(
+        Vec<TrackId>,
+        tokio::sync::oneshot::Sender<HashMap<TrackId, u64>>,
+    ),
+}
+
+/// Hold a source back until the track it follows ends, or until that track
+/// is within `crossfade_ms` of its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Follow {
+    pub after: TrackId,
+    pub crossfade_ms: u32,
 }
 
 struct ManagedSource {
@@ -29,125 +59,570 @@ struct ManagedSource {
     consumer: RingCons,
     current_volume: f32,
     target_volume: f32,
+    current_pan: f32,
+    target_pan: f32,
+    dsp: Option<DspChain>,
+    class: SourceClass,
+    /// Interleaved input samples read from `consumer`, before any DSP.
+    consumed: u64,
+    /// Expected value of `consumed` once the source runs out, if known.
+    length: Option<u64>,
+    /// Set while the source is queued behind another one and not mixed yet.
+    follows: Option<Follow>,
+    fade: Option<Fade>,
+    /// Fading out to be dropped. The session has already let go of the
+    /// track, so it is invisible to lookups and never reports an end.
+    removing: bool,
+    /// Input is paused upstream, so running dry is expected.
+    paused: bool,
+    /// Ran dry while its input is still open.
+    starving: bool,
+    /// Last frame handed out, where an underrun fade starts from.
+    last: [f32; CHANNELS as usize],
 }
 
-fn mixer_thread(cmd_rx: Receiver<MixerCommand>, mut output: OpusProd) {
-    let mut sources: Vec<ManagedSource> = Vec::new();
-    let mut encoder = Encoder::new(SAMPLE_RATE, Channels::Stereo, Application::Audio)
-        .expect("Failed to create Opus encoder");
+impl ManagedSource {
+    /// Milliseconds of source audio played since the source was added.
+    fn position_ms(&self) -> u64 {
+        self.consumed * 1000 / (SAMPLE_RATE as u64 * CHANNELS as u64)
+    }
 
-    let frame_dur = frame_duration();
-    let mut next_tick = std::time::Instant::now();
+    fn input_finished(&self) -> bool {
+        !self.consumer.write_is_held()
+            && self.consumer.is_empty()
+            && self.dsp.as_ref().is_none_or(|d| d.is_drained())
+    }
 
-    loop {
-        let loop_start = std::time::Instant::now();
-
-        while let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                MixerCommand::AddSource(track_id, consumer, end_tx) => {
-                    tracing::debug!(track_id = %track_id, "Adding source to mixer");
-                    sources.push(ManagedSource {
-                        track_id,
-                        consumer,
-                        end_tx,
-                        current_volume: 1.0,
-                        target_volume: 1.0,
-                    });
-                    metrics::inc_mixer_active_sources();
+    /// Pull the next block into `scratch` and mix it into `out` (same length).
+    /// Returns the samples written and whether the source has finished.
+    fn mix_into(
+        &mut self,
+        scratch: &mut [f32],
+        out: &mut [f32],
+        duck: (f32, f32),
+    ) -> (usize, bool) {
+        let c = match self.dsp.as_mut() {
+            Some(chain) => {
+                let before = chain.consumed();
+                let c = chain.fill(&mut self.consumer, scratch);
+                self.consumed += chain.consumed() - before;
+                c
+            }
+            None => {
+                let c = self.consumer.pop_slice(scratch);
+                self.consumed += c as u64;
+                c
+            }
+        };
+
+        // Back from an underrun: fade in instead of jumping to full level.
+        if self.starving && c > 0 {
+            self.starving = false;
+            if self.fade.is_none() {
+                self.fade = Some(Fade::fade_in(ms_to_frames(UNDERRUN_FADE_MS)));
+            }
+        }
+
+        let mut faded_out = false;
+        if let Some(fade) = self.fade.as_mut() {
+            fade.apply(&mut scratch[..c]);
+            if fade.is_done() {
+                faded_out = fade.is_fade_out();
+                self.fade = None;
+            }
+        }
+
+        let channels = self.last.len();
+        if c >= channels {
+            self.last.copy_from_slice(&scratch[c - channels..c]);
+        }
+
+        let ended = faded_out || self.input_finished();
+        let n = if c < scratch.len() && !ended {
+            self.conceal_underrun(scratch, c)
+        } else {
+            c
+        };
+
+        if self.current_pan != 0.0 || self.target_pan != 0.0 {
+            pan::apply(
+                &mut scratch[..n],
+                pan::gains(self.current_pan),
+                pan::gains(self.target_pan),
+            );
+            self.current_pan = self.target_pan;
+        }
+
+        let (duck_start, duck_end) = duck;
+        let (start_vol, end_vol) = if self.class == SourceClass::Music {
+            (
+                self.current_volume * duck_start,
+                self.target_volume * duck_end,
+            )
+        } else {
+            (self.current_volume, self.target_volume)
+        };
+
+        if start_vol == end_vol {
+            // Fast path: Constant volume (easy to vectorize)
+            for i in 0..n {
+                out[i] += scratch[i] * start_vol;
+            }
+        } else {
+            // Ramp path: Linearly interpolate
+            let diff = (end_vol - start_vol) / n as f32;
+            for i in 0..n {
+                let current_v = start_vol + (diff * i as f32);
+                out[i] += scratch[i] * current_v;
+            }
+            self.current_volume = self.target_volume;
+        }
+
+        (c, ended)
+    }
+
+    /// The source ran dry with its input still open. Instead of cutting to
+    /// silence, ramp the last frame down over the start of the gap. Returns
+    /// how much of `scratch` now holds audio.
+    fn conceal_underrun(&mut self, scratch: &mut [f32], c: usize) -> usize {
+        if !self.paused {
+            metrics::record_mixer_starved_frame();
+        }
+        if self.starving {
+            return c;
+        }
+        self.starving = true;
+        if !self.paused {
+            metrics::record_mixer_underrun();
+        }
+
+        let channels = self.last.len();
+        let frames = ms_to_frames(UNDERRUN_FADE_MS) as usize;
+        let end = (c + frames * channels).min(scratch.len());
+        for (i, sample) in scratch[c..end].iter_mut().enumerate() {
+            let gain = 1.0 - (i / channels + 1) as f32 / frames as f32;
+            *sample = self.last[(c + i) % channels] * gain;
+        }
+        end
+    }
+}
+
+const fn ms_to_frames(ms: u64) -> u64 {
+    ms * SAMPLE_RATE as u64 / 1000
+}
+
+/// The source a command for `track_id` applies to. A track restarted while
+/// its old source is still fading out has both in the mixer.
+fn find_live(sources: &mut [ManagedSource], track_id: TrackId) -> Option<&mut ManagedSource> {
+    sources
+        .iter_mut()
+        .find(|s| s.track_id == track_id && !s.removing)
+}
+
+fn fade_out_and_remove(sources: &mut Vec<ManagedSource>, track_id: TrackId, duration: Duration) {
+    let frames = ms_to_frames(duration.as_millis() as u64);
+    if let Some(source) = find_live(sources, track_id) {
+        let gain = source.fade.as_ref().map_or(1.0, Fade::gain);
+        source.fade = Some(Fade::fade_out_from(frames, gain));
+        source.removing = true;
+    }
+
+    // Whatever was queued behind it starts under the fade.
+    for source in sources.iter_mut() {
+        if source.follows.is_some_and(|f| f.after == track_id) {
+            source.follows = None;
+        }
+    }
+
+    // A held source has not made a sound yet, so there is nothing to fade.
+    let prev_len = sources.len();
+    sources.retain(|s| !(s.removing && (frames == 0 || s.follows.is_some())));
+    for _ in sources.len()..prev_len {
+        metrics::dec_mixer_active_sources();
+    }
+}
+
+/// Release queued sources whose leader is gone, and start crossfades for
+/// those whose leader is about to end.
+fn start_transitions(sources: &mut [ManagedSource]) {
+    for i in 0..sources.len() {
+        let Some(follow) = sources[i].follows else {
+            continue;
+        };
+        let Some(j) = sources.iter().position(|s| s.track_id == follow.after) else {
+            sources[i].follows = None;
+            continue;
+        };
+
+        let leader = &sources[j];
+        if follow.crossfade_ms == 0 || leader.follows.is_some() || leader.fade.is_some() {
+            continue;
+        }
+        let Some(length) = leader.length else {
+            continue;
+        };
+        let remaining = length.saturating_sub(leader.consumed) / CHANNELS as u64;
+        if remaining == 0 || remaining > ms_to_frames(follow.crossfade_ms as u64) {
+            continue;
+        }
+
+        sources[j].fade = Some(Fade::fade_out(remaining));
+        sources[i].fade = Some(Fade::fade_in(remaining));
+        sources[i].follows = None;
+    }
+}
+
+fn application_for(signal: OpusSignal) -> Application {
+    match signal {
+        OpusSignal::Voice => Application::Voip,
+        OpusSignal::Auto | OpusSignal::Music => Application::Audio,
+    }
+}
+
+fn new_encoder(settings: EncoderSettings) -> Encoder {
+    let mut encoder = Encoder::new(
+        SAMPLE_RATE,
+        Channels::Stereo,
+        application_for(settings.signal),
+    )
+    .expect("Failed to create Opus encoder");
+    if let Err(e) = configure_encoder(&mut encoder, settings) {
+        tracing::warn!(error = %e, "Failed to apply Opus encoder settings");
+    }
+    encoder
+}
+
+/// Apply everything libopus can change on a running encoder.
+fn configure_encoder(encoder: &mut Encoder, settings: EncoderSettings) -> opus::Result<()> {
+    let bitrate = settings
+        .bitrate_bps()
+        .map_or(Bitrate::Auto, |bps| Bitrate::Bits(bps as i32));
+    encoder.set_bitrate(bitrate)?;
+    encoder.set_complexity(settings.complexity().into())?;
+    encoder.set_inband_fec(settings.fec)?;
+    encoder.set_packet_loss_perc(settings.expected_packet_loss_pct().into())?;
+    encoder.set_signal(match settings.signal {
+        OpusSignal::Auto => Signal::Auto,
+        OpusSignal::Voice => Signal::Voice,
+        OpusSignal::Music => Signal::Music,
+    })
+}
+
+/// One voice session's mixing state. Lives on a pool worker, which ticks it
+/// once per frame.
+struct MixerSession {
+    cmd_rx: Receiver<MixerCommand>,
+    output: OpusProd,
+    sources: Vec<ManagedSource>,
+    ducker: Ducker,
+    limiter: TruePeakLimiter,
+    encoder: Encoder,
+    encoder_settings: EncoderSettings,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl MixerSession {
+    fn new(cmd_rx: Receiver<MixerCommand>, output: OpusProd) -> Self {
+        Self {
+            cmd_rx,
+            output,
+            sources: Vec::new(),
+            ducker: Ducker::new(DuckingSettings::default()),
+            limiter: TruePeakLimiter::new(),
+            encoder: new_encoder(EncoderSettings::default()),
+            encoder_settings: EncoderSettings::default(),
+            recorder: None,
+        }
+    }
+
+    /// Apply pending commands and mix one frame. Returns false once the
+    /// session is over: its output consumer or its `ThreadMixer` was dropped.
+    fn tick(&mut self) -> bool {
+        let connected = loop {
+            match self.cmd_rx.try_recv() {
+                Ok(cmd) => self.handle_command(cmd),
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Disconnected) => break false,
+            }
+        };
+
+        if !self.output.read_is_held() {
+            tracing::warn!("Output consumer dropped, ending mixer session");
+            return false;
+        }
+        if !connected {
+            tracing::debug!("Mixer handle dropped, ending mixer session");
+            return false;
+        }
+
+        if !self.sources.is_empty() {
+            let mix_start = std::time::Instant::now();
+            self.mix_frame();
+            metrics::record_mixer_processing_duration(mix_start.elapsed().as_secs_f64());
+        }
+        true
+    }
+
+    fn handle_command(&mut self, cmd: MixerCommand) {
+        let sources = &mut self.sources;
+        match cmd {
+            MixerCommand::AddSource(track_id, consumer, end_tx) => {
+                tracing::debug!(track_id = %track_id, "Adding source to mixer");
+                sources.push(ManagedSource {
+                    track_id,
+                    consumer,
+                    end_tx,
+                    current_volume: 1.0,
+                    target_volume: 1.0,
+                    current_pan: 0.0,
+                    target_pan: 0.0,
+                    dsp: None,
+                    class: SourceClass::Other,
+                    consumed: 0,
+                    length: None,
+                    follows: None,
+                    fade: None,
+                    removing: false,
+                    paused: false,
+                    starving: false,
+                    last: [0.0; CHANNELS as usize],
+                });
+                metrics::inc_mixer_active_sources();
+            }
+            MixerCommand::QueueSource(track_id, consumer, end_tx, follow) => {
+                tracing::debug!(track_id = %track_id, after = %follow.after, "Queueing source in mixer");
+                sources.push(ManagedSource {
+                    track_id,
+                    consumer,
+                    end_tx,
+                    current_volume: 1.0,
+                    target_volume: 1.0,
+                    current_pan: 0.0,
+                    target_pan: 0.0,
+                    dsp: None,
+                    class: SourceClass::Other,
+                    consumed: 0,
+                    length: None,
+                    follows: Some(follow),
+                    fade: None,
+                    removing: false,
+                    paused: false,
+                    starving: false,
+                    last: [0.0; CHANNELS as usize],
+                });
+                metrics::inc_mixer_active_sources();
+            }
+            MixerCommand::SetRemaining(track_id, remaining_ms) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    source.length =
+                        Some(source.consumed + ms_to_frames(remaining_ms) * CHANNELS as u64);
                 }
-                MixerCommand::RemoveSource(track_id) => {
-                    let prev_len = sources.len();
-                    sources.retain(|s| s.track_id != track_id);
-                    if sources.len() < prev_len {
-                        tracing::debug!(track_id = %track_id, "Removed source from mixer");
-                        metrics::dec_mixer_active_sources();
-                    }
+            }
+            MixerCommand::RemoveSource(track_id) => {
+                let prev_len = sources.len();
+                sources.retain(|s| s.track_id != track_id);
+                if sources.len() < prev_len {
+                    tracing::debug!(track_id = %track_id, "Removed source from mixer");
+                }
+                for _ in sources.len()..prev_len {
+                    metrics::dec_mixer_active_sources();
                 }
-                MixerCommand::SetVolume(track_id, volume) => {
-                    if let Some(source) = sources.iter_mut().find(|s| s.track_id == track_id) {
-                        source.target_volume = volume;
+            }
+            MixerCommand::FadeOutAndRemove(track_id, duration) => {
+                tracing::debug!(track_id = %track_id, duration = ?duration, "Fading out source");
+                fade_out_and_remove(sources, track_id, duration);
+            }
+            MixerCommand::SetVolume(track_id, volume) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    source.target_volume = volume;
+                }
+            }
+            MixerCommand::SetPan(track_id, pan) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    source.target_pan = pan;
+                }
+            }
+            MixerCommand::SetDsp(track_id, params) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    match source.dsp.as_mut() {
+                        Some(chain) => chain.reconfigure(params),
+                        None if !params.is_identity() => {
+                            source.dsp = Some(DspChain::new(params));
+                        }
+                        None => {}
                     }
                 }
-                MixerCommand::HasSource(track_id, resp_tx) => {
-                    let has_source = sources.iter().any(|s| s.track_id == track_id);
-                    let _ = resp_tx.send(has_source);
+            }
+            MixerCommand::SetSourceClass(track_id, class) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    source.class = class;
                 }
-                MixerCommand::HasSources(track_ids, resp_tx) => {
-                    let present: HashSet<TrackId> = track_ids
-                        .into_iter()
-                        .filter(|id| sources.iter().any(|s| s.track_id == *id))
-                        .collect();
-                    let _ = resp_tx.send(present);
+            }
+            MixerCommand::SetPaused(track_id, paused) => {
+                if let Some(source) = find_live(sources, track_id) {
+                    source.paused = paused;
                 }
             }
+            MixerCommand::SetDucking(settings) => {
+                self.ducker.configure(settings);
+            }
+            MixerCommand::SetEncoder(settings) => {
+                self.set_encoder(settings);
+            }
+            MixerCommand::SetRecorder(recorder) => {
+                self.recorder = recorder;
+            }
+            MixerCommand::HasSource(track_id, resp_tx) => {
+                let has_source = sources
+                    .iter()
+                    .any(|s| s.track_id == track_id && !s.removing);
+                let _ = resp_tx.send(has_source);
+            }
+            MixerCommand::HasSources(track_ids, resp_tx) => {
+                let present: HashSet<TrackId> = track_ids
+                    .into_iter()
+                    .filter(|id| sources.iter().any(|s| s.track_id == *id && !s.removing))
+                    .collect();
+                let _ = resp_tx.send(present);
+            }
+            MixerCommand::Positions(track_ids, resp_tx) => {
+                let positions: HashMap<TrackId, u64> = track_ids
+                    .into_iter()
+                    .filter_map(|id| {
+                        sources
+                            .iter()
+                            .find(|s| s.track_id == id && !s.removing)
+                            .map(|s| (id, s.position_ms()))
+                    })
+                    .collect();
+                let _ = resp_tx.send(positions);
+            }
         }
+    }
 
-        if sources.is_empty() {
-            std::thread::sleep(std::time::Duration::from_millis(10));
-            next_tick = std::time::Instant::now();
-            continue;
+    fn set_encoder(&mut self, settings: EncoderSettings) {
+        if settings == self.encoder_settings {
+            return;
+        }
+        // libopus refuses a new application once it has encoded a frame, so
+        // switching between voice and audio mode needs a fresh encoder.
+        if application_for(settings.signal) != application_for(self.encoder_settings.signal) {
+            self.encoder = new_encoder(settings);
+        } else if let Err(e) = configure_encoder(&mut self.encoder, settings) {
+            tracing::warn!(error = %e, "Failed to apply Opus encoder settings");
         }
+        tracing::debug!(settings = ?settings, "Updated Opus encoder settings");
+        self.encoder_settings = settings;
+    }
+
+    fn mix_frame(&mut self) {
+        let sources = &mut self.sources;
+        start_transitions(sources);
 
         let mut mixed_buffer = [0f32; BUFFER_SIZE];
-        let mut ended_sources: Vec<TrackId> = Vec::new();
+        let mut ended_sources: Vec<(TrackId, usize)> = Vec::new();
+        let mut faded_out: Vec<TrackId> = Vec::new();
 
         let mut source_buffer = [0f32; BUFFER_SIZE];
 
-        for source in sources.iter_mut() {
-            if !source.consumer.write_is_held() && source.consumer.is_empty() {
-                ended_sources.push(source.track_id);
+        let speech_active = sources
+            .iter()
+            .any(|s| s.class == SourceClass::Speech && !s.consumer.is_empty());
+        let duck = self.ducker.step(speech_active);
+
+        for source in sources.iter_mut().filter(|s| s.follows.is_none()) {
+            let (c, ended) = source.mix_into(&mut source_buffer, &mut mixed_buffer, duck);
+            if source.removing {
+                // A source that ran dry has nothing left to fade.
+                if ended || c == 0 {
+                    faded_out.push(source.track_id);
+                }
+            } else if ended {
+                ended_sources.push((source.track_id, c));
                 let _ = source.end_tx.try_send(source.track_id);
-                continue;
             }
+        }
 
-            let c = source.consumer.pop_slice(&mut source_buffer);
-
-            let start_vol = source.current_volume;
-            let end_vol = source.target_volume;
-
-            if start_vol == end_vol {
-                // Fast path: Constant volume (easy to vectorize)
-                for i in 0..c {
-                    mixed_buffer[i] += source_buffer[i] * start_vol;
-                }
-            } else {
-                // Ramp path: Linearly interpolate
-                let diff = (end_vol - start_vol) / c as f32;
-                for i in 0..c {
-                    let current_v = start_vol + (diff * i as f32);
-                    mixed_buffer[i] += source_buffer[i] * current_v;
-                }
-                source.current_volume = end_vol;
+        // Gapless hand-over: a queued source picks up in the same frame its
+        // leader ran out.
+        for &(track_id, offset) in &ended_sources {
+            if let Some(next) = sources
+                .iter_mut()
+                .find(|s| s.follows.is_some_and(|f| f.after == track_id))
+            {
+                next.follows = None;
+                next.mix_into(
+                    &mut source_buffer[offset..],
+                    &mut mixed_buffer[offset..],
+                    duck,
+                );
             }
         }
 
-        for track_id in ended_sources {
-            sources.retain(|s| s.track_id != track_id);
+        for (track_id, _) in ended_sources {
+            sources.retain(|s| s.track_id != track_id || s.removing);
             metrics::dec_mixer_active_sources();
         }
-
-        if !output.read_is_held() {
-            tracing::warn!("Output consumer dropped, ending mixer thread");
-            break;
+        if !faded_out.is_empty() {
+            let prev_len = sources.len();
+            sources.retain(|s| !(s.removing && faded_out.contains(&s.track_id)));
+            for _ in sources.len()..prev_len {
+                metrics::dec_mixer_active_sources();
+            }
         }
 
-        let mut final_buffer = [0f32; BUFFER_SIZE];
-        for i in 0..BUFFER_SIZE {
-            final_buffer[i] = mixed_buffer[i].clamp(-1.0, 1.0);
-        }
+        self.limiter.process(&mut mixed_buffer);
 
         let mut out_buf = [0u8; 4000];
-        match encoder.encode_float(&final_buffer, &mut out_buf) {
+        match self.encoder.encode_float(&mixed_buffer, &mut out_buf) {
             Ok(len) => {
-                let _ = output.try_push(bytes::Bytes::copy_from_slice(&out_buf[..len]));
+                let packet = bytes::Bytes::copy_from_slice(&out_buf[..len]);
+                if let Some(recorder) = self.recorder.as_ref() {
+                    recorder.push(packet.clone());
+                }
+                let _ = self.output.try_push(packet);
             }
             Err(e) => {
                 tracing::error!("Opus encode error: {}", e);
             }
         }
+    }
+}
+
+/// What a worker needs to set up a session on its own thread.
+type NewSession = (Receiver<MixerCommand>, OpusProd);
+
+/// Ticks every session assigned to it on one shared frame clock. Parks while
+/// it has no sessions.
+fn worker_thread(id: usize, session_rx: Receiver<NewSession>, load: Arc<AtomicUsize>) {
+    let frame_dur = frame_duration();
+    let mut sessions: Vec<MixerSession> = Vec::new();
+    let mut next_tick = std::time::Instant::now();
 
-        let processing_duration = loop_start.elapsed();
-        metrics::record_mixer_processing_duration(processing_duration.as_secs_f64());
+    loop {
+        if sessions.is_empty() {
+            let Ok((cmd_rx, output)) = session_rx.recv() else {
+                return;
+            };
+            sessions.push(MixerSession::new(cmd_rx, output));
+            next_tick = std::time::Instant::now();
+        }
+        sessions.extend(
+            session_rx
+                .try_iter()
+                .map(|(cmd_rx, output)| MixerSession::new(cmd_rx, output)),
+        );
+
+        let tick_start = std::time::Instant::now();
+        let prev_len = sessions.len();
+        sessions.retain_mut(MixerSession::tick);
+        for _ in sessions.len()..prev_len {
+            load.fetch_sub(1, Ordering::Relaxed);
+            metrics::dec_mixer_worker_sessions(id);
+        }
+
+        let processing_duration = tick_start.elapsed();
+        metrics::record_mixer_worker_tick_duration(id, processing_duration.as_secs_f64());
 
         let now = std::time::Instant::now();
         next_tick += frame_dur;
@@ -156,39 +631,116 @@ fn mixer_thread(cmd_rx: Receiver<MixerCommand>, mut output: OpusProd) {
             std::thread::sleep(next_tick - now);
         } else {
             tracing::warn!(
+                worker = id,
+                sessions = sessions.len(),
                 duration_ms = processing_duration.as_millis(),
                 budget_ms = frame_dur.as_millis(),
-                "Mixer loop exceeded time budget"
+                "Mixer worker exceeded time budget"
             );
             next_tick = std::time::Instant::now();
         }
     }
 }
 
+struct Worker {
+    session_tx: Sender<NewSession>,
+    /// Sessions currently assigned to the worker.
+    load: Arc<AtomicUsize>,
+}
+
+/// A fixed set of mixer threads shared by all sessions. Each new mixer goes
+/// to the worker with the fewest sessions.
+pub struct MixerPool {
+    workers: Vec<Worker>,
+}
+
+impl Default for MixerPool {
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+impl MixerPool {
+    pub fn new(workers: usize) -> Self {
+        let workers = (0..workers.max(1))
+            .map(|id| {
+                let (session_tx, session_rx) = crossbeam::channel::unbounded();
+                let load = Arc::new(AtomicUsize::new(0));
+                let worker_load = load.clone();
+                std::thread::Builder::new()
+                    .name(format!("mixer-{id}"))
+                    .spawn(move || worker_thread(id, session_rx, worker_load))
+                    .expect("Failed to spawn mixer worker");
+                Worker { session_tx, load }
+            })
+            .collect();
+        Self { workers }
+    }
+
+    pub fn create_mixer(&self, output: OpusProd) -> ThreadMixer {
+        let (cmd_tx, cmd_rx) = crossbeam::channel::unbounded();
+
+        let (id, worker) = self
+            .workers
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, w)| w.load.load(Ordering::Relaxed))
+            .expect("mixer pool has no workers");
+        worker.load.fetch_add(1, Ordering::Relaxed);
+        metrics::inc_mixer_worker_sessions(id);
+        let _ = worker.session_tx.send((cmd_rx, output));
+
+        ThreadMixer { cmd_tx }
+    }
+}
+
 pub type ArcMixer = Arc<dyn Mixer>;
 
 #[automock]
 #[async_trait]
 pub trait Mixer: Send + Sync + 'static {
     fn add_source(&self, track_id: TrackId, consumer: RingCons, end_tx: TokioSender<TrackId>);
+    /// Add a source that stays silent until `follow.after` ends or starts
+    /// fading out. Removing the leader releases it immediately.
+    fn queue_source(
+        &self,
+        track_id: TrackId,
+        consumer: RingCons,
+        end_tx: TokioSender<TrackId>,
+        follow: Follow,
+    );
+    /// How much audio (ms) is left in a source, used to time crossfades.
+    fn set_remaining(&self, track_id: TrackId, remaining_ms: u64);
     fn remove_source(&self, track_id: TrackId);
+    /// Ramp a source down to silence over `duration` and then drop it,
+    /// without reporting an end. A zero duration removes it at once.
+    fn fade_out_and_remove(&self, track_id: TrackId, duration: Duration);
     fn set_volume(&self, track_id: TrackId, volume: f32);
+    /// Constant-power stereo position, from -1.0 (left) to 1.0 (right).
+    fn set_pan(&self, track_id: TrackId, pan: f32);
+    fn set_dsp(&self, track_id: TrackId, dsp: TrackDsp);
+    fn set_source_class(&self, track_id: TrackId, class: SourceClass);
+    /// Whether a source's input is paused upstream. A paused source drains
+    /// like a starving one but is not counted as an underrun.
+    fn set_paused(&self, track_id: TrackId, paused: bool);
+    fn set_ducking(&self, settings: DuckingSettings);
+    /// Copy every encoded output frame into `recorder`, or stop with `None`.
+    fn set_recorder(&self, recorder: Option<Arc<Recorder>>);
+    fn set_encoder(&self, settings: EncoderSettings);
     async fn has_source(&self, track_id: TrackId) -> bool;
     async fn has_sources(&self, track_ids: Vec<TrackId>) -> HashSet<TrackId>;
+    /// Elapsed playback (ms) of each track currently in the mixer, measured
+    /// from when it was added.
+    async fn positions(&self, track_ids: Vec<TrackId>) -> HashMap<TrackId, u64>;
 }
 
 pub struct ThreadMixer {
     cmd_tx: Sender<MixerCommand>,
 }
 
-pub fn create_thread_mixer(output: OpusProd) -> ThreadMixer {
-    let (cmd_tx, cmd_rx) = crossbeam::channel::unbounded();
-
-    std::thread::spawn(move || {
-        mixer_thread(cmd_rx, output);
-    });
-
-    ThreadMixer { cmd_tx }
+/// A mixer for one session, ticked by `pool`.
+pub fn create_thread_mixer(pool: &MixerPool, output: OpusProd) -> ThreadMixer {
+    pool.create_mixer(output)
 }
 
 #[async_trait]
@@ -199,14 +751,68 @@ fn add_source(&self, track_id: TrackId, consumer: RingCons, end_tx: TokioSender<
             .send(MixerCommand::AddSource(track_id, consumer, end_tx));
     }
 
+    fn queue_source(
+        &self,
+        track_id: TrackId,
+        consumer: RingCons,
+        end_tx: TokioSender<TrackId>,
+        follow: Follow,
+    ) {
+        let _ = self.cmd_tx.send(MixerCommand::QueueSource(
+            track_id, consumer, end_tx, follow,
+        ));
+    }
+
+    fn set_remaining(&self, track_id: TrackId, remaining_ms: u64) {
+        let _ = self
+            .cmd_tx
+            .send(MixerCommand::SetRemaining(track_id, remaining_ms));
+    }
+
     fn remove_source(&self, track_id: TrackId) {
         let _ = self.cmd_tx.send(MixerCommand::RemoveSource(track_id));
     }
 
+    fn fade_out_and_remove(&self, track_id: TrackId, duration: Duration) {
+        let _ = self
+            .cmd_tx
+            .send(MixerCommand::FadeOutAndRemove(track_id, duration));
+    }
+
     fn set_volume(&self, track_id: TrackId, volume: f32) {
         let _ = self.cmd_tx.send(MixerCommand::SetVolume(track_id, volume));
     }
 
+    fn set_pan(&self, track_id: TrackId, pan: f32) {
+        let _ = self.cmd_tx.send(MixerCommand::SetPan(track_id, pan));
+    }
+
+    fn set_dsp(&self, track_id: TrackId, dsp: TrackDsp) {
+        let _ = self.cmd_tx.send(MixerCommand::SetDsp(track_id, dsp));
+    }
+
+    fn set_source_class(&self, track_id: TrackId, class: SourceClass) {
+        let _ = self
+            .cmd_tx
+            .send(MixerCommand::SetSourceClass(track_id, class));
+    }
+
+    fn set_paused(&self, track_id: TrackId, paused: bool) {
+        let _ = self.cmd_tx.send(MixerCommand::SetPaused(track_id, paused));
+    }
+
+    fn set_ducking(&self, settings: DuckingSettings) {
+        let _ = self.cmd_tx.send(MixerCommand::SetDucking(settings));
+    }
+
+    fn set_recorder(&self, recorder: Option<Arc<Recorder>>) {
+        let _ = self.cmd_tx.send(MixerCommand::SetRecorder(recorder));
+    }
+
+    fn set_encoder(&self, settings: EncoderSettings) {
+        let _ = self.cmd_tx.send(MixerCommand::SetEncoder(settings));
+    }
+
     async fn has_source(&self, track_id: TrackId) -> bool {
         let (resp_tx, resp_rx) = tokio::sync::oneshot::channel();
         let _ = self.cmd_tx.send(MixerCommand::HasSource(track_id, resp_tx));
@@ -224,4 +830,110 @@ async fn has_sources(&self, track_ids: Vec<TrackId>) -> HashSet<TrackId> {
             _ => HashSet::new(),
         }
     }
+
+    async fn positions(&self, track_ids: Vec<TrackId>) -> HashMap<TrackId, u64> {
+        let (resp_tx, resp_rx) = tokio::sync::oneshot::channel();
+        let _ = self.cmd_tx.send(MixerCommand::Positions(track_ids, resp_tx));
+        match tokio::time::timeout(std::time::Duration::from_secs(2), resp_rx).await {
+            Ok(Ok(positions)) => positions,
+            _ => HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_ringbuf_pair;
+
+    const FADE_SAMPLES: usize = ms_to_frames(UNDERRUN_FADE_MS) as usize * CHANNELS as usize;
+
+    fn source(consumer: RingCons) -> ManagedSource {
+        ManagedSource {
+            track_id: TrackId::from(1),
+            consumer,
+            end_tx: tokio::sync::mpsc::channel(1).0,
+            current_volume: 1.0,
+            target_volume: 1.0,
+            current_pan: 0.0,
+            target_pan: 0.0,
+            dsp: None,
+            class: SourceClass::Other,
+            consumed: 0,
+            length: None,
+            follows: None,
+            fade: None,
+            removing: false,
+            paused: false,
+            starving: false,
+            last: [0.0; CHANNELS as usize],
+        }
+    }
+
+    fn mix(source: &mut ManagedSource) -> (Vec<f32>, usize, bool) {
+        let mut scratch = vec![0.0; BUFFER_SIZE];
+        let mut out = vec![0.0; BUFFER_SIZE];
+        let (c, ended) = source.mix_into(&mut scratch, &mut out, (1.0, 1.0));
+        (out, c, ended)
+    }
+
+    #[test]
+    fn underrun_ramps_the_last_frame_down_over_5ms() {
+        let (mut prod, cons) = create_ringbuf_pair();
+        let mut source = source(cons);
+        let half = BUFFER_SIZE / 2;
+        prod.push_slice(&vec![0.5; half]);
+
+        let (out, c, ended) = mix(&mut source);
+        assert_eq!(c, half);
+        assert!(!ended);
+        assert!(source.starving);
+
+        assert!(out[..half].iter().all(|&s| s == 0.5));
+        let ramp = &out[half..half + FADE_SAMPLES];
+        assert!(ramp[0] > 0.0 && ramp[0] < 0.5);
+        assert!(ramp.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(ramp[FADE_SAMPLES - 1], 0.0);
+        assert!(out[half + FADE_SAMPLES..].iter().all(|&s| s == 0.0));
+
+        // Still dry: silence, not a second ramp.
+        let (out, c, ended) = mix(&mut source);
+        assert_eq!(c, 0);
+        assert!(!ended);
+        assert!(out.iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn starving_source_fades_back_in_once_audio_arrives() {
+        let (mut prod, cons) = create_ringbuf_pair();
+        let mut source = source(cons);
+
+        let (_, c, ended) = mix(&mut source);
+        assert_eq!(c, 0);
+        assert!(!ended);
+        assert!(source.starving);
+
+        prod.push_slice(&vec![0.5; BUFFER_SIZE]);
+        let (out, c, _) = mix(&mut source);
+        assert_eq!(c, BUFFER_SIZE);
+        assert!(!source.starving);
+        assert!(out[0] < 0.5);
+        assert!(out[..FADE_SAMPLES].windows(2).all(|w| w[1] >= w[0]));
+        assert!(out[FADE_SAMPLES..].iter().all(|&s| s == 0.5));
+    }
+
+    #[test]
+    fn ended_source_is_not_concealed() {
+        let (mut prod, cons) = create_ringbuf_pair();
+        let mut source = source(cons);
+        let half = BUFFER_SIZE / 2;
+        prod.push_slice(&vec![0.5; half]);
+        drop(prod);
+
+        let (out, c, ended) = mix(&mut source);
+        assert_eq!(c, half);
+        assert!(ended);
+        assert!(!source.starving);
+        assert!(out[half..].iter().all(|&s| s == 0.0));
+    }
 }
@@ -0,0 +1,77 @@
+use std::io::{self, Write};
+
+use bytes::Bytes;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use crate::constant::{BUFFER_SIZE, CHANNELS, SAMPLE_RATE};
+
+/// Samples per channel in one encoded mixer frame.
+const FRAME_SAMPLES: u64 = (BUFFER_SIZE / CHANNELS as usize) as u64;
+/// Encoder lookahead the decoder should drop, per RFC 7845 (libopus default).
+const PRE_SKIP: u16 = 312;
+const STREAM_SERIAL: u32 = 0x7a61_6b6f;
+
+/// Muxes the mixer's encoded frames into an Ogg Opus stream.
+pub struct OggOpusWriter<W: Write> {
+    writer: PacketWriter<'static, W>,
+    /// Frames are written one behind so the last can be marked end-of-stream.
+    pending: Option<Bytes>,
+    granule: u64,
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    /// Start a stream by writing the OpusHead and OpusTags pages.
+    pub fn new(inner: W) -> io::Result<Self> {
+        let mut writer = PacketWriter::new(inner);
+        writer.write_packet(opus_head(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+        writer.write_packet(opus_tags(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+        Ok(Self {
+            writer,
+            pending: None,
+            granule: 0,
+        })
+    }
+
+    pub fn write_frame(&mut self, frame: Bytes) -> io::Result<()> {
+        if let Some(previous) = self.pending.replace(frame) {
+            self.write(previous, PacketWriteEndInfo::NormalPacket)?;
+        }
+        Ok(())
+    }
+
+    /// Close the stream and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(last) = self.pending.take() {
+            self.write(last, PacketWriteEndInfo::EndStream)?;
+        }
+        Ok(self.writer.into_inner())
+    }
+
+    fn write(&mut self, frame: Bytes, end: PacketWriteEndInfo) -> io::Result<()> {
+        self.granule += FRAME_SAMPLES;
+        self.writer
+            .write_packet(frame.to_vec(), STREAM_SERIAL, end, self.granule)
+    }
+}
+
+fn opus_head() -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1);
+    head.push(CHANNELS as u8);
+    head.extend_from_slice(&PRE_SKIP.to_le_bytes());
+    head.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    head
+}
+
+fn opus_tags() -> Vec<u8> {
+    let vendor = concat!("zako3-audio-engine ", env!("CARGO_PKG_VERSION"));
+    let mut tags = Vec::with_capacity(16 + vendor.len());
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    tags
+}
@@ -0,0 +1,26 @@
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+use crate::CHANNELS;
+
+/// Left and right gains for a constant-power pan. `pan` runs from -1.0 (hard
+/// left) to 1.0 (hard right); the centre leaves both channels untouched.
+pub fn gains(pan: f32) -> [f32; 2] {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    [angle.cos() * SQRT_2, angle.sin() * SQRT_2]
+}
+
+/// Pan an interleaved stereo block, moving linearly from `from` to `to`
+/// gains over its length so a change of position doesn't click.
+pub fn apply(samples: &mut [f32], from: [f32; 2], to: [f32; 2]) {
+    let channels = CHANNELS as usize;
+    let frames = samples.len() / channels;
+    if frames == 0 {
+        return;
+    }
+    for (i, frame) in samples.chunks_exact_mut(channels).enumerate() {
+        let t = i as f32 / frames as f32;
+        for (c, sample) in frame.iter_mut().enumerate() {
+            *sample *= from[c] + (to[c] - from[c]) * t;
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+
+use crate::{constant::frame_duration, ogg_opus::OggOpusWriter};
+
+/// Frames queued for a full recording's file before new ones are dropped,
+/// 10 seconds of audio.
+const FULL_QUEUE_FRAMES: usize = 500;
+
+/// Rolling buffer of the Opus packets a mixer sent to Discord. Only frames
+/// the mixer actually produced are kept, so silence between tracks is not
+/// part of a recording. A [`FullRecording`] attached to it is fed every
+/// frame as well.
+pub struct Recorder {
+    inner: Mutex<Inner>,
+    full: Mutex<Option<FullRecording>>,
+}
+
+struct Inner {
+    frames: VecDeque<Bytes>,
+    capacity: usize,
+}
+
+fn frames_for(duration: Duration) -> usize {
+    (duration.as_millis() / frame_duration().as_millis()).max(1) as usize
+}
+
+impl Recorder {
+    pub fn new(retention: Duration) -> Self {
+        let capacity = frames_for(retention);
+        Self {
+            inner: Mutex::new(Inner {
+                frames: VecDeque::with_capacity(capacity),
+                capacity,
+            }),
+            full: Mutex::new(None),
+        }
+    }
+
+    /// Change how much audio is kept, dropping the oldest frames if it shrank.
+    pub fn set_retention(&self, retention: Duration) {
+        let mut inner = self.inner.lock();
+        inner.capacity = frames_for(retention);
+        let excess = inner.frames.len().saturating_sub(inner.capacity);
+        inner.frames.drain(..excess);
+    }
+
+    /// Also write every frame from now on to `full`, or stop the current
+    /// full recording with `None`, which completes its file.
+    pub fn set_full(&self, full: Option<FullRecording>) {
+        *self.full.lock() = full;
+    }
+
+    pub fn is_recording_full(&self) -> bool {
+        self.full.lock().is_some()
+    }
+
+    pub fn push(&self, packet: Bytes) {
+        if let Some(full) = self.full.lock().as_ref() {
+            full.push(packet.clone());
+        }
+        let mut inner = self.inner.lock();
+        if inner.frames.len() >= inner.capacity {
+            inner.frames.pop_front();
+        }
+        inner.frames.push_back(packet);
+    }
+
+    /// Length of the audio currently held.
+    pub fn duration(&self) -> Duration {
+        frame_duration() * self.inner.lock().frames.len() as u32
+    }
+
+    /// Write the last `last` of audio (everything held if `None`) as an Ogg
+    /// Opus file.
+    pub fn export(&self, last: Option<Duration>) -> Vec<u8> {
+        let frames: Vec<Bytes> = {
+            let inner = self.inner.lock();
+            let held = inner.frames.len();
+            let take = last.map_or(held, |d| frames_for(d).min(held));
+            inner.frames.range(held - take..).cloned().collect()
+        };
+        write_ogg_opus(frames)
+    }
+}
+
+fn write_ogg_opus(frames: Vec<Bytes>) -> Vec<u8> {
+    // Writing into a Vec cannot fail.
+    let mut writer = OggOpusWriter::new(Vec::new()).expect("in-memory Ogg write failed");
+    for frame in frames {
+        let _ = writer.write_frame(frame);
+    }
+    writer.finish().unwrap_or_default()
+}
+
+/// Writes the frames pushed into it to an Ogg Opus file. The file is written
+/// on its own thread so the mixer never waits on the disk, and completed once
+/// this is dropped or `max_bytes` of Opus data has been written.
+pub struct FullRecording {
+    tx: SyncSender<Bytes>,
+}
+
+impl FullRecording {
+    pub fn create(path: impl Into<PathBuf>, max_bytes: u64) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let writer = OggOpusWriter::new(BufWriter::new(File::create(&path)?))?;
+        tracing::info!(path = %path.display(), "Started full session recording");
+
+        let (tx, rx) = sync_channel(FULL_QUEUE_FRAMES);
+        std::thread::spawn(move || write_full(writer, rx, max_bytes, &path));
+        Ok(Self { tx })
+    }
+
+    fn push(&self, frame: Bytes) {
+        match self.tx.try_send(frame) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("Full recording is behind; dropped a frame");
+            }
+        }
+    }
+}
+
+fn write_full(
+    mut writer: OggOpusWriter<BufWriter<File>>,
+    rx: Receiver<Bytes>,
+    max_bytes: u64,
+    path: &Path,
+) {
+    let mut written = 0u64;
+    while let Ok(frame) = rx.recv() {
+        written += frame.len() as u64;
+        if written > max_bytes {
+            tracing::warn!(path = %path.display(), "Full recording reached its size limit");
+            break;
+        }
+        if let Err(e) = writer.write_frame(frame) {
+            tracing::error!(path = %path.display(), error = %e, "Failed to write recording");
+            return;
+        }
+    }
+    match writer.finish().and_then(|mut w| w.flush()) {
+        Ok(()) => tracing::info!(path = %path.display(), "Finished full session recording"),
+        Err(e) => {
+            tracing::error!(path = %path.display(), error = %e, "Failed to finish recording")
+        }
+    }
+}
+
+/// How much a recording directory may hold. A limit left at `None` is not
+/// enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordingRetention {
+    /// Recordings last written longer ago than this are deleted.
+    pub max_age: Option<Duration>,
+    /// The oldest recordings are deleted until the rest fit in this many bytes.
+    pub max_total_bytes: Option<u64>,
+}
+
+/// Delete the Ogg files in `dir` that `retention` no longer allows and return
+/// how many were removed. A `dir` that does not exist yet holds nothing.
+pub fn prune_recordings(dir: &Path, retention: RecordingRetention) -> io::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "ogg") {
+            continue;
+        }
+        let meta = std::fs::metadata(&path)?;
+        if meta.is_file() {
+            files.push((meta.modified()?, meta.len(), path));
+        }
+    }
+    // Newest first, so what fits under the size limit is the latest audio.
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let now = SystemTime::now();
+    let mut kept_bytes = 0u64;
+    let mut over_size = false;
+    let mut removed = 0;
+    for (modified, len, path) in files {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let too_old = retention.max_age.is_some_and(|max| age > max);
+        over_size |= retention
+            .max_total_bytes
+            .is_some_and(|max| kept_bytes + len > max);
+        if !too_old && !over_size {
+            kept_bytes += len;
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                removed += 1;
+                tracing::debug!(path = %path.display(), "Deleted old recording");
+            }
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to delete recording")
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("zako3-recorder-{}", std::process::id()))
+            .join(name)
+    }
+
+    /// The audio packets of the file at `path`, once its writer has ended
+    /// the stream.
+    fn finished_packets(path: &Path) -> Vec<Vec<u8>> {
+        for _ in 0..200 {
+            let ogg = std::fs::read(path).unwrap_or_default();
+            let mut reader = ogg::reading::PacketReader::new(std::io::Cursor::new(ogg));
+            let mut packets = Vec::new();
+            while let Ok(Some(packet)) = reader.read_packet() {
+                let last = packet.last_in_stream();
+                packets.push(packet.data);
+                if last {
+                    // Skip OpusHead and OpusTags.
+                    return packets.split_off(2);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("recording was never finished");
+    }
+
+    #[test]
+    fn full_recording_keeps_every_frame_past_the_clip_window() {
+        let path = temp_path("full.ogg");
+        let recorder = Recorder::new(frame_duration());
+        recorder.set_full(Some(FullRecording::create(&path, u64::MAX).unwrap()));
+        for i in 0..10u8 {
+            recorder.push(vec![0xfc, i].into());
+        }
+        assert!(recorder.is_recording_full());
+        recorder.set_full(None);
+
+        let packets = finished_packets(&path);
+        std::fs::remove_file(&path).ok();
+        let expected: Vec<Vec<u8>> = (0..10u8).map(|i| vec![0xfc, i]).collect();
+        assert_eq!(packets, expected);
+        // The clip window still holds just the last frame.
+        assert_eq!(recorder.duration(), frame_duration());
+    }
+
+    #[test]
+    fn full_recording_stops_at_its_size_limit() {
+        let path = temp_path("capped.ogg");
+        let full = FullRecording::create(&path, 4).unwrap();
+        for i in 0..10u8 {
+            full.push(vec![0xfc, i].into());
+        }
+
+        let packets = finished_packets(&path);
+        drop(full);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(packets, vec![vec![0xfc, 0], vec![0xfc, 1]]);
+    }
+
+    /// A file of `len` bytes in `dir`, last written `age` ago.
+    fn aged_file(dir: &Path, name: &str, len: usize, age: Duration) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; len]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn pruning_deletes_recordings_past_their_age() {
+        let dir = temp_path("prune-age");
+        std::fs::create_dir_all(&dir).unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let old = aged_file(&dir, "old.ogg", 10, 3 * day);
+        let recent = aged_file(&dir, "recent.ogg", 10, Duration::from_secs(60));
+        let other = aged_file(&dir, "notes.txt", 10, 3 * day);
+
+        let retention = RecordingRetention {
+            max_age: Some(day),
+            max_total_bytes: None,
+        };
+        let removed = prune_recordings(&dir, retention).unwrap();
+        let left = (old.exists(), recent.exists(), other.exists());
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(removed, 1);
+        assert_eq!(left, (false, true, true));
+    }
+
+    #[test]
+    fn pruning_deletes_the_oldest_recordings_over_the_size_limit() {
+        let dir = temp_path("prune-size");
+        std::fs::create_dir_all(&dir).unwrap();
+        let minutes = |m| Duration::from_secs(m * 60);
+        let oldest = aged_file(&dir, "a.ogg", 10, minutes(30));
+        let older = aged_file(&dir, "b.ogg", 10, minutes(20));
+        let newest = aged_file(&dir, "c.ogg", 10, minutes(10));
+
+        let retention = RecordingRetention {
+            max_age: None,
+            max_total_bytes: Some(25),
+        };
+        let removed = prune_recordings(&dir, retention).unwrap();
+        let left = (oldest.exists(), older.exists(), newest.exists());
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(removed, 1);
+        assert_eq!(left, (false, true, true));
+    }
+
+    #[test]
+    fn pruning_a_missing_directory_does_nothing() {
+        let dir = temp_path("prune-missing");
+        assert_eq!(
+            prune_recordings(&dir, RecordingRetention::default()).unwrap(),
+            0
+        );
+    }
+}
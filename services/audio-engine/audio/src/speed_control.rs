@@ -1,25 +1,88 @@
 use std::time::Duration;
 
-// i love pid, but linear for now
+/// Time constant of the low-pass on the derivative term. The mixer drains
+/// and the decoder fills in whole frames, so the raw slope is all spikes.
+const DERIVATIVE_SMOOTHING: Duration = Duration::from_millis(100);
 
-#[derive(Clone, Debug)]
+/// Tuning for the decoder's producer pacing. The controller steers the ring
+/// buffer towards `target_fill_ratio` by lengthening or shortening the pause
+/// after each write.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SpeedControlConfig {
+    /// Pauses shorter than this are skipped.
     pub min_delay: Duration,
     pub max_delay: Duration,
     pub target_fill_ratio: f32,
+    /// Proportional gain, in units of `max_delay` per unit of fill error.
+    pub kp: f32,
+    /// Integral gain, per second of accumulated fill error.
+    pub ki: f32,
+    /// Derivative gain, in seconds.
+    pub kd: f32,
 }
 
-pub fn calculate_delay(
-    config: &SpeedControlConfig,
-    current_fill: usize,
-    capacity: usize,
-) -> Duration {
-    let fill_ratio = current_fill as f32 / capacity as f32;
-
-    if fill_ratio > config.target_fill_ratio {
-        let scale = (fill_ratio - config.target_fill_ratio) / config.target_fill_ratio;
-        config.min_delay + (config.max_delay - config.min_delay).mul_f32(scale)
-    } else {
-        Duration::ZERO
+impl Default for SpeedControlConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(100),
+            target_fill_ratio: 0.5,
+            kp: 1.0,
+            ki: 2.0,
+            kd: 0.01,
+        }
+    }
+}
+
+/// PID controller for the delay between ring buffer writes. Integration is
+/// held while the output is pinned at either end, so a long stall or burst
+/// does not wind the integral up and overshoot once it is over.
+#[derive(Debug)]
+pub struct SpeedController {
+    config: SpeedControlConfig,
+    integral: f32,
+    derivative: f32,
+    prev_error: Option<f32>,
+}
+
+impl SpeedController {
+    pub fn new(config: SpeedControlConfig) -> Self {
+        Self {
+            config,
+            integral: 0.0,
+            derivative: 0.0,
+            prev_error: None,
+        }
+    }
+
+    /// The pause before the next write, given the current fill and the time
+    /// since the previous call.
+    pub fn next_delay(&mut self, current_fill: usize, capacity: usize, dt: Duration) -> Duration {
+        let config = &self.config;
+        let error = current_fill as f32 / capacity as f32 - config.target_fill_ratio;
+        // A long wait for the tap says nothing about how the loop is doing.
+        let dt = dt.min(config.max_delay).as_secs_f32();
+
+        if let Some(prev) = self.prev_error
+            && dt > 0.0
+        {
+            let alpha = dt / (dt + DERIVATIVE_SMOOTHING.as_secs_f32());
+            self.derivative += ((error - prev) / dt - self.derivative) * alpha;
+        }
+        self.prev_error = Some(error);
+
+        let integral = self.integral + error * dt;
+        let output = config.kp * error + config.ki * integral + config.kd * self.derivative;
+        let pinned = (output >= 1.0 && error > 0.0) || (output <= 0.0 && error < 0.0);
+        if !pinned {
+            self.integral = integral;
+        }
+
+        let delay = config.max_delay.mul_f32(output.clamp(0.0, 1.0));
+        if delay < config.min_delay {
+            Duration::ZERO
+        } else {
+            delay
+        }
     }
 }
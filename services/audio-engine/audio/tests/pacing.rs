@@ -0,0 +1,112 @@
+//! Simulates the decode task's producer pacing against a mixer that drains
+//! one frame every 20 ms, fed by a tap that delivers audio in bursts.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use zako3_audio_engine_audio::speed_control::{SpeedControlConfig, SpeedController};
+use zako3_audio_engine_audio::{BUFFER_SIZE, RINGBUFFER_SIZE};
+
+const FRAME_MS: u64 = 20;
+
+struct Stats {
+    /// Ring fill sampled right before each mixer frame, after warm-up.
+    fills: Vec<usize>,
+    underruns: usize,
+}
+
+/// Run for `total_ms` at 1 ms resolution. The mixer starts draining at
+/// `mixer_start_ms`, and `tap(ms)` is how many 20 ms chunks arrive at that
+/// millisecond.
+fn simulate(
+    total_ms: u64,
+    mixer_start_ms: u64,
+    warmup_ms: u64,
+    tap: impl Fn(u64) -> usize,
+) -> Stats {
+    let mut controller = SpeedController::new(SpeedControlConfig::default());
+    let mut pending: VecDeque<usize> = VecDeque::new();
+    let mut fill = 0usize;
+    let mut wake_at = 0u64;
+    let mut last_update = 0u64;
+    let mut stats = Stats {
+        fills: Vec::new(),
+        underruns: 0,
+    };
+
+    for now in 0..total_ms {
+        pending.extend(std::iter::repeat_n(BUFFER_SIZE, tap(now)));
+
+        // Producer: one write per wake-up, like `spawn_decode_task`.
+        if now >= wake_at
+            && let Some(chunk) = pending.front_mut()
+        {
+            let take = (RINGBUFFER_SIZE - fill).min(*chunk).min(BUFFER_SIZE);
+            fill += take;
+            *chunk -= take;
+            if *chunk == 0 {
+                pending.pop_front();
+            }
+            let delay = controller.next_delay(
+                fill,
+                RINGBUFFER_SIZE,
+                Duration::from_millis(now - last_update),
+            );
+            last_update = now;
+            wake_at = now + delay.as_millis() as u64;
+        }
+
+        // Mixer: drains a frame every 20 ms.
+        if now >= mixer_start_ms && now % FRAME_MS == 0 {
+            if now >= warmup_ms {
+                stats.fills.push(fill);
+                if fill < BUFFER_SIZE {
+                    stats.underruns += 1;
+                }
+            }
+            fill -= fill.min(BUFFER_SIZE);
+        }
+    }
+    stats
+}
+
+fn fill_ratio(fill: usize) -> f32 {
+    fill as f32 / RINGBUFFER_SIZE as f32
+}
+
+/// Ring buffer latency, in ms of audio.
+fn latency_ms(fill: usize) -> f32 {
+    fill as f32 / BUFFER_SIZE as f32 * FRAME_MS as f32
+}
+
+#[test]
+fn bursty_input_keeps_fill_steady() {
+    // 400 ms of audio every 400 ms, all at once, plus an extra chunk every
+    // second so the tap stays a little ahead of real time.
+    let stats = simulate(20_000, 0, 2_000, |ms| {
+        (if ms % 400 == 0 { 20 } else { 0 }) + usize::from(ms % 1_000 == 500)
+    });
+
+    assert_eq!(stats.underruns, 0);
+    let mean = stats.fills.iter().map(|&f| fill_ratio(f)).sum::<f32>() / stats.fills.len() as f32;
+    assert!((mean - 0.5).abs() < 0.1, "mean fill {mean}");
+    let max = stats.fills.iter().copied().max().unwrap();
+    assert!(
+        latency_ms(max) <= 120.0,
+        "max latency {} ms",
+        latency_ms(max)
+    );
+}
+
+#[test]
+fn held_back_source_does_not_wind_up() {
+    // A source queued behind another track fills its ring and then waits
+    // 30 s before the mixer starts on it. An integral wound up over that
+    // wait would starve it once it starts.
+    let stats = simulate(40_000, 30_000, 30_000, |ms| usize::from(ms % 10 == 0));
+
+    assert_eq!(stats.underruns, 0);
+    let settled = &stats.fills[1_000 / FRAME_MS as usize..];
+    let mean = settled.iter().map(|&f| fill_ratio(f)).sum::<f32>() / settled.len() as f32;
+    assert!((mean - 0.5).abs() < 0.1, "mean fill {mean}");
+}
@@ -1,4 +1,10 @@
+use std::time::Duration;
+
 use serde::Deserialize;
+use zako3_audio_engine_core::{
+    DEFAULT_RECORDING_DIR,
+    audio::{DecoderConfig, recorder::RecordingRetention},
+};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct AppConfig {
@@ -19,6 +25,34 @@ pub struct AppConfig {
     #[serde(default = "default_taphub_transport_cert_file")]
     pub taphub_transport_cert_file: String,
 
+    /// Where session audio goes. `file` never connects to Discord and writes
+    /// each session's output to an Ogg file in `file_sink_dir` instead, for
+    /// end-to-end tests.
+    #[serde(default)]
+    pub discord_sink: DiscordSink,
+    #[serde(default = "default_file_sink_dir")]
+    pub file_sink_dir: String,
+    /// Where sessions recording in full mode write their Ogg files.
+    #[serde(default = "default_recording_dir")]
+    pub recording_dir: String,
+    /// Hours a full recording is kept before it is deleted; 0 keeps them
+    /// regardless of age.
+    #[serde(default = "default_recording_max_age_hours")]
+    pub recording_max_age_hours: u64,
+    /// Size (MiB) the recording directory may grow to before its oldest
+    /// recordings are deleted. Unlimited if unset.
+    pub recording_max_total_mib: Option<u64>,
+    /// Mixer worker threads shared by all sessions. Defaults to one per core.
+    pub mixer_workers: Option<usize>,
+    /// Audio (ms) each new track buffers before it starts playing.
+    pub preroll_ms: Option<u64>,
+    /// Gains for the controller that paces decoding into each track's ring
+    /// buffer, and the fill ratio it aims for.
+    pub pacing_kp: Option<f32>,
+    pub pacing_ki: Option<f32>,
+    pub pacing_kd: Option<f32>,
+    pub pacing_target_fill: Option<f32>,
+
     // Telemetry configuration
     #[serde(default = "default_service_name")]
     pub service_name: String,
@@ -27,6 +61,14 @@ pub struct AppConfig {
     pub metrics_port: u16,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscordSink {
+    #[default]
+    Songbird,
+    File,
+}
+
 fn default_ae_port() -> u16 {
     8090
 }
@@ -51,6 +93,18 @@ fn default_taphub_transport_cert_file() -> String {
     "cert.pem".to_string()
 }
 
+fn default_file_sink_dir() -> String {
+    "session-audio".to_string()
+}
+
+fn default_recording_dir() -> String {
+    DEFAULT_RECORDING_DIR.to_string()
+}
+
+fn default_recording_max_age_hours() -> u64 {
+    7 * 24
+}
+
 fn default_service_name() -> String {
     "audio-engine".to_string()
 }
@@ -60,6 +114,31 @@ fn default_metrics_port() -> u16 {
 }
 
 impl AppConfig {
+    /// Decoder defaults with whatever this deployment overrides.
+    pub fn decoder_config(&self) -> DecoderConfig {
+        let mut config = DecoderConfig::default();
+        if let Some(ms) = self.preroll_ms {
+            config.preroll = Duration::from_millis(ms);
+        }
+        let pacing = &mut config.speed_control;
+        pacing.kp = self.pacing_kp.unwrap_or(pacing.kp);
+        pacing.ki = self.pacing_ki.unwrap_or(pacing.ki);
+        pacing.kd = self.pacing_kd.unwrap_or(pacing.kd);
+        pacing.target_fill_ratio = self
+            .pacing_target_fill
+            .map_or(pacing.target_fill_ratio, |f| f.clamp(0.0, 1.0));
+        config
+    }
+
+    /// What the recording directory is pruned down to.
+    pub fn recording_retention(&self) -> RecordingRetention {
+        RecordingRetention {
+            max_age: (self.recording_max_age_hours > 0)
+                .then(|| Duration::from_secs(self.recording_max_age_hours * 60 * 60)),
+            max_total_bytes: self.recording_max_total_mib.map(|mib| mib * 1024 * 1024),
+        }
+    }
+
     pub fn load() -> Self {
         dotenvy::dotenv().ok();
 
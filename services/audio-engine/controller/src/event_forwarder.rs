@@ -0,0 +1,40 @@
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+use zako3_tl_client::TlClient;
+use zako3_types::SessionEvent;
+
+/// Most events sent to TL in one request.
+const MAX_BATCH: usize = 64;
+
+/// Forwards track lifecycle events to TL, which fans them out to subscribers.
+/// Whatever queued up while the previous batch was in flight goes out together.
+/// Runs until the session manager is dropped.
+pub async fn run_event_forwarder(
+    tl_client: TlClient,
+    token: String,
+    mut events: broadcast::Receiver<SessionEvent>,
+) {
+    loop {
+        let mut batch = match events.recv().await {
+            Ok(event) => vec![event],
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(skipped, "event_forwarder: dropped events while TL was slow");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        while batch.len() < MAX_BATCH {
+            match events.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(TryRecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "event_forwarder: dropped events while TL was slow");
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+
+        // Best-effort: events are a live feed, not a log, so a failed batch is not retried.
+        if let Err(e) = tl_client.publish_session_events(token.clone(), batch).await {
+            tracing::warn!("event_forwarder: publish_session_events failed: {e}");
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod address;
 pub mod config;
+pub mod event_forwarder;
 pub mod guild_reporter;
 pub mod ready_waiter;
+pub mod recording_pruner;
 pub mod server;
@@ -10,15 +10,21 @@
 
 use zako3_audio_engine_controller::{
     address::{SelfAddressResolver, HeuristicSelfAddressResolver},
-    config::AppConfig,
+    config::{AppConfig, DiscordSink},
+    event_forwarder::run_event_forwarder,
     guild_reporter::{report_guilds_once, run_ae_heartbeat, run_guild_reporter},
     ready_waiter::create_ready_waiter,
+    recording_pruner::run_recording_pruner,
     server::AeTransportHandler,
 };
 
+use zako3_audio_engine_core::CHECKPOINT_INTERVAL;
+use zako3_audio_engine_core::audio::MixerPool;
 use zako3_audio_engine_core::engine::session_manager::SessionManager;
+use zako3_audio_engine_core::service::discord::ArcDiscordService;
 use zako3_audio_engine_infra::{
-    RedisStateService, discord::SongbirdDiscordService, taphub::RealTapHubService,
+    FileSinkDiscordService, RedisStateService, discord::SongbirdDiscordService,
+    taphub::RealTapHubService,
 };
 
 use zako3_telemetry::TelemetryConfig;
@@ -149,30 +155,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         advertised_addr.clone(),
     ));
 
-    // Step 6: Build the Discord / audio infrastructure with the assigned token.
-    let songbird_manager = songbird::Songbird::serenity();
-    let (ready_waiter, mut ready_recv, mut ctx_recv) = create_ready_waiter();
-
-    let intents = GatewayIntents::GUILD_VOICE_STATES | GatewayIntents::GUILDS;
-    let mut discord_client = Client::builder(&token_str, intents)
-        .event_handler_arc(ready_waiter)
-        .register_songbird_with(songbird_manager.clone())
-        .await
-        .expect("Failed to create Discord client");
-
-    tokio::spawn(async move {
-        if let Err(e) = discord_client.start().await {
-            tracing::error!("Discord client ended: {:?}", e);
-        } else {
-            tracing::warn!("Discord client exited without error");
-        }
-        tracing::error!("Discord task terminated, shutting down AE process for restart");
-        std::process::exit(1);
-    });
-
-    ready_recv.recv().await;
-    let serenity_ctx = ctx_recv.recv().await.expect("ctx channel closed before ready");
-
     // Channel carrying terminal voice disconnects from the songbird driver-event watcher
     // (registered per-join) to the controller's consumer task below.
     let (disconnect_tx, mut disconnect_rx) = tokio::sync::mpsc::unbounded_channel::<(
@@ -180,26 +162,86 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         zako3_audio_engine_core::types::ChannelId,
     )>();
 
-    let discord_service = Arc::new(SongbirdDiscordService::new(
-        songbird_manager.clone(),
-        serenity_ctx.cache.clone(),
-        disconnect_tx,
-    ));
+    // Step 6: Build the Discord / audio infrastructure with the assigned token. The file
+    // sink skips Discord entirely, so there is no serenity context to report guilds from.
+    let (discord_service, serenity_ctx): (ArcDiscordService, _) = match config.discord_sink {
+        DiscordSink::Songbird => {
+            let songbird_manager = songbird::Songbird::serenity();
+            let (ready_waiter, mut ready_recv, mut ctx_recv) = create_ready_waiter();
+
+            let intents = GatewayIntents::GUILD_VOICE_STATES | GatewayIntents::GUILDS;
+            let mut discord_client = Client::builder(&token_str, intents)
+                .event_handler_arc(ready_waiter)
+                .register_songbird_with(songbird_manager.clone())
+                .await
+                .expect("Failed to create Discord client");
+
+            tokio::spawn(async move {
+                if let Err(e) = discord_client.start().await {
+                    tracing::error!("Discord client ended: {:?}", e);
+                } else {
+                    tracing::warn!("Discord client exited without error");
+                }
+                tracing::error!("Discord task terminated, shutting down AE process for restart");
+                std::process::exit(1);
+            });
+
+            ready_recv.recv().await;
+            let serenity_ctx = ctx_recv
+                .recv()
+                .await
+                .expect("ctx channel closed before ready");
+
+            let discord_service = Arc::new(SongbirdDiscordService::new(
+                songbird_manager.clone(),
+                serenity_ctx.cache.clone(),
+                disconnect_tx,
+            ));
+            (discord_service, Some(serenity_ctx))
+        }
+        DiscordSink::File => {
+            tracing::warn!(
+                dir = %config.file_sink_dir,
+                "Discord disabled; writing session audio to files"
+            );
+            drop(disconnect_tx);
+            let discord_service = Arc::new(FileSinkDiscordService::new(&config.file_sink_dir));
+            (discord_service, None)
+        }
+    };
 
     // Redis-backed session store, namespaced by this bot's token so a restart rejoins exactly
     // this bot's previously-active channels. Best-effort: degrades to no persistence if Redis
     // is unavailable.
     let state_service = Arc::new(RedisStateService::new(&config.redis_url, &token_str).await);
 
-    let session_manager = Arc::new(SessionManager::new(
-        discord_service,
-        state_service,
-        taphub_service,
+    let session_manager = Arc::new(
+        SessionManager::new(discord_service, state_service, taphub_service)
+            .with_recording_dir(&config.recording_dir)
+            .with_decoder_config(config.decoder_config())
+            .with_mixer_pool(
+                config
+                    .mixer_workers
+                    .map_or_else(MixerPool::default, MixerPool::new),
+            ),
+    );
+
+    // Keep full recordings within the configured age and size.
+    tokio::spawn(run_recording_pruner(
+        config.recording_dir.clone().into(),
+        config.recording_retention(),
     ));
 
     // Fill the OnceLock now that session_manager is constructed
     let _ = sm_cell.set(session_manager.clone());
 
+    // Relay track lifecycle events to TL. Subscribed before rejoin so those are reported too.
+    tokio::spawn(run_event_forwarder(
+        tl_client,
+        token_str.clone(),
+        session_manager.subscribe_events(),
+    ));
+
     // Rejoin any sessions persisted from a previous run. The bot reconnects to its channels
     // automatically; TL's reconcile will re-adopt these live sessions into its cache.
     match session_manager.list_sessions().await {
@@ -244,18 +286,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
     }
 
+    // Periodically persist track positions so a restart resumes mid-track.
+    {
+        let sm = session_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECKPOINT_INTERVAL);
+            loop {
+                interval.tick().await;
+                sm.checkpoint_all().await;
+            }
+        });
+    }
+
     tracing::info!("Audio Engine is ready and connected to Discord!");
     telemetry.healthy();
 
-    // Step 6: Spawn background guild reporter.
-    tokio::spawn(run_guild_reporter(
-        serenity_ctx.clone(),
-        config.tl_rpc_url.clone(),
-        token_str.clone(),
-    ));
+    if let Some(serenity_ctx) = serenity_ctx {
+        // Step 6: Spawn background guild reporter.
+        tokio::spawn(run_guild_reporter(
+            serenity_ctx.clone(),
+            config.tl_rpc_url.clone(),
+            token_str.clone(),
+        ));
 
-    // Report guilds once on startup
-    report_guilds_once(&serenity_ctx, &config.tl_rpc_url, &token_str).await;
+        // Report guilds once on startup
+        report_guilds_once(&serenity_ctx, &config.tl_rpc_url, &token_str).await;
+    }
 
     tracing::info!("Audio Engine is serving requests");
 
@@ -290,5 +346,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // Record where every track is, so the next start picks up from here.
+    session_manager.checkpoint_all().await;
+
     Ok(())
 }
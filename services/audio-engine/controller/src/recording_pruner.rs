@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use zako3_audio_engine_core::audio::recorder::{RecordingRetention, prune_recordings};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Deletes the full recordings in `dir` that `retention` no longer allows,
+/// once at startup and then every hour. Runs forever.
+pub async fn run_recording_pruner(dir: PathBuf, retention: RecordingRetention) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let target = dir.clone();
+        match tokio::task::spawn_blocking(move || prune_recordings(&target, retention)).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(removed)) => tracing::info!(removed, "Pruned old session recordings"),
+            Ok(Err(e)) => {
+                tracing::warn!(dir = %dir.display(), error = %e, "Failed to prune recordings")
+            }
+            Err(e) => tracing::warn!("recording_pruner: prune task failed: {e}"),
+        }
+    }
+}
@@ -5,7 +5,7 @@
 use opentelemetry::global;
 use tl_protocol::{
     AudioEngineCommand, AudioEngineCommandRequest, AudioEngineCommandResponse, AudioEngineError,
-    AudioEngineRpcServer, AudioEngineSessionCommand,
+    AudioEngineRpcServer, AudioEngineSessionCommand, OggOpus,
 };
 use tracing::{Instrument as _, error, warn};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
@@ -109,6 +109,7 @@ async fn handle_command(&self, req: AudioEngineCommandRequest) -> AudioEngineCom
                                 play_req.ars,
                                 play_req.volume,
                                 play_req.initiator,
+                                play_req.dsp,
                             )
                             .await
                         {
@@ -117,6 +118,52 @@ async fn handle_command(&self, req: AudioEngineCommandRequest) -> AudioEngineCom
                         }
                     }
 
+                    AudioEngineSessionCommand::PlayNext(play_req) => {
+                        match session
+                            .insert_at(
+                                1,
+                                play_req.queue_name,
+                                play_req.tap_id,
+                                play_req.ars,
+                                play_req.volume,
+                                play_req.initiator,
+                                play_req.dsp,
+                            )
+                            .await
+                        {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => map_engine_err(e),
+                        }
+                    }
+
+                    AudioEngineSessionCommand::InsertAt {
+                        request: play_req,
+                        index,
+                    } => {
+                        match session
+                            .insert_at(
+                                index,
+                                play_req.queue_name,
+                                play_req.tap_id,
+                                play_req.ars,
+                                play_req.volume,
+                                play_req.initiator,
+                                play_req.dsp,
+                            )
+                            .await
+                        {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => map_engine_err(e),
+                        }
+                    }
+
+                    AudioEngineSessionCommand::Move { track_id, index } => {
+                        match session.move_track(track_id, index).await {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
+
                     AudioEngineSessionCommand::Stop(track_id) => {
                         match session.stop(track_id).await {
                             Ok(_) => AudioEngineCommandResponse::Ok,
@@ -138,11 +185,47 @@ async fn handle_command(&self, req: AudioEngineCommandRequest) -> AudioEngineCom
                         }
                     }
 
+                    AudioEngineSessionCommand::SetDsp { track_id, dsp } => {
+                        match session.set_dsp(track_id, dsp).await {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
+
+                    AudioEngineSessionCommand::SetPan { track_id, pan } => {
+                        match session.set_pan(track_id, pan).await {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
+
+                    AudioEngineSessionCommand::Seek {
+                        track_id,
+                        position_ms,
+                    } => match session.seek(track_id, position_ms).await {
+                        Ok(_) => AudioEngineCommandResponse::Ok,
+                        Err(e) => err(&e.to_string()),
+                    },
+
+                    AudioEngineSessionCommand::SetSettings(settings) => {
+                        match session.set_settings(settings).await {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
+
                     AudioEngineSessionCommand::NextMusic => match session.next_music().await {
                         Ok(_) => AudioEngineCommandResponse::Ok,
                         Err(e) => err(&e.to_string()),
                     },
 
+                    AudioEngineSessionCommand::SetQueueMode(mode) => {
+                        match session.set_queue_mode(mode).await {
+                            Ok(_) => AudioEngineCommandResponse::Ok,
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
+
                     AudioEngineSessionCommand::Pause(queue_name) => {
                         match session.pause_queue(queue_name).await {
                             Ok(_) => AudioEngineCommandResponse::Ok,
@@ -164,6 +247,13 @@ async fn handle_command(&self, req: AudioEngineCommandRequest) -> AudioEngineCom
                             Err(e) => err(&e.to_string()),
                         }
                     }
+
+                    AudioEngineSessionCommand::ExportRecording { seconds } => {
+                        match session.export_recording(seconds) {
+                            Ok(ogg) => AudioEngineCommandResponse::Recording(OggOpus(ogg)),
+                            Err(e) => err(&e.to_string()),
+                        }
+                    }
                 }
             }
         }
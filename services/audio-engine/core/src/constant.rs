@@ -1,3 +1,11 @@
+use std::time::Duration;
+
 pub const BUFFER_SIZE: usize = 1024;
 pub const SAMPLE_RATE: u32 = 48000;
 pub const CHANNELS: u32 = 2;
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+/// How often live track positions are persisted, so a restart resumes close to
+/// where playback was.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+/// Where full session recordings are written unless the deployment says otherwise.
+pub const DEFAULT_RECORDING_DIR: &str = "session-recordings";
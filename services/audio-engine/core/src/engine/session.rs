@@ -1,17 +1,30 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use tokio::sync::{Mutex, mpsc::Sender};
+use rand::{Rng, seq::SliceRandom};
+use tokio::sync::{Mutex, broadcast, mpsc::Sender, oneshot};
 use tracing::instrument;
-use zako3_audio_engine_audio::metrics;
-use zako3_types::SessionState;
+use zako3_audio_engine_audio::{SourceClass, TrackLoudness, metrics};
+use zako3_types::{
+    AudioCacheType, QueueMode, RecordingMode, RecordingSettings, RepeatMode, SessionAudioSettings,
+    SessionEvent, SessionState, TapHubError, TrackEventKind, TransitionSettings, TtsPanSettings,
+};
 
 use crate::{
-    audio::{ArcDecoder, ArcMixer},
+    DEFAULT_RECORDING_DIR, EVENT_CHANNEL_CAPACITY,
+    audio::{
+        ArcDecoder, ArcMixer, Follow, RingCons,
+        recorder::{FullRecording, Recorder},
+    },
     error::{ZakoError, ZakoResult},
     service::{ArcStateService, ArcTapHubService, modify_state_session},
     types::{
         AudioRequest, AudioRequestString, AudioStopFilter, CachedAudioRequest, ChannelId, GuildId,
-        QueueName, Track, TrackId, Volume,
+        Pan, QueueName, QueuePriority, Track, TrackDsp, TrackId, Volume,
     },
     util::id_gen,
 };
@@ -26,23 +39,61 @@ pub struct SessionControl {
 
     pub(crate) end_tx: Sender<TrackId>,
 
+    // Sources whose pre-roll finished after `play_now` returned.
+    ready_tx: Sender<Prerolled>,
+
     pub(crate) state_service: ArcStateService,
     pub(crate) taphub_service: ArcTapHubService,
 
     // Serializes reconcile() to prevent concurrent check-then-act races where
     // two callers both see a track as "not in mixer" and both call play_now().
     reconcile_guard: Mutex<()>,
+
+    // Set while the session's recording setting is enabled; shared with the
+    // mixer thread, which pushes every encoded frame into it.
+    recorder: parking_lot::Mutex<Option<Arc<Recorder>>>,
+
+    // Where full recordings of this session are written.
+    recording_dir: PathBuf,
+
+    // Track lifecycle events, shared by every session of the manager.
+    events: broadcast::Sender<SessionEvent>,
+
+    // Tracks reconcile paused because a higher priority class is playing.
+    // Not persisted: a rejoin simply recomputes it.
+    held: parking_lot::Mutex<HashSet<TrackId>>,
+
+    // Offset each track in the mixer started from. Checkpoints move the
+    // persisted position forward, so live positions are measured from here.
+    started_from: parking_lot::Mutex<HashMap<TrackId, u64>>,
+
+    // Tracks lined up in the mixer behind a playing one. They are announced
+    // as started once reconcile finds them at the head of their queue.
+    lined_up: parking_lot::Mutex<HashSet<TrackId>>,
+
+    // Tracks still buffering their pre-roll, by start attempt. Reconcile
+    // counts them as playing; a seek or stop forgets the attempt, and its
+    // source is dropped once buffered.
+    starting: parking_lot::Mutex<HashMap<TrackId, u64>>,
+
+    // Auto-pan position of each TTS queue. Not persisted: a rejoin hands
+    // the positions out again.
+    pan_slots: parking_lot::Mutex<PanSlots>,
 }
 
 impl SessionControl {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         guild_id: GuildId,
         channel_id: ChannelId,
         end_tx: Sender<TrackId>,
+        ready_tx: Sender<Prerolled>,
         mixer: ArcMixer,
         decoder: ArcDecoder,
         state_service: ArcStateService,
         taphub_service: ArcTapHubService,
+        events: broadcast::Sender<SessionEvent>,
+        recording_dir: PathBuf,
     ) -> Self {
         SessionControl {
             guild_id,
@@ -50,12 +101,31 @@ fn new(
             mixer,
             decoder,
             end_tx,
+            ready_tx,
             state_service,
             taphub_service,
             reconcile_guard: Mutex::new(()),
+            recorder: parking_lot::Mutex::new(None),
+            recording_dir,
+            events,
+            held: parking_lot::Mutex::new(HashSet::new()),
+            started_from: parking_lot::Mutex::new(HashMap::new()),
+            lined_up: parking_lot::Mutex::new(HashSet::new()),
+            starting: parking_lot::Mutex::new(HashMap::new()),
+            pan_slots: parking_lot::Mutex::new(PanSlots::default()),
         }
     }
 
+    fn emit(&self, track_id: TrackId, kind: TrackEventKind) {
+        // An error only means nobody is subscribed.
+        let _ = self.events.send(SessionEvent {
+            guild_id: self.guild_id,
+            channel_id: self.channel_id,
+            track_id,
+            kind,
+        });
+    }
+
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     pub async fn play(
         &self,
@@ -64,12 +134,63 @@ pub async fn play(
         request: AudioRequestString,
         volume: Volume,
         discord_user_id: zako3_types::hq::DiscordUserId,
+        dsp: TrackDsp,
+    ) -> ZakoResult<TrackId> {
+        self.enqueue(
+            queue_name,
+            tap_id,
+            request,
+            volume,
+            discord_user_id,
+            dsp,
+            None,
+        )
+        .await
+    }
+
+    /// Queue a track at `index` instead of the end of its queue. Index 0 is
+    /// the playing track, so inserts land at 1 at the earliest.
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn insert_at(
+        &self,
+        index: usize,
+        queue_name: QueueName,
+        tap_id: TapId,
+        request: AudioRequestString,
+        volume: Volume,
+        discord_user_id: zako3_types::hq::DiscordUserId,
+        dsp: TrackDsp,
+    ) -> ZakoResult<TrackId> {
+        self.enqueue(
+            queue_name,
+            tap_id,
+            request,
+            volume,
+            discord_user_id,
+            dsp,
+            Some(index),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn enqueue(
+        &self,
+        queue_name: QueueName,
+        tap_id: TapId,
+        request: AudioRequestString,
+        volume: Volume,
+        discord_user_id: zako3_types::hq::DiscordUserId,
+        dsp: TrackDsp,
+        index: Option<usize>,
     ) -> ZakoResult<TrackId> {
         tracing::info!(
             queue_name = %queue_name,
             tap_id = %tap_id.0,
             volume = %volume,
             discord_user_id = %discord_user_id,
+            index = ?index,
             "Playing audio"
         );
 
@@ -91,6 +212,12 @@ pub async fn play(
         tracing::info!("base_volume = {}", meta.base_volume);
 
         let effective_volume = Volume::from(f32::from(volume) * meta.base_volume);
+        let dsp = dsp.clamped();
+
+        let queued_before = match index {
+            Some(_) => self.next_music_tracks().await?,
+            None => HashSet::new(),
+        };
 
         let queue_name_for_metric = queue_name.clone();
         modify_state_session(
@@ -107,18 +234,37 @@ pub async fn play(
                         cache_key: meta.cache_key,
                         discord_user_id,
                         headers: Default::default(),
+                        start_offset_ms: 0,
                     },
                     volume: effective_volume,
                     queue_name: queue_name.clone(),
                     paused: false,
+                    dsp,
+                    pan: None,
+                    duration_ms: meta.duration_ms,
+                    position_ms: 0,
                 };
 
+                if let Some(index) = index {
+                    insert_track(&mut session.queues, queue_name, track, index);
+                    return;
+                }
+                let shuffle =
+                    session.queue_mode.shuffle && source_class(&queue_name) == SourceClass::Music;
                 upsert_track(&mut session.queues, queue_name.clone(), track);
+                if shuffle && let Some(queue) = session.queues.get_mut(&queue_name) {
+                    move_last_to_random_slot(queue);
+                }
             },
         )
         .await?;
 
         metrics::record_track_lifecycle("queued", &normalize_queue_name(&queue_name_for_metric));
+        self.emit(track_id, TrackEventKind::Queued);
+
+        if !queued_before.is_empty() {
+            self.release_unqueued(queued_before).await?;
+        }
 
         self.reconcile().await?;
         self.preload_if_possible(track_id).await?;
@@ -144,10 +290,280 @@ pub async fn set_volume(&self, track_id: TrackId, volume: Volume) -> ZakoResult<
         Ok(())
     }
 
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn set_dsp(&self, track_id: TrackId, dsp: TrackDsp) -> ZakoResult<()> {
+        let dsp = dsp.clamped();
+        tracing::debug!(track_id = %track_id, dsp = ?dsp, "Setting DSP");
+        self.mixer.set_dsp(track_id, dsp);
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                if let Some(track) = session.find_track_mut(track_id) {
+                    track.dsp = dsp;
+                }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn set_pan(&self, track_id: TrackId, pan: Pan) -> ZakoResult<()> {
+        let pan = pan.clamped();
+        tracing::debug!(track_id = %track_id, pan = %pan, "Setting pan");
+        self.mixer.set_pan(track_id, pan.into());
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                if let Some(track) = session.find_track_mut(track_id) {
+                    track.pan = Some(pan);
+                }
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Restart a track from `position_ms`. Paused tracks only move their
+    /// position and start from there on resume.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn seek(&self, track_id: TrackId, position_ms: u64) -> ZakoResult<()> {
+        let session = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?;
+        let track = session
+            .as_ref()
+            .and_then(|s| s.find_track(track_id).cloned())
+            .ok_or(ZakoError::TrackNotFound(track_id))?;
+        // A live tap stream can only start over from the beginning.
+        if position_ms > 0 && matches!(track.request.cache_key.cache_type, AudioCacheType::None) {
+            return Err(ZakoError::SeekUnsupported(track_id));
+        }
+
+        let position_ms = match track.duration_ms {
+            Some(duration_ms) => position_ms.min(duration_ms),
+            None => position_ms,
+        };
+        tracing::info!(track_id = %track_id, position_ms, "Seeking track");
+
+        // A track queued behind this one would start as soon as it leaves the
+        // mixer; drop it too and let reconcile queue it again.
+        if let Some(next) = session.as_ref().and_then(|s| next_in_queue(s, &track)) {
+            self.mixer.remove_source(next);
+            self.decoder.stop_track(next);
+            self.forget_track(next);
+        }
+        self.mixer.remove_source(track_id);
+        self.decoder.stop_track(track_id);
+        self.started_from.lock().remove(&track_id);
+        self.starting.lock().remove(&track_id);
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                if let Some(track) = session.find_track_mut(track_id) {
+                    track.position_ms = position_ms;
+                }
+            },
+        )
+        .await?;
+
+        self.reconcile().await
+    }
+
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn set_settings(&self, settings: SessionAudioSettings) -> ZakoResult<()> {
+        tracing::info!(settings = ?settings, "Updating session audio settings");
+        self.apply_settings(&settings);
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                session.settings = settings;
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Change how music queues advance. Turning shuffle on reorders every
+    /// music queue behind its playing track.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn set_queue_mode(&self, mode: QueueMode) -> ZakoResult<()> {
+        tracing::info!(mode = ?mode, "Updating queue mode");
+        let Some(mut session) = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let queued_before = next_music_tracks(&session);
+        if mode.shuffle && !session.queue_mode.shuffle {
+            let mut rng = rand::rng();
+            for (name, queue) in session.queues.iter_mut() {
+                if source_class(name) == SourceClass::Music && queue.len() > 2 {
+                    queue[1..].shuffle(&mut rng);
+                }
+            }
+        }
+        session.queue_mode = mode;
+        self.state_service.save_session(&session).await?;
+
+        self.release_unqueued(queued_before).await?;
+        self.reconcile().await
+    }
+
+    /// Move a queued track to `index` within its queue. The playing track
+    /// stays where it is and nothing can be moved in front of it.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn move_track(&self, track_id: TrackId, index: usize) -> ZakoResult<()> {
+        tracing::info!(track_id = %track_id, index, "Moving track");
+        let mut session = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+            .ok_or(ZakoError::TrackNotFound(track_id))?;
+
+        let queued_before = next_music_tracks(&session);
+        let (queue, from) = session
+            .queues
+            .values_mut()
+            .find_map(|q| {
+                let from = q.iter().position(|t| t.track_id == track_id)?;
+                Some((q, from))
+            })
+            .ok_or(ZakoError::TrackNotFound(track_id))?;
+        if from == 0 {
+            return Err(ZakoError::TrackPlaying(track_id));
+        }
+        let track = queue.remove(from);
+        let index = index.clamp(1, queue.len());
+        queue.insert(index, track);
+        self.state_service.save_session(&session).await?;
+
+        self.release_unqueued(queued_before).await?;
+        self.reconcile().await
+    }
+
+    /// Push settings into the mixer and decoder without touching persisted state.
+    pub(crate) fn apply_settings(&self, settings: &SessionAudioSettings) {
+        self.mixer.set_ducking(settings.ducking);
+        self.mixer.set_encoder(settings.encoder);
+        self.decoder.set_loudness(settings.loudness);
+        self.apply_recording(settings.recording);
+        if !settings.tts_pan.enabled {
+            *self.pan_slots.lock() = PanSlots::default();
+        }
+    }
+
+    /// Start or stop the mixer tap, and the full recording with it. Changing
+    /// only the retention keeps what has been recorded so far.
+    fn apply_recording(&self, settings: RecordingSettings) {
+        let mut recorder = self.recorder.lock();
+        match (recorder.as_ref(), settings.enabled) {
+            (Some(current), true) => current.set_retention(settings.retention()),
+            (None, true) => {
+                let new_recorder = Arc::new(Recorder::new(settings.retention()));
+                self.mixer.set_recorder(Some(new_recorder.clone()));
+                *recorder = Some(new_recorder);
+            }
+            (Some(_), false) => {
+                self.mixer.set_recorder(None);
+                *recorder = None;
+            }
+            (None, false) => {}
+        }
+        if let Some(current) = recorder.as_ref() {
+            self.apply_full_recording(current, settings);
+        }
+    }
+
+    /// Start a full recording file when the mode is switched to `Full`, or
+    /// complete it when switched back. One that hit its size limit stays
+    /// stopped until recording is turned off and on again.
+    fn apply_full_recording(&self, recorder: &Recorder, settings: RecordingSettings) {
+        let wanted = settings.mode == RecordingMode::Full;
+        if wanted == recorder.is_recording_full() {
+            return;
+        }
+        if !wanted {
+            recorder.set_full(None);
+            return;
+        }
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = self.recording_dir.join(format!(
+            "{}-{}-{started}.ogg",
+            self.guild_id, self.channel_id
+        ));
+        match FullRecording::create(&path, settings.max_full_size_bytes()) {
+            Ok(full) => recorder.set_full(Some(full)),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to start full recording")
+            }
+        }
+    }
+
+    /// The last `seconds` of what the session played, or everything still
+    /// retained if `None`, as an Ogg Opus file.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub fn export_recording(&self, seconds: Option<u32>) -> ZakoResult<Vec<u8>> {
+        let recorder = self
+            .recorder
+            .lock()
+            .clone()
+            .ok_or(ZakoError::RecordingUnavailable)?;
+        if recorder.duration().is_zero() {
+            return Err(ZakoError::RecordingUnavailable);
+        }
+        let ogg = recorder.export(seconds.map(|s| Duration::from_secs(s as u64)));
+        tracing::info!(bytes = ogg.len(), "Exported session recording");
+        Ok(ogg)
+    }
+
+    /// Fade out everything that is playing and wait for it, before the
+    /// session goes away.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub async fn fade_out_all(&self) -> ZakoResult<()> {
+        let Some(session) = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+        else {
+            return Ok(());
+        };
+        let fade = session.settings.fade_out.duration();
+        if fade.is_zero() {
+            return Ok(());
+        }
+
+        // Tracks lined up behind the playing ones would start under the fade.
+        for track_id in next_music_tracks(&session) {
+            self.mixer.remove_source(track_id);
+        }
+        for track in session.get_active_tracks() {
+            self.mixer.fade_out_and_remove(track.track_id, fade);
+        }
+        tokio::time::sleep(fade).await;
+        Ok(())
+    }
+
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     pub async fn pause(&self, track_id: TrackId) -> ZakoResult<()> {
         tracing::info!(track_id = %track_id, "Pausing track");
         self.decoder.pause_track(track_id);
+        self.mixer.set_paused(track_id, true);
         modify_state_session(
             &self.state_service,
             self.guild_id,
@@ -159,6 +575,7 @@ pub async fn pause(&self, track_id: TrackId) -> ZakoResult<()> {
             },
         )
         .await?;
+        self.emit(track_id, TrackEventKind::Paused);
         Ok(())
     }
 
@@ -177,6 +594,10 @@ pub async fn resume(&self, track_id: TrackId) -> ZakoResult<()> {
         )
         .await?;
         self.decoder.resume_track(track_id);
+        self.mixer.set_paused(track_id, false);
+        self.emit(track_id, TrackEventKind::Resumed);
+        // A track seeked while paused was taken out of the mixer.
+        self.reconcile().await?;
         Ok(())
     }
 
@@ -184,14 +605,31 @@ pub async fn resume(&self, track_id: TrackId) -> ZakoResult<()> {
     pub async fn stop(&self, track_id: TrackId) -> ZakoResult<()> {
         tracing::info!(track_id = %track_id, "Stopping track");
 
-        let queue_name = self
+        if let Some(qn) = self.remove_track(track_id).await? {
+            metrics::record_track_lifecycle("stop", &normalize_queue_name(&qn));
+            self.emit(track_id, TrackEventKind::Stopped);
+        }
+
+        self.reconcile().await?;
+
+        Ok(())
+    }
+
+    /// Take a track out of the mixer and its queue without announcing why.
+    /// Returns the queue it was in, if it was still queued.
+    async fn remove_track(&self, track_id: TrackId) -> ZakoResult<Option<QueueName>> {
+        let session = self
             .state_service
             .get_session(self.guild_id, self.channel_id)
-            .await?
+            .await?;
+        let queue_name = session
+            .as_ref()
             .and_then(|s| s.find_track(track_id).map(|t| t.queue_name.clone()));
 
-        self.mixer.remove_source(track_id);
+        self.mixer
+            .fade_out_and_remove(track_id, fade_out_duration(session.as_ref()));
         self.decoder.stop_track(track_id);
+        self.forget_track(track_id);
         modify_state_session(
             &self.state_service,
             self.guild_id,
@@ -202,13 +640,7 @@ pub async fn stop(&self, track_id: TrackId) -> ZakoResult<()> {
         )
         .await?;
 
-        if let Some(qn) = queue_name {
-            metrics::record_track_lifecycle("stop", &normalize_queue_name(&qn));
-        }
-
-        self.reconcile().await?;
-
-        Ok(())
+        Ok(queue_name)
     }
 
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
@@ -231,10 +663,14 @@ pub async fn stop_many(&self, filter: AudioStopFilter) -> ZakoResult<()> {
             .unwrap_or_default();
 
         let stop_count = track_ids.len();
+        let fade = fade_out_duration(session.as_ref());
 
-        for track_id in track_ids {
-            self.mixer.remove_source(track_id);
+        // Back to front, so a track lined up behind another one is gone
+        // before the one it follows starts fading and would release it.
+        for track_id in track_ids.into_iter().rev() {
+            self.mixer.fade_out_and_remove(track_id, fade);
             self.decoder.stop_track(track_id);
+            self.forget_track(track_id);
 
             if let Some(session) = session.as_mut() {
                 if let Some(track) = session.find_track(track_id) {
@@ -242,6 +678,7 @@ pub async fn stop_many(&self, filter: AudioStopFilter) -> ZakoResult<()> {
                         "stop",
                         &normalize_queue_name(&track.queue_name),
                     );
+                    self.emit(track_id, TrackEventKind::Stopped);
                 }
                 session.remove_track(track_id);
             }
@@ -260,10 +697,12 @@ pub async fn stop_many(&self, filter: AudioStopFilter) -> ZakoResult<()> {
 
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     pub async fn next_music(&self) -> ZakoResult<()> {
-        let music_tracks = self
+        let session = self
             .state_service
             .get_session(self.guild_id, self.channel_id)
-            .await?
+            .await?;
+        let fade = fade_out_duration(session.as_ref());
+        let music_tracks = session
             .map(|s| {
                 s.get_all_track_ids_by_queue_name_prefix("music")
                     .into_iter()
@@ -284,18 +723,24 @@ pub async fn next_music(&self) -> ZakoResult<()> {
 
         tracing::info!(track_id = %current_track_id, "Skipping to next track");
 
-        self.mixer.remove_source(current_track_id);
-        modify_state_session(
-            &self.state_service,
-            self.guild_id,
-            self.channel_id,
-            move |session| {
-                session.remove_track(current_track_id);
-            },
-        )
-        .await?;
+        if self.queue_mode().await?.repeat == RepeatMode::Queue {
+            self.requeue(current_track_id, fade).await?;
+        } else {
+            self.mixer.fade_out_and_remove(current_track_id, fade);
+            self.forget_track(current_track_id);
+            modify_state_session(
+                &self.state_service,
+                self.guild_id,
+                self.channel_id,
+                move |session| {
+                    session.remove_track(current_track_id);
+                },
+            )
+            .await?;
+        }
 
         metrics::record_track_lifecycle("skip", &normalize_queue_name(&queue_name));
+        self.emit(current_track_id, TrackEventKind::Skipped);
 
         self.reconcile().await?;
         self.preload_if_possible(next_track_id).await?;
@@ -359,13 +804,75 @@ pub async fn resume_queue(&self, queue_name: QueueName) -> ZakoResult<()> {
 
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     pub async fn session_state(&self) -> ZakoResult<Option<SessionState>> {
-        self.state_service
+        let Some(mut session) = self
+            .state_service
             .get_session(self.guild_id, self.channel_id)
-            .await
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let track_ids = session.get_all_track_ids();
+        let positions = self.mixer.positions(track_ids).await;
+        let started_from = self.started_from.lock();
+        for track in session.queues.values_mut().flatten() {
+            if let Some(elapsed) = positions.get(&track.track_id) {
+                let start = started_from
+                    .get(&track.track_id)
+                    .copied()
+                    .unwrap_or(track.position_ms);
+                track.position_ms = start + elapsed;
+            }
+        }
+
+        Ok(Some(session))
     }
 
+    /// Persist the live position of every track in the mixer, so a restarted
+    /// AE resumes them from about here instead of from the start.
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
-    async fn reconcile(&self) -> ZakoResult<()> {
+    pub async fn checkpoint(&self) -> ZakoResult<()> {
+        let Some(session) = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let positions = self.mixer.positions(session.get_all_track_ids()).await;
+        let checkpoints: HashMap<TrackId, u64> = {
+            let started_from = self.started_from.lock();
+            positions
+                .into_iter()
+                .filter_map(|(track_id, elapsed)| {
+                    started_from
+                        .get(&track_id)
+                        .map(|start| (track_id, start + elapsed))
+                })
+                .collect()
+        };
+        if checkpoints.is_empty() {
+            return Ok(());
+        }
+
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                for track in session.queues.values_mut().flatten() {
+                    if let Some(&position_ms) = checkpoints.get(&track.track_id) {
+                        track.position_ms = position_ms;
+                    }
+                }
+            },
+        )
+        .await
+    }
+
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    pub(crate) async fn reconcile(&self) -> ZakoResult<()> {
         let _guard = self.reconcile_guard.lock().await;
 
         let session = self
@@ -374,6 +881,8 @@ async fn reconcile(&self) -> ZakoResult<()> {
             .await?;
 
         if let Some(session) = session {
+            let settings = &session.settings;
+            let transitions = settings.transitions;
             let active_tracks = session.get_active_tracks();
 
             let non_paused_ids: Vec<TrackId> = active_tracks
@@ -381,91 +890,490 @@ async fn reconcile(&self) -> ZakoResult<()> {
                 .filter(|t| !t.paused)
                 .map(|t| t.track_id)
                 .collect();
-            let present = self.mixer.has_sources(non_paused_ids).await;
+            let mut playing = self.mixer.has_sources(non_paused_ids).await;
+            playing.extend(self.starting.lock().keys().copied());
+            let top = active_tracks
+                .iter()
+                .filter(|t| !t.paused)
+                .map(|t| t.queue_name.priority())
+                .max()
+                .unwrap_or(QueuePriority::Music);
 
             for track in active_tracks {
                 if track.paused {
+                    self.held.lock().remove(&track.track_id);
                     continue;
                 }
-                if !present.contains(&track.track_id) {
-                    if let Err(e) = self.play_now(track.clone()).await {
-                        tracing::warn!(
-                            track_id = %track.track_id,
-                            queue_name = %track.queue_name,
-                            error = %e,
-                            "Failed to start playback, removing track from queue"
-                        );
-                        self.mixer.remove_source(track.track_id);
-                        let tid = track.track_id;
-                        let qn = track.queue_name.clone();
-                        modify_state_session(
-                            &self.state_service,
-                            self.guild_id,
-                            self.channel_id,
-                            move |session| {
-                                session.remove_track(tid);
-                            },
-                        )
-                        .await?;
-                        metrics::record_track_lifecycle("fail", &normalize_queue_name(&qn));
+                if top.interrupts(track.queue_name.priority()) {
+                    // Held where it is; one that has not started yet waits.
+                    if playing.contains(&track.track_id) {
+                        self.decoder.pause_track(track.track_id);
+                        self.mixer.set_paused(track.track_id, true);
+                        if self.held.lock().insert(track.track_id) {
+                            self.emit(track.track_id, TrackEventKind::Paused);
+                        }
                     }
+                    continue;
+                }
+                if self.held.lock().remove(&track.track_id) {
+                    self.decoder.resume_track(track.track_id);
+                    self.mixer.set_paused(track.track_id, false);
+                    self.emit(track.track_id, TrackEventKind::Resumed);
+                }
+                if playing.contains(&track.track_id) {
+                    // The mixer already handed over to a lined-up track.
+                    if self.lined_up.lock().remove(&track.track_id) {
+                        self.announce_started(&track);
+                    }
+                } else if let Err(e) = self.play_now(track.clone(), None, settings).await {
+                    tracing::warn!(
+                        track_id = %track.track_id,
+                        queue_name = %track.queue_name,
+                        error = %e,
+                        "Failed to start playback, removing track from queue"
+                    );
+                    self.mixer.remove_source(track.track_id);
+                    self.forget_track(track.track_id);
+                    let tid = track.track_id;
+                    let qn = track.queue_name.clone();
+                    modify_state_session(
+                        &self.state_service,
+                        self.guild_id,
+                        self.channel_id,
+                        move |session| {
+                            session.remove_track(tid);
+                        },
+                    )
+                    .await?;
+                    metrics::record_track_lifecycle("fail", &normalize_queue_name(&qn));
+                    self.emit(
+                        tid,
+                        TrackEventKind::Failed {
+                            error: tap_error(e),
+                        },
+                    );
+                } else {
+                    playing.insert(track.track_id);
                 }
             }
+
+            if transitions.enabled() {
+                self.queue_next_music(&session, &playing, &transitions)
+                    .await;
+            }
         }
 
         Ok(())
     }
 
+    /// Start the second track of each playing music queue ahead of time so
+    /// the mixer can hand over to it without a gap, or crossfade into it.
+    async fn queue_next_music(
+        &self,
+        session: &SessionState,
+        playing: &HashSet<TrackId>,
+        transitions: &TransitionSettings,
+    ) {
+        if session.queue_mode.repeat == RepeatMode::One {
+            return;
+        }
+        // A head still buffering is not in the mixer to follow yet; its
+        // hand-over queues the next track instead.
+        let starting = self.starting.lock().clone();
+        let pairs: Vec<(&Track, &Track)> = session
+            .queues
+            .iter()
+            .filter(|(name, _)| source_class(name) == SourceClass::Music)
+            .filter_map(|(_, tracks)| match tracks.as_slice() {
+                [head, next, ..]
+                    if playing.contains(&head.track_id)
+                        && !starting.contains_key(&head.track_id)
+                        && !next.paused =>
+                {
+                    Some((head, next))
+                }
+                _ => None,
+            })
+            .collect();
+        if pairs.is_empty() {
+            return;
+        }
+
+        let queued = self
+            .mixer
+            .has_sources(pairs.iter().map(|(_, next)| next.track_id).collect())
+            .await;
+
+        for (head, next) in pairs {
+            if queued.contains(&next.track_id) || starting.contains_key(&next.track_id) {
+                continue;
+            }
+            let follow = Follow {
+                after: head.track_id,
+                crossfade_ms: transitions.crossfade_ms(),
+            };
+            // A failure here is retried, and handled, once the track reaches
+            // the head of its queue.
+            if let Err(e) = self
+                .play_now(next.clone(), Some(follow), &session.settings)
+                .await
+            {
+                tracing::warn!(
+                    track_id = %next.track_id,
+                    error = %e,
+                    "Failed to queue next track"
+                );
+            }
+        }
+    }
+
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
-    async fn play_now(&self, track: Track) -> ZakoResult<()> {
+    async fn play_now(
+        &self,
+        track: Track,
+        follow: Option<Follow>,
+        settings: &SessionAudioSettings,
+    ) -> ZakoResult<()> {
         tracing::info!(
             track_id = %track.track_id,
             queue_name = %track.queue_name,
+            follows = ?follow.map(|f| f.after),
             "Starting playback"
         );
 
+        let mut request = track.request.clone();
+        request.start_offset_ms = track.position_ms;
         let response = tokio::time::timeout(
             Duration::from_secs(30),
-            self.taphub_service.request_audio(track.request.clone()),
+            self.taphub_service.request_audio(request),
         )
         .await
         .map_err(|_| ZakoError::TaphubTimeout)??;
 
-        let consumer = self
+        let duration_ms = track.duration_ms.or(response.duration_ms);
+        if track.duration_ms.is_none()
+            && let Some(duration_ms) = response.duration_ms
+        {
+            let tid = track.track_id;
+            modify_state_session(
+                &self.state_service,
+                self.guild_id,
+                self.channel_id,
+                move |session| {
+                    if let Some(track) = session.find_track_mut(tid) {
+                        track.duration_ms = Some(duration_ms);
+                    }
+                },
+            )
+            .await?;
+        }
+
+        let loudness = self.track_loudness(&track, response.loudness_lufs);
+        let preroll = self
             .decoder
-            .start_decoding(track.track_id, response.stream)
+            .start_decoding(track.track_id, response.stream, loudness)
             .await?;
 
-        self.mixer
-            .add_source(track.track_id, consumer, self.end_tx.clone());
+        match preroll.try_ready() {
+            Ok(consumer) => self.hand_over(&track, consumer, follow, duration_ms, settings),
+            Err(preroll) => {
+                // Waiting here would hold the reconcile guard, and every
+                // command behind it, for as long as the tap is slow.
+                let attempt: u64 = id_gen::generate_id();
+                self.starting.lock().insert(track.track_id, attempt);
+                let ready_tx = self.ready_tx.clone();
+                let track_id = track.track_id;
+                tokio::spawn(async move {
+                    let consumer = preroll.wait().await;
+                    let _ = ready_tx
+                        .send(Prerolled {
+                            track_id,
+                            attempt,
+                            consumer,
+                            follow,
+                            duration_ms,
+                        })
+                        .await;
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hand a buffered source over to the mixer, unless the track was
+    /// stopped, seeked or removed while it buffered.
+    async fn finish_preroll(&self, source: Prerolled) -> ZakoResult<()> {
+        let _guard = self.reconcile_guard.lock().await;
+        {
+            let mut starting = self.starting.lock();
+            if starting.get(&source.track_id) != Some(&source.attempt) {
+                return Ok(());
+            }
+            starting.remove(&source.track_id);
+        }
+
+        let Some(session) = self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+        else {
+            return Ok(());
+        };
+        let Some(track) = session.find_track(source.track_id) else {
+            return Ok(());
+        };
+
+        // The track it was to follow may have ended in the meantime.
+        let follow = match source.follow {
+            Some(follow)
+                if self
+                    .mixer
+                    .has_sources(vec![follow.after])
+                    .await
+                    .contains(&follow.after) =>
+            {
+                Some(follow)
+            }
+            _ => None,
+        };
+        self.hand_over(
+            track,
+            source.consumer,
+            follow,
+            source.duration_ms,
+            &session.settings,
+        );
+
+        let transitions = session.settings.transitions;
+        if follow.is_none() && transitions.enabled() {
+            let playing = HashSet::from([track.track_id]);
+            self.queue_next_music(&session, &playing, &transitions)
+                .await;
+        }
+        Ok(())
+    }
+
+    fn hand_over(
+        &self,
+        track: &Track,
+        consumer: RingCons,
+        follow: Option<Follow>,
+        duration_ms: Option<u64>,
+        settings: &SessionAudioSettings,
+    ) {
+        let lined_up = follow.is_some();
+        match follow {
+            Some(follow) => {
+                self.mixer
+                    .queue_source(track.track_id, consumer, self.end_tx.clone(), follow)
+            }
+            None => self
+                .mixer
+                .add_source(track.track_id, consumer, self.end_tx.clone()),
+        }
+        self.started_from
+            .lock()
+            .insert(track.track_id, track.position_ms);
+        if settings.transitions.crossfade_ms() > 0
+            && let Some(duration_ms) = duration_ms
+        {
+            self.mixer.set_remaining(
+                track.track_id,
+                duration_ms.saturating_sub(track.position_ms),
+            );
+        }
         self.mixer.set_volume(track.track_id, track.volume.into());
+        self.mixer
+            .set_source_class(track.track_id, source_class(&track.queue_name));
+        if !track.dsp.is_identity() {
+            self.mixer.set_dsp(track.track_id, track.dsp);
+        }
+        let pan = self.track_pan(track, settings);
+        if !pan.is_center() {
+            self.mixer.set_pan(track.track_id, pan.into());
+        }
+        // Paused, or held for an announcement, while it buffered.
+        if track.paused || self.held.lock().contains(&track.track_id) {
+            self.mixer.set_paused(track.track_id, true);
+        }
+
+        if lined_up {
+            self.lined_up.lock().insert(track.track_id);
+        } else {
+            self.lined_up.lock().remove(&track.track_id);
+            self.announce_started(track);
+        }
+    }
+
+    /// Drop what the session tracks about a track that left the mixer.
+    fn forget_track(&self, track_id: TrackId) {
+        self.held.lock().remove(&track_id);
+        self.started_from.lock().remove(&track_id);
+        self.lined_up.lock().remove(&track_id);
+        self.starting.lock().remove(&track_id);
+        self.pan_slots.lock().release(track_id);
+    }
 
+    /// Where a track sits in the stereo field. TTS tracks without an
+    /// explicit pan take their queue's position when auto-pan is on.
+    fn track_pan(&self, track: &Track, settings: &SessionAudioSettings) -> Pan {
+        let qn: String = track.queue_name.clone().into();
+        match track.pan {
+            Some(pan) => pan,
+            None if settings.tts_pan.enabled && qn.starts_with("tts_") => {
+                let slot = self
+                    .pan_slots
+                    .lock()
+                    .acquire(&track.queue_name, track.track_id);
+                settings.tts_pan.position(slot)
+            }
+            None => Pan::CENTER,
+        }
+    }
+
+    fn announce_started(&self, track: &Track) {
         metrics::record_track_lifecycle("start", &normalize_queue_name(&track.queue_name));
+        self.emit(track.track_id, TrackEventKind::Started);
+    }
 
-        Ok(())
+    /// Feed the known loudness to the decoder, or arrange for the measured
+    /// value to be written back to the cache once the track is fully decoded.
+    /// A track started part-way through is never written back.
+    fn track_loudness(&self, track: &Track, known_lufs: Option<f32>) -> TrackLoudness {
+        if known_lufs.is_some()
+            || track.position_ms > 0
+            || matches!(track.request.cache_key.cache_type, AudioCacheType::None)
+        {
+            return TrackLoudness {
+                known_lufs,
+                report_tx: None,
+            };
+        }
+
+        let (report_tx, report_rx) = oneshot::channel();
+        let taphub_service = self.taphub_service.clone();
+        let request = track.request.clone();
+        tokio::spawn(async move {
+            if let Ok(lufs) = report_rx.await
+                && let Err(e) = taphub_service.store_loudness(request, lufs).await
+            {
+                tracing::warn!(error = %e, "Failed to store measured loudness");
+            }
+        });
+
+        TrackLoudness {
+            known_lufs: None,
+            report_tx: Some(report_tx),
+        }
     }
 
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     async fn handle_ended_track(&self, track_id: TrackId) -> ZakoResult<()> {
         tracing::info!(track_id = %track_id, "Track ended naturally");
 
-        let queue_name = self
+        let (queue_name, repeat) = self
             .state_service
             .get_session(self.guild_id, self.channel_id)
             .await?
-            .and_then(|s| s.find_track(track_id).map(|t| t.queue_name.clone()));
+            .and_then(|s| {
+                s.find_track(track_id)
+                    .map(|t| (Some(t.queue_name.clone()), s.queue_mode.repeat))
+            })
+            .unwrap_or((None, RepeatMode::Off));
 
         if let Some(qn) = &queue_name {
             metrics::record_track_lifecycle("end", &normalize_queue_name(qn));
+            self.emit(track_id, TrackEventKind::Ended);
         }
 
-        self.stop(track_id).await?;
+        let is_music = queue_name
+            .as_ref()
+            .is_some_and(|qn| source_class(qn) == SourceClass::Music);
+        match repeat {
+            RepeatMode::One if is_music => self.seek(track_id, 0).await?,
+            RepeatMode::Queue if is_music => self.requeue(track_id, Duration::ZERO).await?,
+            // Already announced as ended, not stopped.
+            _ => {
+                self.remove_track(track_id).await?;
+                self.reconcile().await?;
+            }
+        }
 
         self.preload_if_possible(track_id).await?;
 
         Ok(())
     }
 
+    async fn next_music_tracks(&self) -> ZakoResult<HashSet<TrackId>> {
+        Ok(self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+            .map(|s| next_music_tracks(&s))
+            .unwrap_or_default())
+    }
+
+    /// A track lined up in the mixer behind a playing one starts as soon as
+    /// that one ends. Take it out again when it is no longer next.
+    async fn release_unqueued(&self, queued_before: HashSet<TrackId>) -> ZakoResult<()> {
+        let queued_after = match self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+        {
+            Some(s) if s.queue_mode.repeat != RepeatMode::One => next_music_tracks(&s),
+            _ => HashSet::new(),
+        };
+        for &track_id in queued_before.difference(&queued_after) {
+            self.mixer.remove_source(track_id);
+            self.decoder.stop_track(track_id);
+            self.forget_track(track_id);
+        }
+        Ok(())
+    }
+
+    async fn queue_mode(&self) -> ZakoResult<QueueMode> {
+        Ok(self
+            .state_service
+            .get_session(self.guild_id, self.channel_id)
+            .await?
+            .map(|s| s.queue_mode)
+            .unwrap_or_default())
+    }
+
+    /// Send a track back to the end of its queue, or to a random place in it
+    /// when shuffling, to be played again from the start.
+    #[instrument(skip(self), fields(guild_id = %self.guild_id))]
+    async fn requeue(&self, track_id: TrackId, fade: Duration) -> ZakoResult<()> {
+        tracing::info!(track_id = %track_id, "Requeueing track");
+        self.mixer.fade_out_and_remove(track_id, fade);
+        self.decoder.stop_track(track_id);
+        self.forget_track(track_id);
+        modify_state_session(
+            &self.state_service,
+            self.guild_id,
+            self.channel_id,
+            move |session| {
+                let shuffle = session.queue_mode.shuffle;
+                for queue in session.queues.values_mut() {
+                    if let Some(index) = queue.iter().position(|t| t.track_id == track_id) {
+                        let mut track = queue.remove(index);
+                        track.position_ms = 0;
+                        queue.push(track);
+                        if shuffle {
+                            move_last_to_random_slot(queue);
+                        }
+                        break;
+                    }
+                }
+            },
+        )
+        .await?;
+
+        self.reconcile().await
+    }
+
     #[instrument(skip(self), fields(guild_id = %self.guild_id))]
     async fn preload_if_possible(&self, track_id: TrackId) -> ZakoResult<()> {
         let session = self
@@ -513,7 +1421,9 @@ async fn preload_if_possible(&self, track_id: TrackId) -> ZakoResult<()> {
 
 fn normalize_queue_name(queue_name: &QueueName) -> String {
     let qn: String = queue_name.clone().into();
-    if qn.starts_with("tts_") {
+    if qn.starts_with(QueueName::ANNOUNCEMENT_PREFIX) {
+        "announcement".to_string()
+    } else if qn.starts_with("tts_") {
         "tts".to_string()
     } else if qn.starts_with("music") {
         "music".to_string()
@@ -522,6 +1432,105 @@ fn normalize_queue_name(queue_name: &QueueName) -> String {
     }
 }
 
+fn source_class(queue_name: &QueueName) -> SourceClass {
+    let qn: String = queue_name.clone().into();
+    if qn.starts_with("tts_")
+        || qn.starts_with("temp-")
+        || qn.starts_with(QueueName::ANNOUNCEMENT_PREFIX)
+    {
+        SourceClass::Speech
+    } else if qn.starts_with("music") {
+        SourceClass::Music
+    } else {
+        SourceClass::Other
+    }
+}
+
+/// Auto-pan slots of the TTS queues, handed out round-robin.
+#[derive(Default)]
+struct PanSlots {
+    /// Slot of each queue, and its tracks in the mixer.
+    queues: HashMap<QueueName, (u64, HashSet<TrackId>)>,
+    /// Where the search for the next slot starts.
+    next: u64,
+}
+
+impl PanSlots {
+    /// The slot of `queue_name`, now held by `track_id`. A queue without one
+    /// takes the next slot no queue holds, then the next one held by a queue
+    /// with nothing playing, and only shares a slot when all are in use.
+    fn acquire(&mut self, queue_name: &QueueName, track_id: TrackId) -> u64 {
+        if let Some((slot, tracks)) = self.queues.get_mut(queue_name) {
+            tracks.insert(track_id);
+            return *slot;
+        }
+
+        let holder = |slot: u64| self.queues.iter().find(|(_, (s, _))| *s == slot);
+        let in_turn = (0..TtsPanSettings::POSITIONS)
+            .map(|i| (self.next + i) % TtsPanSettings::POSITIONS)
+            .collect::<Vec<_>>();
+        let slot = in_turn
+            .iter()
+            .copied()
+            .find(|&slot| holder(slot).is_none())
+            .or_else(|| {
+                in_turn
+                    .iter()
+                    .copied()
+                    .find(|&slot| holder(slot).is_some_and(|(_, (_, tracks))| tracks.is_empty()))
+            })
+            .unwrap_or(self.next);
+
+        self.queues
+            .retain(|_, (held, tracks)| *held != slot || !tracks.is_empty());
+        self.queues
+            .insert(queue_name.clone(), (slot, HashSet::from([track_id])));
+        self.next = (slot + 1) % TtsPanSettings::POSITIONS;
+        slot
+    }
+
+    /// `track_id` left the mixer. Its queue keeps the slot until another
+    /// queue needs it.
+    fn release(&mut self, track_id: TrackId) {
+        for (_, tracks) in self.queues.values_mut() {
+            tracks.remove(&track_id);
+        }
+    }
+}
+
+/// The track right behind `track` in its queue.
+fn next_in_queue(session: &SessionState, track: &Track) -> Option<TrackId> {
+    let queue = session.queues.get(&track.queue_name)?;
+    let index = queue.iter().position(|t| t.track_id == track.track_id)?;
+    queue.get(index + 1).map(|t| t.track_id)
+}
+
+/// How long a stopped track fades out for, per the session's settings.
+fn fade_out_duration(session: Option<&SessionState>) -> Duration {
+    session
+        .map(|s| s.settings.fade_out.duration())
+        .unwrap_or_default()
+}
+
+/// The track lined up behind the playing one in each music queue.
+fn next_music_tracks(session: &SessionState) -> HashSet<TrackId> {
+    session
+        .queues
+        .iter()
+        .filter(|(name, _)| source_class(name) == SourceClass::Music)
+        .filter_map(|(_, tracks)| tracks.get(1).map(|t| t.track_id))
+        .collect()
+}
+
+/// Move the last track to a random place behind the playing track and the
+/// one already lined up after it.
+fn move_last_to_random_slot(queue: &mut Vec<Track>) {
+    if let Some(track) = queue.pop() {
+        let index = rand::rng().random_range(queue.len().min(2)..=queue.len());
+        queue.insert(index, track);
+    }
+}
+
 fn upsert_track(queues: &mut HashMap<QueueName, Vec<Track>>, queue_name: QueueName, track: Track) {
     if let Some(queue) = queues.get_mut(&queue_name) {
         queue.push(track);
@@ -530,6 +1539,35 @@ fn upsert_track(queues: &mut HashMap<QueueName, Vec<Track>>, queue_name: QueueNa
     }
 }
 
+/// Insert a track at `index`, but never in front of the playing track.
+fn insert_track(
+    queues: &mut HashMap<QueueName, Vec<Track>>,
+    queue_name: QueueName,
+    track: Track,
+    index: usize,
+) {
+    let queue = queues.entry(queue_name).or_default();
+    let index = index.clamp(queue.len().min(1), queue.len());
+    queue.insert(index, track);
+}
+
+/// A source handed back to the session once its pre-roll is buffered.
+struct Prerolled {
+    track_id: TrackId,
+    attempt: u64,
+    consumer: RingCons,
+    follow: Option<Follow>,
+    duration_ms: Option<u64>,
+}
+
+/// The failure as reported in lifecycle events.
+fn tap_error(e: ZakoError) -> TapHubError {
+    match e {
+        ZakoError::TapHub(t) => t,
+        e => TapHubError::Internal(e.to_string()),
+    }
+}
+
 pub fn create_session_control(
     guild_id: GuildId,
     channel_id: ChannelId,
@@ -537,17 +1575,47 @@ pub fn create_session_control(
     decoder: ArcDecoder,
     state_service: ArcStateService,
     taphub_service: ArcTapHubService,
+) -> Arc<SessionControl> {
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    create_session_control_with_events(
+        guild_id,
+        channel_id,
+        mixer,
+        decoder,
+        state_service,
+        taphub_service,
+        events,
+        PathBuf::from(DEFAULT_RECORDING_DIR),
+    )
+}
+
+/// Like `create_session_control`, publishing lifecycle events to `events`
+/// and writing full recordings under `recording_dir`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_session_control_with_events(
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    mixer: ArcMixer,
+    decoder: ArcDecoder,
+    state_service: ArcStateService,
+    taphub_service: ArcTapHubService,
+    events: broadcast::Sender<SessionEvent>,
+    recording_dir: PathBuf,
 ) -> Arc<SessionControl> {
     let (end_tx, end_rx) = tokio::sync::mpsc::channel(16);
+    let (ready_tx, mut ready_rx) = tokio::sync::mpsc::channel::<Prerolled>(16);
 
     let session_control = Arc::new(SessionControl::new(
         guild_id,
         channel_id,
         end_tx,
+        ready_tx,
         mixer,
         decoder,
         state_service,
         taphub_service,
+        events,
+        recording_dir,
     ));
 
     let sc_clone = session_control.clone();
@@ -560,5 +1628,19 @@ pub fn create_session_control(
         }
     });
 
+    // Weak, so the session is not kept alive by its own pending sources.
+    let sc_weak = Arc::downgrade(&session_control);
+    tokio::spawn(async move {
+        while let Some(source) = ready_rx.recv().await {
+            let Some(sc) = sc_weak.upgrade() else {
+                break;
+            };
+            let track_id = source.track_id;
+            if let Err(e) = sc.finish_preroll(source).await {
+                tracing::warn!(track_id = %track_id, error = %e, "Failed to hand over source");
+            }
+        }
+    });
+
     session_control
 }
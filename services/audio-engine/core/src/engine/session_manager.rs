@@ -1,15 +1,20 @@
-use std::sync::Arc;
+use std::{
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
 
 use dashmap::DashMap;
+use tokio::sync::broadcast;
 use tracing::instrument;
 use zako3_audio_engine_audio::{create_opus_ringbuf_pair, metrics};
 
 use crate::{
-    audio::{PcmDecoder, create_thread_mixer},
+    DEFAULT_RECORDING_DIR, EVENT_CHANNEL_CAPACITY,
+    audio::{DecoderConfig, MixerPool, PcmDecoder, create_thread_mixer},
     error::ZakoResult,
     service::{ArcDiscordService, ArcStateService, ArcTapHubService},
-    session::{SessionControl, create_session_control},
-    types::{ChannelId, GuildId, SessionState},
+    session::{SessionControl, create_session_control_with_events},
+    types::{AudioCacheType, ChannelId, GuildId, SessionEvent, SessionState},
 };
 
 pub struct SessionManager {
@@ -18,6 +23,12 @@ pub struct SessionManager {
     taphub_service: ArcTapHubService,
 
     sessions: DashMap<(GuildId, ChannelId), Arc<SessionControl>>,
+    events: broadcast::Sender<SessionEvent>,
+    recording_dir: PathBuf,
+    decoder_config: DecoderConfig,
+    // Built on first use unless one is passed in, so an injected pool does
+    // not leave a default one's threads behind.
+    mixer_pool: OnceLock<MixerPool>,
 }
 
 impl SessionManager {
@@ -31,25 +42,54 @@ pub fn new(
             state_service,
             taphub_service,
             sessions: DashMap::new(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            recording_dir: PathBuf::from(DEFAULT_RECORDING_DIR),
+            decoder_config: DecoderConfig::default(),
+            mixer_pool: OnceLock::new(),
         }
     }
 
+    /// Write full session recordings under `dir`.
+    pub fn with_recording_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.recording_dir = dir.into();
+        self
+    }
+
+    /// Buffer and pace every session's tracks with `config`.
+    pub fn with_decoder_config(mut self, config: DecoderConfig) -> Self {
+        self.decoder_config = config;
+        self
+    }
+
+    /// Tick every session's mixer on `pool`.
+    pub fn with_mixer_pool(mut self, pool: MixerPool) -> Self {
+        self.mixer_pool = OnceLock::from(pool);
+        self
+    }
+
+    /// Track lifecycle events from every session, as they happen.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
     #[instrument(skip(self), fields(guild_id = %guild_id, channel_id = %channel_id))]
     async fn initiate_session(&self, guild_id: GuildId, channel_id: ChannelId) -> ZakoResult<()> {
         tracing::debug!("Initiating audio session");
 
         let (prod, cons) = create_opus_ringbuf_pair();
 
-        let mixer = create_thread_mixer(prod);
-        let decoder = PcmDecoder::new();
+        let mixer = create_thread_mixer(self.mixer_pool.get_or_init(MixerPool::default), prod);
+        let decoder = PcmDecoder::with_config(self.decoder_config);
 
-        let control = create_session_control(
+        let control = create_session_control_with_events(
             guild_id,
             channel_id,
             Arc::new(mixer),
             Arc::new(decoder),
             self.state_service.clone(),
             self.taphub_service.clone(),
+            self.events.clone(),
+            self.recording_dir.clone(),
         );
 
         self.discord_service.play_audio(guild_id, cons).await?;
@@ -74,6 +114,8 @@ pub async fn join(&self, guild_id: GuildId, channel_id: ChannelId) -> ZakoResult
             guild_id,
             channel_id,
             queues: Default::default(),
+            settings: Default::default(),
+            queue_mode: Default::default(),
         };
 
         self.initiate_session(guild_id, channel_id).await?;
@@ -93,11 +135,37 @@ pub async fn rejoin(&self, session: &SessionState) -> ZakoResult<()> {
 
         self.initiate_session(session.guild_id, session.channel_id)
             .await?;
-        self.state_service.save_session(session).await?;
+        let control = self.get_session(session.guild_id, session.channel_id);
+        if let Some(control) = &control {
+            control.apply_settings(&session.settings);
+        }
+
+        // Only cached tracks can be sought cheaply; anything else restarts.
+        let mut session = session.clone();
+        for track in session.queues.values_mut().flatten() {
+            if matches!(track.request.cache_key.cache_type, AudioCacheType::None) {
+                track.position_ms = 0;
+            }
+        }
+        self.state_service.save_session(&session).await?;
+
+        if let Some(control) = control {
+            control.reconcile().await?;
+        }
 
         Ok(())
     }
 
+    /// Persist the live playback position of every active session.
+    pub async fn checkpoint_all(&self) {
+        let controls: Vec<_> = self.sessions.iter().map(|e| e.value().clone()).collect();
+        for control in controls {
+            if let Err(e) = control.checkpoint().await {
+                tracing::warn!(error = %e, "Failed to checkpoint playback positions");
+            }
+        }
+    }
+
     pub async fn list_sessions(&self) -> ZakoResult<Vec<SessionState>> {
         self.state_service.list_sessions().await
     }
@@ -110,6 +178,12 @@ pub async fn get_sessions_in_guild(&self, guild_id: GuildId) -> ZakoResult<Vec<S
     pub async fn leave(&self, guild_id: GuildId, channel_id: ChannelId) -> ZakoResult<()> {
         tracing::info!("Leaving voice channel");
 
+        if let Some(control) = self.get_session(guild_id, channel_id)
+            && let Err(e) = control.fade_out_all().await
+        {
+            tracing::warn!(error = %e, "Failed to fade out before leaving");
+        }
+
         self.discord_service.leave_voice_channel(guild_id).await?;
         self.state_service
             .delete_session(guild_id, channel_id)
@@ -1,9 +1,10 @@
 use std::time::Duration;
 use zako3_audio_engine_audio::ringbuf::traits::consumer::Consumer;
+use zako3_audio_engine_audio::ringbuf::traits::{Observer, Producer};
 
 use crate::types::TrackId;
 use zako3_audio_engine_audio::{
-    Mixer, create_opus_ringbuf_pair, create_ringbuf_pair, create_thread_mixer,
+    Mixer, MixerPool, create_opus_ringbuf_pair, create_ringbuf_pair, create_thread_mixer,
 };
 
 #[tokio::test]
@@ -11,7 +12,8 @@ async fn test_mixer_add_remove_source() {
     let (output_prod, mut output_cons) = create_opus_ringbuf_pair();
     // Drain output to prevent blocking the mixer thread
     tokio::spawn(async move { while Consumer::try_pop(&mut output_cons).is_some() {} });
-    let mixer = create_thread_mixer(output_prod);
+    let pool = MixerPool::new(1);
+    let mixer = create_thread_mixer(&pool, output_prod);
 
     let track_id = TrackId::from(1);
     let (_source_prod, source_cons) = create_ringbuf_pair();
@@ -32,3 +34,57 @@ async fn test_mixer_add_remove_source() {
     // Verify source is removed
     assert!(!mixer.has_source(track_id).await);
 }
+
+#[tokio::test]
+async fn test_mixer_fade_out_and_remove_source() {
+    let (output_prod, mut output_cons) = create_opus_ringbuf_pair();
+    tokio::spawn(async move { while Consumer::try_pop(&mut output_cons).is_some() {} });
+    let pool = MixerPool::new(1);
+    let mixer = create_thread_mixer(&pool, output_prod);
+
+    let track_id = TrackId::from(1);
+    let (_source_prod, source_cons) = create_ringbuf_pair();
+    let (end_tx, mut end_rx) = tokio::sync::mpsc::channel(16);
+
+    mixer.add_source(track_id, source_cons, end_tx);
+    assert!(mixer.has_source(track_id).await);
+
+    // The source is gone for the session right away, while it fades out.
+    mixer.fade_out_and_remove(track_id, Duration::from_millis(100));
+    assert!(!mixer.has_source(track_id).await);
+
+    // Removal is not a natural end.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(end_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_mixer_pool_shares_workers_between_sessions() {
+    let pool = MixerPool::new(2);
+    let mut sessions = Vec::new();
+
+    for id in 1..=4 {
+        let (output_prod, output_cons) = create_opus_ringbuf_pair();
+        let mixer = pool.create_mixer(output_prod);
+        let (mut source_prod, source_cons) = create_ringbuf_pair();
+        source_prod.push_slice(&[0.1; 1920 * 3]);
+        drop(source_prod);
+        let (end_tx, end_rx) = tokio::sync::mpsc::channel(16);
+        mixer.add_source(TrackId::from(id), source_cons, end_tx);
+        sessions.push((mixer, output_cons, end_rx));
+    }
+
+    // Every session is ticked and runs its source to the end.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for (id, (_, output_cons, end_rx)) in (1..=4).zip(sessions.iter_mut()) {
+        assert_eq!(end_rx.try_recv().unwrap(), TrackId::from(id));
+        assert!(output_cons.occupied_len() >= 3);
+    }
+
+    // Dropping a mixer ends its session and releases the output stream.
+    let (mixer, output_cons, _) = sessions.pop().unwrap();
+    drop(mixer);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!output_cons.write_is_held());
+    assert!(sessions[0].1.write_is_held());
+}
@@ -4,14 +4,21 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use zako3_audio_engine_audio::{MockDecoder, MockMixer, create_ringbuf_pair};
+use tokio::sync::broadcast;
+use zako3_audio_engine_audio::recorder::Recorder;
+use zako3_audio_engine_audio::{
+    Follow, MockDecoder, MockMixer, Preroll, SourceClass, create_ringbuf_pair,
+};
 
-use crate::engine::session::create_session_control;
+use crate::engine::session::{create_session_control, create_session_control_with_events};
+use crate::error::ZakoError;
 use crate::service::{state::MockStateService, taphub::MockTapHubService};
 use crate::types::{
     AudioCachePolicy, AudioCacheType, AudioMetaResponse, AudioMetadata, AudioRequestString,
-    AudioResponse, CachedAudioRequest, ChannelId, GuildId, QueueName, SessionState, Track,
-    TrackId, Volume,
+    AudioResponse, CachedAudioRequest, ChannelId, DuckingSettings, EncoderSettings, GuildId,
+    LoudnessSettings, OpusSignal, Pan, QueueMode, QueueName, RecordingMode, RecordingSettings,
+    RepeatMode, SessionAudioSettings, SessionState, Track, TrackDsp, TrackEventKind, TrackId,
+    TransitionSettings, TtsPanSettings, Volume,
 };
 use zako3_types::hq::TapId;
 use zako3_types::hq::DiscordUserId;
@@ -30,10 +37,15 @@ fn create_dummy_track(id: u64, queue: &str) -> Track {
             },
             discord_user_id: DiscordUserId::from("123".to_string()),
             headers: Default::default(),
+            start_offset_ms: 0,
         },
         volume: Volume::from(1.0),
         queue_name: QueueName::from(queue.to_string()),
         paused: false,
+        dsp: TrackDsp::default(),
+        pan: None,
+        duration_ms: None,
+        position_ms: 0,
     }
 }
 
@@ -57,6 +69,8 @@ async fn test_play_success() {
                     ttl_seconds: None,
                 },
                 base_volume: 1.0,
+                loudness_lufs: None,
+                duration_ms: None,
             })
         });
 
@@ -69,6 +83,8 @@ async fn test_play_success() {
                 guild_id,
                 channel_id: ChannelId::from(100),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
 
@@ -87,6 +103,8 @@ async fn test_play_success() {
                 guild_id,
                 channel_id: ChannelId::from(100),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -111,21 +129,29 @@ async fn test_play_success() {
                 ttl_seconds: None,
             }),
             stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: Some(-20.0),
+            duration_ms: None,
         })
     });
 
-    // 7. play_now -> decoder
+    // 7. play_now -> decoder (known loudness is passed through, nothing to report)
     mock_decoder
         .expect_start_decoding()
+        .withf(|_, _, loudness| loudness.known_lufs == Some(-20.0) && loudness.report_tx.is_none())
         .times(1)
-        .returning(|_, _| {
+        .returning(|_, _, _| {
             let (_, c) = create_ringbuf_pair();
-            Ok(c)
+            Ok(Preroll::ready(c))
         });
 
     // 8. play_now -> mixer
     mock_mixer.expect_add_source().times(1).return_const(());
     mock_mixer.expect_set_volume().times(1).return_const(());
+    mock_mixer
+        .expect_set_source_class()
+        .with(eq(TrackId::from(999)), eq(SourceClass::Music))
+        .times(1)
+        .return_const(());
 
     let control = create_session_control(
         guild_id,
@@ -143,6 +169,7 @@ async fn test_play_success() {
             AudioRequestString::from("test".to_string()),
             Volume::from(1.0),
             DiscordUserId::from("123".to_string()),
+            TrackDsp::default(),
         )
         .await;
 
@@ -166,6 +193,8 @@ async fn test_play_queued() {
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     });
 
@@ -178,6 +207,8 @@ async fn test_play_queued() {
                 guild_id,
                 channel_id: ChannelId::from(100),
                 queues: HashMap::new(), // simplified
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
     mock_state
@@ -194,6 +225,8 @@ async fn test_play_queued() {
                 guild_id,
                 channel_id: ChannelId::from(100),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -231,6 +264,7 @@ async fn test_play_queued() {
             AudioRequestString::from("t".to_string()),
             Volume::from(1.0),
             DiscordUserId::from("123".to_string()),
+            TrackDsp::default(),
         )
         .await;
 
@@ -247,8 +281,8 @@ async fn test_stop_success() {
     let track_id = TrackId::from(100);
 
     mock_mixer
-        .expect_remove_source()
-        .with(eq(track_id))
+        .expect_fade_out_and_remove()
+        .with(eq(track_id), eq(Duration::from_millis(80)))
         .times(1)
         .return_const(());
 
@@ -267,6 +301,8 @@ async fn test_stop_success() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -283,6 +319,8 @@ async fn test_stop_success() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -304,6 +342,8 @@ async fn test_stop_success() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
 
@@ -328,8 +368,8 @@ async fn test_stop_non_existent() {
     let track_id = TrackId::from(999);
 
     mock_mixer
-        .expect_remove_source()
-        .with(eq(track_id))
+        .expect_fade_out_and_remove()
+        .with(eq(track_id), always())
         .return_const(());
 
     mock_decoder
@@ -347,6 +387,8 @@ async fn test_stop_non_existent() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
     // Get session (empty)
@@ -358,6 +400,8 @@ async fn test_stop_non_existent() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
     // Save session (still empty)
@@ -374,6 +418,8 @@ async fn test_stop_non_existent() {
                 guild_id,
                 channel_id: ChannelId::from(200),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             }))
         });
 
@@ -411,6 +457,8 @@ async fn test_set_volume() {
                 guild_id,
                 channel_id: ChannelId::from(300),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -439,6 +487,360 @@ async fn test_set_volume() {
     );
 }
 
+#[tokio::test]
+async fn test_set_dsp_clamps_and_saves() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+    let track_id = TrackId::from(100);
+
+    let expected = TrackDsp {
+        speed: 2.0,
+        pitch: -12.0,
+        tempo: 1.25,
+    };
+
+    mock_mixer
+        .expect_set_dsp()
+        .with(eq(track_id), eq(expected))
+        .times(1)
+        .return_const(());
+
+    mock_state
+        .expect_get_session()
+        .times(1)
+        .returning(move |_, _| {
+            let mut s = SessionState {
+                guild_id,
+                channel_id: ChannelId::from(300),
+                queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
+            };
+            s.queues.insert(
+                QueueName::from("music".to_string()),
+                vec![create_dummy_track(100, "music")],
+            );
+            Ok(Some(s))
+        });
+    mock_state
+        .expect_save_session()
+        .withf(move |s| s.find_track(track_id).map(|t| t.dsp) == Some(expected))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(
+        control
+            .set_dsp(
+                track_id,
+                TrackDsp {
+                    speed: 5.0,
+                    pitch: -30.0,
+                    tempo: 1.25,
+                },
+            )
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_set_pan_clamps_and_saves() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+    let track_id = TrackId::from(100);
+
+    mock_mixer
+        .expect_set_pan()
+        .with(eq(track_id), eq(-1.0))
+        .times(1)
+        .return_const(());
+
+    mock_state
+        .expect_get_session()
+        .times(1)
+        .returning(move |_, _| {
+            let mut s = SessionState {
+                guild_id,
+                channel_id: ChannelId::from(300),
+                queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
+            };
+            s.queues.insert(
+                QueueName::from("music".to_string()),
+                vec![create_dummy_track(100, "music")],
+            );
+            Ok(Some(s))
+        });
+    mock_state
+        .expect_save_session()
+        .withf(move |s| s.find_track(track_id).map(|t| t.pan) == Some(Some(Pan::from(-1.0))))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.set_pan(track_id, Pan::from(-3.0)).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_seek_clamps_to_duration_and_restarts() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+    let track_id = TrackId::from(100);
+
+    mock_mixer
+        .expect_remove_source()
+        .with(eq(track_id))
+        .times(1)
+        .return_const(());
+    mock_decoder
+        .expect_stop_track()
+        .with(eq(track_id))
+        .times(1)
+        .return_const(());
+    // The track is paused, so reconcile does not restart it yet.
+    mock_mixer
+        .expect_has_sources()
+        .returning(|_| std::collections::HashSet::new());
+
+    mock_state.expect_get_session().returning(move |_, _| {
+        let mut s = SessionState {
+            guild_id,
+            channel_id: ChannelId::from(300),
+            queues: HashMap::new(),
+            settings: Default::default(),
+            queue_mode: Default::default(),
+        };
+        let mut track = create_dummy_track(100, "music");
+        track.paused = true;
+        track.duration_ms = Some(60_000);
+        s.queues
+            .insert(QueueName::from("music".to_string()), vec![track]);
+        Ok(Some(s))
+    });
+    mock_state
+        .expect_save_session()
+        .withf(move |s| s.find_track(track_id).map(|t| t.position_ms) == Some(60_000))
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.seek(track_id, 90_000).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_seek_unknown_track_fails() {
+    let mock_mixer = MockMixer::new();
+    let mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+
+    mock_state.expect_get_session().returning(|_, _| {
+        Ok(Some(SessionState {
+            guild_id: GuildId::from(3),
+            channel_id: ChannelId::from(300),
+            queues: HashMap::new(),
+            settings: Default::default(),
+            queue_mode: Default::default(),
+        }))
+    });
+
+    let control = create_session_control(
+        GuildId::from(3),
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.seek(TrackId::from(1), 1_000).await.is_err());
+}
+
+#[tokio::test]
+async fn test_seek_uncached_track_fails() {
+    // The mixer and decoder are left alone.
+    let mock_mixer = MockMixer::new();
+    let mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+
+    mock_state.expect_get_session().returning(|_, _| {
+        let mut track = create_dummy_track(1, "music");
+        track.request.cache_key.cache_type = AudioCacheType::None;
+        Ok(Some(SessionState {
+            guild_id: GuildId::from(3),
+            channel_id: ChannelId::from(300),
+            queues: HashMap::from([(QueueName::from("music".to_string()), vec![track])]),
+            settings: Default::default(),
+            queue_mode: Default::default(),
+        }))
+    });
+
+    let control = create_session_control(
+        GuildId::from(3),
+        ChannelId::from(300),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(matches!(
+        control.seek(TrackId::from(1), 1_000).await,
+        Err(ZakoError::SeekUnsupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_session_state_reports_live_position() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+
+    mock_mixer
+        .expect_positions()
+        .times(1)
+        .returning(|_| HashMap::from([(TrackId::from(100), 1_500)]));
+    mock_state.expect_get_session().returning(move |_, _| {
+        let mut s = SessionState {
+            guild_id,
+            channel_id: ChannelId::from(300),
+            queues: HashMap::new(),
+            settings: Default::default(),
+            queue_mode: Default::default(),
+        };
+        let mut playing = create_dummy_track(100, "music");
+        playing.position_ms = 30_000;
+        s.queues.insert(
+            QueueName::from("music".to_string()),
+            vec![playing, create_dummy_track(101, "music")],
+        );
+        Ok(Some(s))
+    });
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    let state = control.session_state().await.unwrap().unwrap();
+    assert_eq!(
+        state.find_track(TrackId::from(100)).unwrap().position_ms,
+        31_500
+    );
+    assert_eq!(state.find_track(TrackId::from(101)).unwrap().position_ms, 0);
+}
+
+#[tokio::test]
+async fn test_set_settings_applies_ducking() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+
+    let settings = SessionAudioSettings {
+        ducking: DuckingSettings {
+            enabled: true,
+            depth_db: 18.0,
+            attack_ms: 50,
+            release_ms: 300,
+        },
+        loudness: LoudnessSettings {
+            enabled: true,
+            target_lufs: -18.0,
+        },
+        encoder: EncoderSettings {
+            bitrate_kbps: 32,
+            complexity: 5,
+            fec: true,
+            expected_packet_loss_pct: 10,
+            signal: OpusSignal::Voice,
+        },
+        ..Default::default()
+    };
+
+    mock_mixer
+        .expect_set_ducking()
+        .with(eq(settings.ducking))
+        .times(1)
+        .return_const(());
+    mock_mixer
+        .expect_set_encoder()
+        .with(eq(settings.encoder))
+        .times(1)
+        .return_const(());
+    mock_decoder
+        .expect_set_loudness()
+        .with(eq(settings.loudness))
+        .times(1)
+        .return_const(());
+
+    mock_state
+        .expect_get_session()
+        .times(1)
+        .returning(move |_, _| {
+            Ok(Some(SessionState {
+                guild_id,
+                channel_id: ChannelId::from(100),
+                queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
+            }))
+        });
+    let expected = settings.clone();
+    mock_state
+        .expect_save_session()
+        .withf(move |s| s.settings == expected)
+        .times(1)
+        .returning(|_| Ok(()));
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.set_settings(settings).await.is_ok());
+}
+
 #[tokio::test]
 async fn test_next_music_success() {
     let guild_id = GuildId::from(4);
@@ -456,6 +858,8 @@ async fn test_next_music_success() {
                 guild_id,
                 channel_id: ChannelId::from(400),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -469,8 +873,8 @@ async fn test_next_music_success() {
 
     // 2. Remove first
     mock_mixer
-        .expect_remove_source()
-        .with(eq(TrackId::from(1)))
+        .expect_fade_out_and_remove()
+        .with(eq(TrackId::from(1)), eq(Duration::from_millis(80)))
         .times(1)
         .return_const(());
 
@@ -483,6 +887,8 @@ async fn test_next_music_success() {
                 guild_id,
                 channel_id: ChannelId::from(400),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -507,6 +913,8 @@ async fn test_next_music_success() {
                 guild_id,
                 channel_id: ChannelId::from(400),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -528,14 +936,17 @@ async fn test_next_music_success() {
                 ttl_seconds: None,
             }),
             stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     });
-    mock_decoder.expect_start_decoding().returning(|_, _| {
+    mock_decoder.expect_start_decoding().returning(|_, _, _| {
         let (_, c) = create_ringbuf_pair();
-        Ok(c)
+        Ok(Preroll::ready(c))
     });
     mock_mixer.expect_add_source().return_const(());
     mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
 
     let control = create_session_control(
         guild_id,
@@ -565,6 +976,8 @@ async fn test_next_music_last_track() {
                 guild_id,
                 channel_id: ChannelId::from(500),
                 queues: HashMap::new(),
+                settings: Default::default(),
+                queue_mode: Default::default(),
             };
             s.queues.insert(
                 QueueName::from("music".to_string()),
@@ -599,6 +1012,8 @@ async fn test_end_of_track_handling_and_preload() {
         guild_id,
         channel_id: ChannelId::from(600),
         queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
     }));
 
     // Initial state: Track 1 playing, Track 2 queued
@@ -639,6 +1054,8 @@ async fn test_end_of_track_handling_and_preload() {
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     });
 
@@ -668,13 +1085,15 @@ async fn test_end_of_track_handling_and_preload() {
                 ttl_seconds: None,
             }),
             stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     });
 
     // Decoder
-    mock_decoder.expect_start_decoding().returning(|_, _| {
+    mock_decoder.expect_start_decoding().returning(|_, _, _| {
         let (_, c) = create_ringbuf_pair();
-        Ok(c)
+        Ok(Preroll::ready(c))
     });
 
     // Mixer Add Source - Capture on first call (Track 1)
@@ -694,13 +1113,14 @@ async fn test_end_of_track_handling_and_preload() {
         .return_const(());
 
     mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
 
     // --- Expectations for Event ---
 
     // Remove Track 1
     mock_mixer
-        .expect_remove_source()
-        .with(eq(TrackId::from(1)))
+        .expect_fade_out_and_remove()
+        .with(eq(TrackId::from(1)), eq(Duration::from_millis(80)))
         .times(1)
         .return_const(());
 
@@ -723,6 +1143,8 @@ async fn test_end_of_track_handling_and_preload() {
                     ttl_seconds: None,
                 },
                 base_volume: 1.0,
+                loudness_lufs: None,
+                duration_ms: None,
             })
         });
 
@@ -744,6 +1166,7 @@ async fn test_end_of_track_handling_and_preload() {
             AudioRequestString::from("t".to_string()),
             Volume::from(1.0),
             DiscordUserId::from("123".to_string()),
+            TrackDsp::default(),
         )
         .await;
 
@@ -758,3 +1181,1223 @@ async fn test_end_of_track_handling_and_preload() {
     // 3. Wait for background task
     tokio::time::sleep(Duration::from_millis(100)).await;
 }
+
+#[tokio::test]
+async fn test_crossfade_queues_next_music_track() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+    let current = TrackId::from(100);
+    let next = TrackId::from(101);
+
+    mock_state.expect_get_session().returning(move |_, _| {
+        let mut s = SessionState {
+            guild_id,
+            channel_id: ChannelId::from(300),
+            queues: HashMap::new(),
+            settings: SessionAudioSettings {
+                transitions: TransitionSettings {
+                    gapless: false,
+                    crossfade_ms: 5_000,
+                },
+                ..Default::default()
+            },
+            queue_mode: Default::default(),
+        };
+        s.queues.insert(
+            QueueName::from("music".to_string()),
+            vec![
+                create_dummy_track(100, "music"),
+                create_dummy_track(101, "music"),
+            ],
+        );
+        Ok(Some(s))
+    });
+    mock_state.expect_save_session().returning(|_| Ok(()));
+    mock_decoder.expect_resume_track().return_const(());
+    mock_mixer.expect_set_paused().return_const(());
+
+    // The current track is already in the mixer; the next one is not.
+    mock_mixer
+        .expect_has_sources()
+        .returning(move |ids| ids.into_iter().filter(|id| *id == current).collect());
+
+    mock_taphub.expect_request_audio().times(1).returning(|_| {
+        Ok(AudioResponse {
+            metadatas: vec![],
+            cache_key: None,
+            stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: Some(-16.0),
+            duration_ms: Some(180_000),
+        })
+    });
+    mock_decoder
+        .expect_start_decoding()
+        .withf(move |id, _, _| *id == next)
+        .times(1)
+        .returning(|_, _, _| {
+            let (_, c) = create_ringbuf_pair();
+            Ok(Preroll::ready(c))
+        });
+    mock_mixer
+        .expect_queue_source()
+        .withf(move |id, _, _, follow| {
+            *id == next
+                && *follow
+                    == Follow {
+                        after: current,
+                        crossfade_ms: 5_000,
+                    }
+        })
+        .times(1)
+        .return_const(());
+    mock_mixer
+        .expect_set_remaining()
+        .with(eq(next), eq(180_000))
+        .times(1)
+        .return_const(());
+    mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.resume(current).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_seek_drops_queued_next_track() {
+    let guild_id = GuildId::from(3);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+    let current = TrackId::from(100);
+    let next = TrackId::from(101);
+
+    mock_state.expect_get_session().returning(move |_, _| {
+        let mut s = SessionState {
+            guild_id,
+            channel_id: ChannelId::from(300),
+            queues: HashMap::new(),
+            settings: SessionAudioSettings {
+                transitions: TransitionSettings {
+                    gapless: true,
+                    crossfade_ms: 0,
+                },
+                ..Default::default()
+            },
+            queue_mode: Default::default(),
+        };
+        let mut track = create_dummy_track(100, "music");
+        track.paused = true;
+        s.queues.insert(
+            QueueName::from("music".to_string()),
+            vec![track, create_dummy_track(101, "music")],
+        );
+        Ok(Some(s))
+    });
+    mock_state.expect_save_session().returning(|_| Ok(()));
+
+    for track_id in [current, next] {
+        mock_mixer
+            .expect_remove_source()
+            .with(eq(track_id))
+            .times(1)
+            .return_const(());
+        mock_decoder
+            .expect_stop_track()
+            .with(eq(track_id))
+            .times(1)
+            .return_const(());
+    }
+    // The current track is paused, so nothing is queued behind it again.
+    mock_mixer
+        .expect_has_sources()
+        .returning(|_| std::collections::HashSet::new());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.seek(current, 30_000).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_repeat_queue_requeues_ended_track() {
+    let guild_id = GuildId::from(7);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id: ChannelId::from(700),
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: QueueMode {
+            repeat: RepeatMode::Queue,
+            shuffle: false,
+        },
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        vec![
+            create_dummy_track(1, "music"),
+            create_dummy_track(2, "music"),
+        ],
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    mock_mixer
+        .expect_fade_out_and_remove()
+        .with(eq(TrackId::from(1)), eq(Duration::ZERO))
+        .times(1)
+        .return_const(());
+    mock_decoder
+        .expect_stop_track()
+        .with(eq(TrackId::from(1)))
+        .times(1)
+        .return_const(());
+    // Track 2 is already playing after the hand-over.
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+    mock_taphub.expect_preload_audio().returning(|_| {
+        Ok(AudioMetaResponse {
+            metadatas: vec![],
+            cache_key: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
+        })
+    });
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(700),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+
+    control.end_tx.send(TrackId::from(1)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let s = state_store.lock().unwrap();
+    let queue = s.queues.get(&QueueName::from("music".to_string())).unwrap();
+    let ids: Vec<TrackId> = queue.iter().map(|t| t.track_id).collect();
+    assert_eq!(ids, vec![TrackId::from(2), TrackId::from(1)]);
+    assert_eq!(queue[1].position_ms, 0);
+}
+
+#[tokio::test]
+async fn test_set_queue_mode_shuffle_keeps_current_track() {
+    let guild_id = GuildId::from(8);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id: ChannelId::from(800),
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        (1..=6).map(|id| create_dummy_track(id, "music")).collect(),
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    // The previously lined-up track may no longer be next.
+    mock_mixer.expect_remove_source().return_const(());
+    mock_decoder.expect_stop_track().return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(800),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(MockTapHubService::new()),
+    );
+
+    let mode = QueueMode {
+        repeat: RepeatMode::Off,
+        shuffle: true,
+    };
+    assert!(control.set_queue_mode(mode).await.is_ok());
+
+    let s = state_store.lock().unwrap();
+    assert_eq!(s.queue_mode, mode);
+    let queue = s.queues.get(&QueueName::from("music".to_string())).unwrap();
+    assert_eq!(queue[0].track_id, TrackId::from(1));
+    let mut ids: Vec<u64> = queue.iter().map(|t| u64::from(t.track_id)).collect();
+    ids.sort();
+    assert_eq!(ids, (1..=6).collect::<Vec<_>>());
+}
+
+fn music_ids(state: &Arc<Mutex<SessionState>>) -> Vec<u64> {
+    state
+        .lock()
+        .unwrap()
+        .queues
+        .get(&QueueName::from("music".to_string()))
+        .unwrap()
+        .iter()
+        .map(|t| u64::from(t.track_id))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_move_track_reorders_queue() {
+    let guild_id = GuildId::from(9);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id: ChannelId::from(900),
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        (1..=4).map(|id| create_dummy_track(id, "music")).collect(),
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    // Track 2 is no longer next once track 4 moves in front of it.
+    mock_mixer
+        .expect_remove_source()
+        .with(eq(TrackId::from(2)))
+        .times(1)
+        .return_const(());
+    mock_decoder
+        .expect_stop_track()
+        .with(eq(TrackId::from(2)))
+        .times(1)
+        .return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(900),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(MockTapHubService::new()),
+    );
+
+    assert!(control.move_track(TrackId::from(4), 1).await.is_ok());
+    assert_eq!(music_ids(&state_store), vec![1, 4, 2, 3]);
+
+    // The playing track stays put.
+    assert!(control.move_track(TrackId::from(1), 3).await.is_err());
+    assert!(control.move_track(TrackId::from(99), 1).await.is_err());
+    assert_eq!(music_ids(&state_store), vec![1, 4, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_insert_at_never_displaces_playing_track() {
+    let guild_id = GuildId::from(10);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id: ChannelId::from(1000),
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        (1..=3).map(|id| create_dummy_track(id, "music")).collect(),
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    mock_taphub.expect_request_audio_meta().returning(|_| {
+        Ok(AudioMetaResponse {
+            metadatas: vec![],
+            cache_key: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
+        })
+    });
+    mock_taphub.expect_preload_audio().returning(|_| {
+        Ok(AudioMetaResponse {
+            metadatas: vec![],
+            cache_key: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
+        })
+    });
+    mock_mixer
+        .expect_remove_source()
+        .with(eq(TrackId::from(2)))
+        .times(1)
+        .return_const(());
+    mock_decoder
+        .expect_stop_track()
+        .with(eq(TrackId::from(2)))
+        .times(1)
+        .return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(1000),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+
+    let track_id = control
+        .insert_at(
+            0,
+            QueueName::from("music".to_string()),
+            TapId("test_tap_id".to_string()),
+            AudioRequestString::from("next".to_string()),
+            Volume::from(1.0),
+            DiscordUserId::from("123".to_string()),
+            TrackDsp::default(),
+        )
+        .await
+        .unwrap();
+
+    let ids = music_ids(&state_store);
+    assert_eq!(ids, vec![1, u64::from(track_id), 2, 3]);
+}
+
+#[tokio::test]
+async fn test_export_recording_follows_setting() {
+    let guild_id = GuildId::from(11);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mock_state = MockStateService::new();
+    let mock_taphub = MockTapHubService::new();
+
+    let tapped: Arc<Mutex<Option<Arc<Recorder>>>> = Arc::new(Mutex::new(None));
+    let tapped_clone = tapped.clone();
+    mock_mixer.expect_set_ducking().return_const(());
+    mock_mixer.expect_set_encoder().return_const(());
+    mock_decoder.expect_set_loudness().return_const(());
+    mock_mixer
+        .expect_set_recorder()
+        .withf(|r| r.is_some())
+        .times(1)
+        .returning(move |r| *tapped_clone.lock().unwrap() = r);
+    mock_mixer
+        .expect_set_recorder()
+        .withf(|r| r.is_none())
+        .times(1)
+        .return_const(());
+
+    let control = create_session_control(
+        guild_id,
+        ChannelId::from(1100),
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.export_recording(None).is_err());
+
+    let mut settings = SessionAudioSettings {
+        recording: RecordingSettings {
+            enabled: true,
+            retention_secs: 60,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    control.apply_settings(&settings);
+    // Changing the retention keeps the existing tap.
+    settings.recording.retention_secs = 30;
+    control.apply_settings(&settings);
+
+    let recorder = tapped
+        .lock()
+        .unwrap()
+        .clone()
+        .expect("mixer was not tapped");
+    for _ in 0..100 {
+        recorder.push(vec![0xfc, 0xff, 0xfe].into());
+    }
+    let ogg = control.export_recording(Some(1)).unwrap();
+    assert!(ogg.starts_with(b"OggS"));
+    assert!(ogg.windows(8).any(|w| w == b"OpusHead"));
+    assert!(ogg.len() < control.export_recording(None).unwrap().len());
+
+    settings.recording.enabled = false;
+    control.apply_settings(&settings);
+    assert!(control.export_recording(None).is_err());
+}
+
+#[tokio::test]
+async fn test_full_recording_writes_the_session_to_a_file() {
+    let guild_id = GuildId::from(13);
+    let channel_id = ChannelId::from(1300);
+    let dir = std::env::temp_dir().join(format!("zako3-full-recording-{}", std::process::id()));
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+
+    let tapped: Arc<Mutex<Option<Arc<Recorder>>>> = Arc::new(Mutex::new(None));
+    let tapped_clone = tapped.clone();
+    mock_mixer.expect_set_ducking().return_const(());
+    mock_mixer.expect_set_encoder().return_const(());
+    mock_decoder.expect_set_loudness().return_const(());
+    mock_mixer
+        .expect_set_recorder()
+        .times(1)
+        .returning(move |r| *tapped_clone.lock().unwrap() = r);
+
+    let control = create_session_control_with_events(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(MockStateService::new()),
+        Arc::new(MockTapHubService::new()),
+        broadcast::channel(16).0,
+        dir.clone(),
+    );
+
+    let mut settings = SessionAudioSettings {
+        recording: RecordingSettings {
+            enabled: true,
+            mode: RecordingMode::Full,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    control.apply_settings(&settings);
+    let recorder = tapped
+        .lock()
+        .unwrap()
+        .clone()
+        .expect("mixer was not tapped");
+    assert!(recorder.is_recording_full());
+    for i in 0..10u8 {
+        recorder.push(vec![0xfc, i].into());
+    }
+
+    // Back to clips: the file is completed and the clip window stays.
+    settings.recording.mode = RecordingMode::Clip;
+    control.apply_settings(&settings);
+    assert!(!recorder.is_recording_full());
+    assert!(control.export_recording(None).is_ok());
+
+    let path = std::fs::read_dir(&dir)
+        .unwrap()
+        .next()
+        .expect("no recording file")
+        .unwrap()
+        .path();
+    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(name.starts_with("13-1300-") && name.ends_with(".ogg"));
+    // The last frame is only written once the file is completed.
+    let mut ogg = Vec::new();
+    for _ in 0..200 {
+        ogg = std::fs::read(&path).unwrap();
+        if ogg.windows(2).any(|w| w == [0xfc, 9]) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(ogg.starts_with(b"OggS"));
+    assert!(ogg.windows(2).any(|w| w == [0xfc, 9]));
+}
+
+#[tokio::test]
+async fn test_lifecycle_events_are_published() {
+    let guild_id = GuildId::from(12);
+    let channel_id = ChannelId::from(1200);
+    let track_id = TrackId::from(1);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        vec![create_dummy_track(1, "music")],
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+    mock_decoder.expect_pause_track().return_const(());
+    mock_decoder.expect_stop_track().return_const(());
+    mock_mixer.expect_set_paused().return_const(());
+    mock_mixer.expect_fade_out_and_remove().return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+
+    let (events, mut rx) = broadcast::channel(16);
+    let control = create_session_control_with_events(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(MockTapHubService::new()),
+        events,
+        crate::DEFAULT_RECORDING_DIR.into(),
+    );
+
+    assert!(control.pause(track_id).await.is_ok());
+    assert!(control.stop(track_id).await.is_ok());
+
+    let paused = rx.try_recv().unwrap();
+    assert_eq!(paused.guild_id, guild_id);
+    assert_eq!(paused.channel_id, channel_id);
+    assert_eq!(paused.track_id, track_id);
+    assert!(matches!(paused.kind, TrackEventKind::Paused));
+    assert!(matches!(
+        rx.try_recv().unwrap().kind,
+        TrackEventKind::Stopped
+    ));
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_natural_end_publishes_only_ended() {
+    let guild_id = GuildId::from(13);
+    let channel_id = ChannelId::from(1300);
+    let track_id = TrackId::from(1);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        vec![create_dummy_track(1, "music")],
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+    mock_decoder.expect_stop_track().return_const(());
+    mock_mixer.expect_fade_out_and_remove().return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+
+    let (events, mut rx) = broadcast::channel(16);
+    let control = create_session_control_with_events(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(MockTapHubService::new()),
+        events,
+        crate::DEFAULT_RECORDING_DIR.into(),
+    );
+
+    // The mixer reports the track ran out.
+    control.end_tx.send(track_id).await.unwrap();
+    for _ in 0..50 {
+        if state_store.lock().unwrap().find_track(track_id).is_none() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(state_store.lock().unwrap().find_track(track_id).is_none());
+    // Let the end handling finish.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let ended = rx.try_recv().unwrap();
+    assert_eq!(ended.track_id, track_id);
+    assert!(matches!(ended.kind, TrackEventKind::Ended));
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_lined_up_track_is_announced_when_it_takes_over() {
+    let guild_id = GuildId::from(14);
+    let channel_id = ChannelId::from(1400);
+    let current = TrackId::from(1);
+    let next = TrackId::from(2);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: SessionAudioSettings {
+            transitions: TransitionSettings {
+                gapless: true,
+                crossfade_ms: 0,
+            },
+            ..Default::default()
+        },
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        vec![
+            create_dummy_track(1, "music"),
+            create_dummy_track(2, "music"),
+        ],
+    );
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    // Sources the mock mixer holds; the current track is already playing.
+    let in_mixer = Arc::new(Mutex::new(vec![current]));
+    let m = in_mixer.clone();
+    mock_mixer.expect_has_sources().returning(move |ids| {
+        let held = m.lock().unwrap();
+        ids.into_iter().filter(|id| held.contains(id)).collect()
+    });
+    let m = in_mixer.clone();
+    mock_mixer
+        .expect_queue_source()
+        .withf(move |id, _, _, _| *id == next)
+        .times(1)
+        .returning(move |id, _, _, _| m.lock().unwrap().push(id));
+    let m = in_mixer.clone();
+    mock_mixer
+        .expect_fade_out_and_remove()
+        .returning(move |id, _| m.lock().unwrap().retain(|t| *t != id));
+    mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
+    mock_decoder.expect_stop_track().return_const(());
+    mock_decoder
+        .expect_start_decoding()
+        .times(1)
+        .returning(|_, _, _| {
+            let (_, c) = create_ringbuf_pair();
+            Ok(Preroll::ready(c))
+        });
+    mock_taphub.expect_request_audio().times(1).returning(|_| {
+        Ok(AudioResponse {
+            metadatas: vec![],
+            cache_key: None,
+            stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: Some(-16.0),
+            duration_ms: None,
+        })
+    });
+
+    let (events, mut rx) = broadcast::channel(16);
+    let control = create_session_control_with_events(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+        events,
+        crate::DEFAULT_RECORDING_DIR.into(),
+    );
+
+    // Lining the next track up in the mixer does not start it yet.
+    assert!(control.reconcile().await.is_ok());
+    assert!(rx.try_recv().is_err());
+
+    // Once the current track is gone, the lined-up one is announced once.
+    assert!(control.stop(current).await.is_ok());
+    let stopped = rx.try_recv().unwrap();
+    assert_eq!(stopped.track_id, current);
+    assert!(matches!(stopped.kind, TrackEventKind::Stopped));
+    let started = rx.try_recv().unwrap();
+    assert_eq!(started.track_id, next);
+    assert!(matches!(started.kind, TrackEventKind::Started));
+
+    assert!(control.reconcile().await.is_ok());
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_slow_preroll_does_not_hold_up_reconcile() {
+    let guild_id = GuildId::from(17);
+    let channel_id = ChannelId::from(1700);
+    let track_id = TrackId::from(1);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    state_store.lock().unwrap().queues.insert(
+        QueueName::from("music".to_string()),
+        vec![create_dummy_track(1, "music")],
+    );
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+
+    let in_mixer = Arc::new(Mutex::new(vec![]));
+    let m = in_mixer.clone();
+    mock_mixer.expect_has_sources().returning(move |ids| {
+        let held = m.lock().unwrap();
+        ids.into_iter().filter(|id| held.contains(id)).collect()
+    });
+    let m = in_mixer.clone();
+    mock_mixer
+        .expect_add_source()
+        .times(1)
+        .returning(move |id, _, _| m.lock().unwrap().push(id));
+    mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
+
+    // The tap has not filled the pre-roll until the test says so.
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+    let ready_rx = Mutex::new(Some(ready_rx));
+    mock_decoder
+        .expect_start_decoding()
+        .times(1)
+        .returning(move |_, _, _| {
+            let (_, c) = create_ringbuf_pair();
+            Ok(Preroll::new(c, ready_rx.lock().unwrap().take().unwrap()))
+        });
+    mock_taphub.expect_request_audio().times(1).returning(|_| {
+        Ok(AudioResponse {
+            metadatas: vec![],
+            cache_key: None,
+            stream: tokio::sync::mpsc::channel(1).1,
+            loudness_lufs: Some(-16.0),
+            duration_ms: None,
+        })
+    });
+
+    let control = create_session_control(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+
+    // Neither reconcile waits for the audio, nor starts the track twice.
+    tokio::time::timeout(Duration::from_millis(500), control.reconcile())
+        .await
+        .expect("reconcile does not wait for the pre-roll")
+        .unwrap();
+    assert!(control.reconcile().await.is_ok());
+    assert!(in_mixer.lock().unwrap().is_empty());
+
+    ready_tx.send(()).unwrap();
+    for _ in 0..50 {
+        if !in_mixer.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(*in_mixer.lock().unwrap(), vec![track_id]);
+}
+
+#[tokio::test]
+async fn test_announcement_holds_lower_classes_until_it_ends() {
+    let guild_id = GuildId::from(13);
+    let channel_id = ChannelId::from(1300);
+    let announcement = QueueName::announcement();
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    {
+        let mut s = state_store.lock().unwrap();
+        s.queues.insert(
+            QueueName::from("music".to_string()),
+            vec![
+                create_dummy_track(1, "music"),
+                create_dummy_track(2, "music"),
+            ],
+        );
+        s.queues.insert(
+            QueueName::from("tts_123".to_string()),
+            vec![create_dummy_track(3, "tts_123")],
+        );
+        s.queues.insert(
+            announcement.clone(),
+            vec![create_dummy_track(4, &announcement.to_string())],
+        );
+    }
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+    mock_mixer.expect_fade_out_and_remove().return_const(());
+    mock_decoder.expect_stop_track().return_const(());
+    mock_mixer
+        .expect_has_sources()
+        .returning(|ids| ids.into_iter().collect());
+    // Held once while the announcement plays, then resumed exactly once.
+    for id in [1, 3] {
+        mock_decoder
+            .expect_pause_track()
+            .with(eq(TrackId::from(id)))
+            .times(1)
+            .return_const(());
+        mock_decoder
+            .expect_resume_track()
+            .with(eq(TrackId::from(id)))
+            .times(1)
+            .return_const(());
+        for paused in [true, false] {
+            mock_mixer
+                .expect_set_paused()
+                .with(eq(TrackId::from(id)), eq(paused))
+                .times(1)
+                .return_const(());
+        }
+    }
+
+    let (events, mut rx) = broadcast::channel(16);
+    let control = create_session_control_with_events(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(MockTapHubService::new()),
+        events,
+        crate::DEFAULT_RECORDING_DIR.into(),
+    );
+
+    // Dropping the queued music track runs reconcile with the announcement up.
+    assert!(control.stop(TrackId::from(2)).await.is_ok());
+    let mut held: Vec<u64> = std::iter::from_fn(|| rx.try_recv().ok())
+        .filter(|e| matches!(e.kind, TrackEventKind::Paused))
+        .map(|e| u64::from(e.track_id))
+        .collect();
+    held.sort();
+    assert_eq!(held, vec![1, 3]);
+
+    control.end_tx.send(TrackId::from(4)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut resumed: Vec<u64> = std::iter::from_fn(|| rx.try_recv().ok())
+        .filter(|e| matches!(e.kind, TrackEventKind::Resumed))
+        .map(|e| u64::from(e.track_id))
+        .collect();
+    resumed.sort();
+    assert_eq!(resumed, vec![1, 3]);
+    let s = state_store.lock().unwrap();
+    assert!(!s.queues.values().flatten().any(|t| t.paused));
+}
+
+#[tokio::test]
+async fn test_checkpoint_persists_live_position_and_resumes_from_it() {
+    let guild_id = GuildId::from(14);
+    let channel_id = ChannelId::from(1400);
+    let track_id = TrackId::from(1);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let state_store = Arc::new(Mutex::new(SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }));
+    let mut track = create_dummy_track(1, "music");
+    track.position_ms = 10_000;
+    state_store
+        .lock()
+        .unwrap()
+        .queues
+        .insert(QueueName::from("music".to_string()), vec![track]);
+
+    let s_clone = state_store.clone();
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(s_clone.lock().unwrap().clone())));
+    let s_clone2 = state_store.clone();
+    mock_state.expect_save_session().returning(move |s| {
+        *s_clone2.lock().unwrap() = s.clone();
+        Ok(())
+    });
+
+    // A rejoined track is requested from its persisted position.
+    mock_taphub
+        .expect_request_audio()
+        .withf(|r| r.start_offset_ms == 10_000)
+        .times(1)
+        .returning(|_| {
+            Ok(AudioResponse {
+                metadatas: vec![],
+                cache_key: None,
+                stream: tokio::sync::mpsc::channel(1).1,
+                loudness_lufs: Some(-20.0),
+                duration_ms: None,
+            })
+        });
+    mock_decoder.expect_start_decoding().returning(|_, _, _| {
+        let (_, c) = create_ringbuf_pair();
+        Ok(Preroll::ready(c))
+    });
+    mock_mixer
+        .expect_has_sources()
+        .returning(|_| std::collections::HashSet::new());
+    mock_mixer.expect_add_source().times(1).return_const(());
+    mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
+    let mut elapsed = vec![4_000, 2_000];
+    mock_mixer
+        .expect_positions()
+        .times(2)
+        .returning(move |_| HashMap::from([(TrackId::from(1), elapsed.pop().unwrap())]));
+
+    let control = create_session_control(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.reconcile().await.is_ok());
+
+    let position = |store: &Arc<Mutex<SessionState>>| {
+        store
+            .lock()
+            .unwrap()
+            .find_track(track_id)
+            .unwrap()
+            .position_ms
+    };
+    assert!(control.checkpoint().await.is_ok());
+    assert_eq!(position(&state_store), 12_000);
+    // Elapsed time is measured from where the track started, not from the
+    // last checkpoint.
+    assert!(control.checkpoint().await.is_ok());
+    assert_eq!(position(&state_store), 14_000);
+}
+
+#[tokio::test]
+async fn test_tts_auto_pan_gives_each_queue_its_own_position() {
+    let guild_id = GuildId::from(15);
+    let channel_id = ChannelId::from(1500);
+    let mut mock_mixer = MockMixer::new();
+    let mut mock_decoder = MockDecoder::new();
+    let mut mock_state = MockStateService::new();
+    let mut mock_taphub = MockTapHubService::new();
+
+    let tts_pan = TtsPanSettings {
+        enabled: true,
+        width: 0.6,
+    };
+    let voices = TtsPanSettings::POSITIONS;
+    let mut session = SessionState {
+        guild_id,
+        channel_id,
+        queues: HashMap::new(),
+        settings: SessionAudioSettings {
+            tts_pan,
+            ..Default::default()
+        },
+        queue_mode: Default::default(),
+    };
+    for user in 1..=voices {
+        let queue = format!("tts_{user}");
+        session.queues.insert(
+            QueueName::from(queue.clone()),
+            vec![create_dummy_track(user, &queue)],
+        );
+    }
+    // Explicitly centred, so it stays out of auto-pan.
+    let mut explicit = create_dummy_track(100, "tts_explicit");
+    explicit.pan = Some(Pan::CENTER);
+    session
+        .queues
+        .insert(QueueName::from("tts_explicit".to_string()), vec![explicit]);
+
+    mock_state
+        .expect_get_session()
+        .returning(move |_, _| Ok(Some(session.clone())));
+    mock_taphub
+        .expect_request_audio()
+        .times(voices as usize + 1)
+        .returning(|_| {
+            Ok(AudioResponse {
+                metadatas: vec![],
+                cache_key: None,
+                stream: tokio::sync::mpsc::channel(1).1,
+                loudness_lufs: Some(-20.0),
+                duration_ms: None,
+            })
+        });
+    mock_decoder.expect_start_decoding().returning(|_, _, _| {
+        let (_, c) = create_ringbuf_pair();
+        Ok(Preroll::ready(c))
+    });
+    mock_mixer
+        .expect_has_sources()
+        .returning(|_| std::collections::HashSet::new());
+    mock_mixer
+        .expect_add_source()
+        .times(voices as usize + 1)
+        .return_const(());
+    mock_mixer.expect_set_volume().return_const(());
+    mock_mixer.expect_set_source_class().return_const(());
+    let pans = Arc::new(Mutex::new(HashMap::new()));
+    let pans_clone = pans.clone();
+    // The centre slot is never passed on, so one voice fewer is panned.
+    mock_mixer
+        .expect_set_pan()
+        .withf(move |track_id, _| u64::from(*track_id) <= voices)
+        .times(voices as usize - 1)
+        .returning(move |track_id, pan| {
+            pans_clone.lock().unwrap().insert(track_id, pan);
+        });
+
+    let control = create_session_control(
+        guild_id,
+        channel_id,
+        Arc::new(mock_mixer),
+        Arc::new(mock_decoder),
+        Arc::new(mock_state),
+        Arc::new(mock_taphub),
+    );
+    assert!(control.reconcile().await.is_ok());
+
+    let mut placed: Vec<f32> = pans.lock().unwrap().values().copied().collect();
+    placed.sort_by(f32::total_cmp);
+    let expected: Vec<f32> = (0..voices)
+        .map(|slot| f32::from(tts_pan.position(slot)))
+        .filter(|&pan| pan != 0.0)
+        .collect();
+    assert_eq!(placed, expected);
+}
@@ -88,6 +88,8 @@ async fn test_session_manager_get_sessions_in_guild() {
         guild_id,
         channel_id: ChannelId::from(300),
         queues: Default::default(),
+        settings: Default::default(),
+        queue_mode: Default::default(),
     };
 
     let mock_discord = MockDiscordService::new();
@@ -15,4 +15,9 @@ pub trait TapHubService: Send + Sync + 'static {
     async fn request_audio(&self, request: CachedAudioRequest) -> ZakoResult<AudioResponse>;
     async fn preload_audio(&self, request: CachedAudioRequest) -> ZakoResult<AudioMetaResponse>;
     async fn request_audio_meta(&self, request: AudioRequest) -> ZakoResult<AudioMetaResponse>;
+    async fn store_loudness(
+        &self,
+        request: CachedAudioRequest,
+        loudness_lufs: f32,
+    ) -> ZakoResult<()>;
 }
@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use tracing::instrument;
+use zako3_audio_engine_audio::{
+    OpusCons,
+    ogg_opus::OggOpusWriter,
+    ringbuf::traits::{Consumer, Observer},
+};
+use zako3_audio_engine_core::{
+    error::ZakoResult,
+    service::discord::DiscordService,
+    types::{ChannelId, GuildId},
+};
+
+/// How often a sink checks for new frames. The mixer produces one every 20 ms.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Stands in for Discord voice in headless runs. Joins are bookkeeping only,
+/// and each played stream is written to `<dir>/<guild>-<channel>-<n>.ogg`,
+/// where `n` counts streams started by this service.
+pub struct FileSinkDiscordService {
+    dir: PathBuf,
+    connections: DashMap<GuildId, ChannelId>,
+    sinks: DashMap<GuildId, Sink>,
+    next_file: AtomicU64,
+}
+
+struct Sink {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl FileSinkDiscordService {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            connections: DashMap::new(),
+            sinks: DashMap::new(),
+            next_file: AtomicU64::new(0),
+        }
+    }
+
+    /// Stop the guild's sink and wait until its file is complete.
+    async fn close_sink(&self, guild_id: GuildId) {
+        if let Some((_, sink)) = self.sinks.remove(&guild_id) {
+            sink.stop.store(true, Ordering::Relaxed);
+            let _ = tokio::task::spawn_blocking(move || sink.handle.join()).await;
+        }
+    }
+}
+
+#[async_trait]
+impl DiscordService for FileSinkDiscordService {
+    async fn join_voice_channel(&self, guild_id: GuildId, channel_id: ChannelId) -> ZakoResult<()> {
+        self.connections.insert(guild_id, channel_id);
+        Ok(())
+    }
+
+    async fn leave_voice_channel(&self, guild_id: GuildId) -> ZakoResult<()> {
+        self.close_sink(guild_id).await;
+        self.connections.remove(&guild_id);
+        Ok(())
+    }
+
+    #[instrument(skip(self, stream))]
+    async fn play_audio(&self, guild_id: GuildId, stream: OpusCons) -> ZakoResult<()> {
+        let Some(channel_id) = self.connections.get(&guild_id).map(|c| *c) else {
+            tracing::warn!(guild_id = %guild_id, "play_audio without a voice connection");
+            return Ok(());
+        };
+        self.close_sink(guild_id).await;
+
+        std::fs::create_dir_all(&self.dir)?;
+        let n = self.next_file.fetch_add(1, Ordering::Relaxed);
+        let path = self.dir.join(format!("{guild_id}-{channel_id}-{n}.ogg"));
+        let file = File::create(&path)?;
+        tracing::info!(path = %path.display(), "Writing session audio to file");
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = std::thread::spawn(move || run_sink(stream, file, &thread_stop, &path));
+        self.sinks.insert(guild_id, Sink { stop, handle });
+        Ok(())
+    }
+
+    async fn get_active_voice_connections(&self) -> ZakoResult<Vec<(GuildId, ChannelId)>> {
+        Ok(self
+            .connections
+            .iter()
+            .map(|c| (*c.key(), *c.value()))
+            .collect())
+    }
+}
+
+/// Copy frames into the file until the sink is stopped or the mixer goes away.
+fn run_sink(mut stream: OpusCons, file: File, stop: &AtomicBool, path: &Path) {
+    let mut writer = match OggOpusWriter::new(BufWriter::new(file)) {
+        Ok(writer) => writer,
+        Err(e) => {
+            tracing::error!(path = %path.display(), error = %e, "Failed to start Ogg stream");
+            return;
+        }
+    };
+
+    loop {
+        while let Some(frame) = stream.try_pop() {
+            if let Err(e) = writer.write_frame(frame) {
+                tracing::error!(path = %path.display(), error = %e, "Failed to write Opus frame");
+                return;
+            }
+        }
+        if stop.load(Ordering::Relaxed) || !stream.write_is_held() {
+            break;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    if let Err(e) = writer.finish().and_then(|mut w| w.flush()) {
+        tracing::error!(path = %path.display(), error = %e, "Failed to finish Ogg stream");
+    }
+}
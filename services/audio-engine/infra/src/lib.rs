@@ -1,8 +1,10 @@
 pub mod discord;
+pub mod file_sink;
 pub mod redis_state;
 pub mod state;
 pub mod taphub;
 
+pub use file_sink::FileSinkDiscordService;
 pub use redis_state::RedisStateService;
 pub use state::InMemoryStateService;
 pub use taphub::InstrumentedTapHubService;
@@ -121,6 +121,19 @@ async fn request_audio_meta(&self, mut request: AudioRequest) -> ZakoResult<Audi
 
         result
     }
+
+    #[instrument(skip(self, request), fields(tap_id = %request.tap_id.0))]
+    async fn store_loudness(
+        &self,
+        request: CachedAudioRequest,
+        loudness_lufs: f32,
+    ) -> ZakoResult<()> {
+        let client = self.get_client().await?;
+        client
+            .store_loudness(request, loudness_lufs)
+            .await
+            .map_err(ZakoError::TapHub)
+    }
 }
 
 pub struct StubTapHubService;
@@ -178,6 +191,8 @@ async fn request_audio(&self, request: CachedAudioRequest) -> ZakoResult<AudioRe
             cache_key: Some(request.cache_key),
             metadatas: vec![AudioMetadata::Title("Dumym Title".to_string())],
             stream: rx,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -197,6 +212,8 @@ async fn preload_audio(&self, _request: CachedAudioRequest) -> ZakoResult<AudioM
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -211,6 +228,8 @@ async fn request_audio_meta(&self, _request: AudioRequest) -> ZakoResult<AudioMe
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         let duration = start.elapsed();
@@ -220,6 +239,15 @@ async fn request_audio_meta(&self, _request: AudioRequest) -> ZakoResult<AudioMe
 
         Ok(result)
     }
+
+    #[instrument(skip(self, _request))]
+    async fn store_loudness(
+        &self,
+        _request: CachedAudioRequest,
+        _loudness_lufs: f32,
+    ) -> ZakoResult<()> {
+        Ok(())
+    }
 }
 
 pub struct InstrumentedTapHubService<T: TapHubService> {
@@ -299,4 +327,18 @@ async fn request_audio_meta(&self, request: AudioRequest) -> ZakoResult<AudioMet
 
         result
     }
+
+    #[instrument(skip_all, fields(tap_id = %request.tap_id.0))]
+    async fn store_loudness(
+        &self,
+        request: CachedAudioRequest,
+        loudness_lufs: f32,
+    ) -> ZakoResult<()> {
+        let result = self.inner.store_loudness(request, loudness_lufs).await;
+        if let Err(e) = &result {
+            tracing::warn!(error = %e, "Storing measured loudness failed");
+            metrics::record_taphub_error("store_loudness");
+        }
+        result
+    }
 }
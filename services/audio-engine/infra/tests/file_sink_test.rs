@@ -0,0 +1,45 @@
+use zako3_audio_engine_audio::{create_opus_ringbuf_pair, ringbuf::traits::Producer};
+use zako3_audio_engine_core::{
+    service::discord::DiscordService,
+    types::{ChannelId, GuildId},
+};
+use zako3_audio_engine_infra::FileSinkDiscordService;
+
+#[tokio::test]
+async fn file_sink_writes_session_audio_to_ogg() {
+    let dir = std::env::temp_dir().join(format!("zako3-file-sink-{}", std::process::id()));
+    let service = FileSinkDiscordService::new(&dir);
+    let guild_id = GuildId::from(1);
+    let channel_id = ChannelId::from(2);
+
+    service
+        .join_voice_channel(guild_id, channel_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        service.get_active_voice_connections().await.unwrap(),
+        vec![(guild_id, channel_id)]
+    );
+
+    let (mut prod, cons) = create_opus_ringbuf_pair();
+    service.play_audio(guild_id, cons).await.unwrap();
+    for i in 0..10u8 {
+        prod.try_push(vec![0xfc, i].into()).unwrap();
+    }
+
+    // Leaving drains whatever is buffered and closes the file.
+    service.leave_voice_channel(guild_id).await.unwrap();
+    assert!(
+        service
+            .get_active_voice_connections()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let ogg = std::fs::read(dir.join("1-2-0.ogg")).unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(ogg.starts_with(b"OggS"));
+    assert!(ogg.windows(8).any(|w| w == b"OpusHead"));
+    assert!(ogg.windows(2).any(|w| w == [0xfc, 9]));
+}
@@ -4,11 +4,14 @@
     extract::{Path, Query, State},
 };
 use hq_core::{CoreError, Service};
+use hq_types::hq::playback::{AnnouncementDto, AnnouncementFailureDto, AnnouncementResultDto};
 use hq_types::hq::settings::PartialUserSettings;
 use hq_types::hq::{
-    AuthUserDto, PaginatedResponseDto, PlatformStatsDto, RejectVerificationDto, UpdateUserRoleDto,
-    UserId, VerificationRequest, VerificationRequestId, VerificationStatus,
+    AuthUserDto, DiscordUserId, PaginatedResponseDto, PlatformStatsDto, RejectVerificationDto,
+    TapId, UpdateUserRoleDto, UserId, VerificationRequest, VerificationRequestId,
+    VerificationStatus,
 };
+use hq_types::{AudioRequestString, GuildId, TapName};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -498,3 +501,76 @@ pub async fn get_platform_stats(
         global_unique_users,
     }))
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/announcements",
+    request_body = AnnouncementDto,
+    responses(
+        (status = 200, description = "Announcement queued in the guild's voice sessions, and the ones it failed in", body = AnnouncementResultDto)
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn send_announcement(
+    State(service): State<Arc<Service>>,
+    AdminUser(admin_id): AdminUser,
+    Json(payload): Json<AnnouncementDto>,
+) -> Result<Json<AnnouncementResultDto>, (axum::http::StatusCode, String)> {
+    let guild_id: u64 = payload.guild_id.parse().map_err(|_| {
+        (
+            axum::http::StatusCode::BAD_REQUEST,
+            "invalid guild_id".to_string(),
+        )
+    })?;
+    if payload.message.trim().is_empty() {
+        return Err((
+            axum::http::StatusCode::BAD_REQUEST,
+            "message is empty".to_string(),
+        ));
+    }
+
+    let tap_id = match payload.tap_id {
+        Some(tap_id) => TapId(tap_id),
+        None => service
+            .tap
+            .get_tap_by_name(&TapName::from("google".to_string()))
+            .await
+            .map_err(map_error)?
+            .map(|tap| tap.id)
+            .ok_or_else(|| {
+                map_error(CoreError::NotFound(
+                    "Default tap 'google' not found.".into(),
+                ))
+            })?,
+    };
+    let admin = service
+        .auth
+        .get_user(&admin_id.to_string())
+        .await
+        .map_err(map_error)?;
+
+    let delivery = service
+        .audio_engine
+        .announce(
+            GuildId::from(guild_id),
+            tap_id,
+            AudioRequestString::from(payload.message),
+            DiscordUserId::from(admin.discord_id),
+        )
+        .await
+        .map_err(map_error)?;
+
+    Ok(Json(AnnouncementResultDto {
+        sessions: delivery.delivered,
+        failures: delivery
+            .failures
+            .into_iter()
+            .map(|(channel_id, e)| AnnouncementFailureDto {
+                channel_id: channel_id.to_string(),
+                error: e.to_string(),
+            })
+            .collect(),
+    }))
+}
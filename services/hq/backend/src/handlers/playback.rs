@@ -15,7 +15,7 @@
 use hq_core::{Claims, CoreError, Service};
 use hq_types::hq::playback::{
     EditQueueDto, GuildPlaybackStateDto, PauseTrackDto, PlaybackActionDto, PlaybackEvent,
-    ResumeTrackDto, SkipDto, StopTrackDto,
+    ResumeTrackDto, SetQueueModeDto, SkipDto, StopTrackDto,
 };
 use jsonwebtoken::{DecodingKey, Validation, decode};
 use std::sync::Arc;
@@ -192,6 +192,29 @@ pub async fn skip_music(
     Ok(Json(action))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/playback/queue-mode",
+    request_body = SetQueueModeDto,
+    responses(
+        (status = 200, description = "Queue mode changed", body = PlaybackActionDto)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_queue_mode(
+    State(service): State<Arc<Service>>,
+    AuthUser(user_id): AuthUser,
+    Json(payload): Json<SetQueueModeDto>,
+) -> Result<Json<PlaybackActionDto>, (StatusCode, String)> {
+    let discord_id = get_discord_id(&service, &user_id).await?;
+    let action = service
+        .playback
+        .set_queue_mode(payload, &discord_id)
+        .await
+        .map_err(map_error)?;
+    Ok(Json(action))
+}
+
 #[utoipa::path(
     patch,
     path = "/api/v1/playback/queue",
@@ -279,17 +302,32 @@ pub async fn playback_sse(
     };
 
     let user_id = token_data.claims.sub;
-    if service.auth.get_user(&user_id).await.is_err() {
-        return (StatusCode::UNAUTHORIZED, "Unknown user").into_response();
-    }
+    let discord_id = match service.auth.get_user(&user_id).await {
+        Ok(user) => user.discord_id,
+        Err(_) => return (StatusCode::UNAUTHORIZED, "Unknown user").into_response(),
+    };
 
     let rx = event_tx.subscribe();
-    let stream = BroadcastStream::new(rx).filter_map(|msg| async move {
-        msg.ok().and_then(|event| {
+    let stream = BroadcastStream::new(rx).filter_map(move |msg| {
+        let service = service.clone();
+        let discord_id = discord_id.clone();
+        async move {
+            let event = msg.ok()?;
+            // Track events carry a guild's tracks and tap failures; only
+            // users in that guild get them.
+            if let PlaybackEvent::Track(track) = &event
+                && !service
+                    .playback
+                    .is_in_guild(&discord_id, &track.guild_id)
+                    .await
+                    .unwrap_or(false)
+            {
+                return None;
+            }
             serde_json::to_string(&event)
                 .ok()
                 .map(|data| Ok::<Event, std::convert::Infallible>(Event::default().data(data)))
-        })
+        }
     });
 
     Sse::new(stream)
@@ -72,6 +72,7 @@
         handlers::admin::update_user_settings,
         handlers::admin::get_user_guild_settings,
         handlers::admin::update_user_guild_settings,
+        handlers::admin::send_announcement,
         handlers::tap::request_verification,
         handlers::notification::list_notifications,
         handlers::notification::mark_notification_read,
@@ -82,6 +83,7 @@
         handlers::playback::skip_music,
         handlers::playback::pause_track,
         handlers::playback::resume_track,
+        handlers::playback::set_queue_mode,
         handlers::playback::edit_queue,
         handlers::playback::undo_action,
         handlers::playback::get_history,
@@ -155,9 +157,15 @@
             hq_types::hq::playback::StopTrackDto,
             hq_types::hq::playback::SkipDto,
             hq_types::hq::playback::PauseTrackDto,
+            hq_types::hq::playback::AnnouncementDto,
+            hq_types::hq::playback::AnnouncementResultDto,
+            hq_types::hq::playback::AnnouncementFailureDto,
             hq_types::hq::playback::ResumeTrackDto,
             hq_types::hq::playback::QueueOperation,
             hq_types::hq::playback::EditQueueDto,
+            hq_types::hq::playback::SetQueueModeDto,
+            hq_types::QueueMode,
+            hq_types::RepeatMode,
 
             hq_types::hq::guild::GuildSummaryDto,
 
@@ -276,6 +284,10 @@ pub fn app(
             delete(cache::delete_tap_cache_entry),
         )
         .route("/api/v1/admin/stats", get(admin::get_platform_stats))
+        .route(
+            "/api/v1/admin/announcements",
+            post(admin::send_announcement),
+        )
         .route(
             "/api/v1/notifications/unread-count",
             get(notification::get_unread_count),
@@ -346,6 +358,10 @@ pub fn app(
             "/api/v1/playback/queue",
             axum::routing::patch(playback::edit_queue),
         )
+        .route(
+            "/api/v1/playback/queue-mode",
+            post(playback::set_queue_mode),
+        )
         .route(
             "/api/v1/playback/undo/:action_id",
             post(playback::undo_action),
@@ -4,7 +4,9 @@
 //! `PlaybackEvent::PlaybackChanged`, forwards a JSON-RPC notification string to
 //! the MCP SSE broadcast channel, which `mcp_sse` fans out to every connected
 //! client. (Per the user's decision the existing `PlaybackChanged` event is
-//! reused as the "playback end" signal.)
+//! reused as the "playback end" signal.) Track lifecycle events relayed from
+//! the audio engine go out as `notifications/playback/track`, with the
+//! `TrackEventDto` as params.
 
 use hq_core::PlaybackEvent;
 use mcpkit::protocol::{Message, Notification};
@@ -12,6 +14,8 @@
 
 /// MCP notification method clients receive over `/mcp/sse`.
 const PLAYBACK_METHOD: &str = "notifications/playback/changed";
+/// Per-track lifecycle notification (queued, started, ended, ...).
+const TRACK_METHOD: &str = "notifications/playback/track";
 
 /// Spawn the background task bridging playback events to MCP SSE clients.
 pub fn spawn_playback_notifier(
@@ -32,6 +36,14 @@ pub fn spawn_playback_notifier(
                         Err(e) => tracing::warn!(%e, "failed to serialize playback notification"),
                     }
                 }
+                Ok(PlaybackEvent::Track(track)) => {
+                    let note = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": TRACK_METHOD,
+                        "params": track,
+                    });
+                    let _ = sse_tx.send(note.to_string());
+                }
                 Ok(_) => {}
                 Err(broadcast::error::RecvError::Lagged(skipped)) => {
                     tracing::warn!(skipped, "playback notifier lagged");
@@ -4,7 +4,9 @@
 use crate::mcp::support::{json_ok, map_core, mk_tool, parse_args, run};
 use hq_core::Service;
 use hq_types::hq::UserId;
-use hq_types::hq::playback::{EditQueueDto, PauseTrackDto, ResumeTrackDto, SkipDto, StopTrackDto};
+use hq_types::hq::playback::{
+    EditQueueDto, PauseTrackDto, ResumeTrackDto, SetQueueModeDto, SkipDto, StopTrackDto,
+};
 use mcpkit::server::capability::tools::ToolService;
 use mcpkit::types::ToolOutput;
 use serde::Deserialize;
@@ -165,11 +167,34 @@ pub fn register(tools: &mut ToolService, service: &Arc<Service>) {
         },
     );
 
+    let svc = service.clone();
+    tools.register(
+        mk_tool(
+            "set_queue_mode",
+            "Set repeat (off, one, queue) and shuffle for the music queue. Body is a SetQueueModeDto.",
+            json!({"type": "object"}),
+        ),
+        move |args, _ctx| {
+            let svc = svc.clone();
+            run(async move {
+                let uid = require_user()?;
+                let did = discord_id(&svc, &uid).await?;
+                let p: SetQueueModeDto = parse_args(args)?;
+                let action = svc
+                    .playback
+                    .set_queue_mode(p, &did)
+                    .await
+                    .map_err(map_core)?;
+                json_ok(&action)
+            })
+        },
+    );
+
     let svc = service.clone();
     tools.register(
         mk_tool(
             "edit_queue",
-            "Edit the playback queue. Body is an EditQueueDto.",
+            "Edit the playback queue. Body is an EditQueueDto; ops are remove, set_volume, move, play_next and insert_at.",
             json!({"type": "object"}),
         ),
         move |args, _ctx| {
@@ -86,6 +86,7 @@ pub fn register(tools: &mut ToolService, service: &Arc<Service>) {
                         uptime_percent: 0.0,
                         use_rate_history: vec![],
                         cache_hit_rate_history: vec![],
+                        connections: vec![],
                     },
                 };
                 json_ok(&dto)
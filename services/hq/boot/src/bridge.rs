@@ -1,7 +1,8 @@
 use futures_util::StreamExt;
-use hq_core::PlaybackEvent;
+use hq_core::{PlaybackEvent, TrackEventDto};
 use tokio::sync::broadcast;
 use zako3_states::RedisPubSub;
+use zako3_tl_client::SessionEventStream;
 
 pub async fn run_history_bridge(
     redis_url: String,
@@ -26,3 +27,26 @@ pub async fn run_history_bridge(
         tokio::time::sleep(std::time::Duration::from_secs(5)).await;
     }
 }
+
+/// Relays track lifecycle events from TL into the playback event stream, so SSE
+/// and MCP clients hear about them as they happen.
+pub async fn run_session_event_bridge(tl_url: String, event_tx: broadcast::Sender<PlaybackEvent>) {
+    loop {
+        match SessionEventStream::connect(&tl_url).await {
+            Ok(mut stream) => {
+                while let Some(event) = stream.next().await {
+                    match event {
+                        Ok(event) => {
+                            let _ =
+                                event_tx.send(PlaybackEvent::Track(TrackEventDto::from(&event)));
+                        }
+                        Err(e) => tracing::warn!(%e, "Malformed session event from TL"),
+                    }
+                }
+                tracing::warn!("session event subscription ended; reconnecting");
+            }
+            Err(e) => tracing::error!(%e, "Failed to subscribe to TL session events"),
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+}
@@ -60,6 +60,12 @@ async fn main() -> anyhow::Result<()> {
     ));
     info!("History bridge started (stats SSE + playback SSE)");
 
+    // Bridge TL's track lifecycle events into event_tx for SSE and MCP.
+    tokio::spawn(bridge::run_session_event_bridge(
+        config.traffic_light_url.clone(),
+        event_tx.clone(),
+    ));
+
     let backend_address = config.backend_address.clone();
     let service_backend = service.clone();
     let event_tx_backend = event_tx.clone();
@@ -1,15 +1,24 @@
 use std::hash::{BuildHasher, Hasher, RandomState};
+use std::time::Duration;
 
 use crate::{Context, Error, ui, util};
 use hq_core::CoreError;
 use hq_types::{
-    AudioRequestString, AudioStopFilter, ChannelId, GuildId, QueueName, Volume,
+    AudioRequestString, AudioStopFilter, ChannelId, GuildId, QueueName, TrackDsp, Volume,
     hq::{DiscordUserId, TapId, TapName},
 };
 use poise::serenity_prelude as serenity;
 
 const MUSIC_QUEUE: &str = "music";
 
+const SEEK_STEP_MS: i64 = 10_000;
+const SEEK_BACK_ID: &str = "seek_back";
+const SEEK_FORWARD_ID: &str = "seek_forward";
+/// How long the forward/back buttons under a `/seek` reply stay active.
+const SEEK_BUTTON_TIMEOUT: Duration = Duration::from_secs(300);
+
+const DEFAULT_CLIP_SECS: u32 = 30;
+
 #[derive(Debug, poise::ChoiceParameter)]
 pub enum StopScope {
     #[name = "현재 트랙"]
@@ -370,3 +379,224 @@ pub async fn volume(
     ctx.say(ui::messages::volume_set(level)).await?;
     Ok(())
 }
+
+/// Jump to a position in the current track.
+#[poise::command(
+    slash_command,
+    name_localized("ko", "탐색"),
+    description_localized("en-US", "Jump to a position in the current track"),
+    description_localized("ko", "현재 트랙의 재생 위치 이동")
+)]
+pub async fn seek(
+    ctx: Context<'_>,
+    #[description = "Position such as 1:23, or an offset such as +10 / -10"]
+    #[description_localized("ko", "재생 위치 (예: 1:23) 또는 이동할 시간 (예: +10, -10)")]
+    position: String,
+    #[description = "Voice channel to use (defaults to your current channel)"]
+    #[description_localized("ko", "사용할 음성 채널 (기본값: 현재 채널)")]
+    #[channel_types("Voice")]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let target = util::parse_seek_target(&position).ok_or(Error::InvalidSeekPosition)?;
+    let session = util::resolve_session(ctx, channel).await?;
+
+    let (position_ms, duration_ms) =
+        seek_music(ctx, session.guild_id, session.channel_id, target).await?;
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(ui::messages::seeked(position_ms, duration_ms))
+                .components(vec![seek_buttons()]),
+        )
+        .await?;
+    let message = reply.message().await?;
+
+    while let Some(mci) = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .message_id(message.id)
+        .author_id(ctx.author().id)
+        .timeout(SEEK_BUTTON_TIMEOUT)
+        .await
+    {
+        let delta = match mci.data.custom_id.as_str() {
+            SEEK_BACK_ID => -SEEK_STEP_MS,
+            SEEK_FORWARD_ID => SEEK_STEP_MS,
+            _ => continue,
+        };
+        let content = match seek_music(
+            ctx,
+            session.guild_id,
+            session.channel_id,
+            util::SeekTarget::Relative(delta),
+        )
+        .await
+        {
+            Ok((position_ms, duration_ms)) => ui::messages::seeked(position_ms, duration_ms),
+            Err(e) => e.to_user_message().into_owned(),
+        };
+        mci.create_response(
+            ctx.serenity_context(),
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await?;
+    }
+
+    reply
+        .edit(ctx, poise::CreateReply::default().components(vec![]))
+        .await?;
+    Ok(())
+}
+
+/// Change the speed, pitch or tempo of the current track.
+#[poise::command(
+    slash_command,
+    name_localized("ko", "효과"),
+    description_localized("en-US", "Change the speed, pitch or tempo of the current track"),
+    description_localized("ko", "현재 트랙의 속도, 음높이, 템포 변경")
+)]
+pub async fn effects(
+    ctx: Context<'_>,
+    #[description = "Speed without changing pitch (0.5–2.0)"]
+    #[description_localized("ko", "음높이를 유지한 채 바꿀 속도 (0.5–2.0)")]
+    #[min = 0.5]
+    #[max = 2.0]
+    speed: Option<f32>,
+    #[description = "Pitch shift in semitones (-12–12)"]
+    #[description_localized("ko", "반음 단위 음높이 변경 (-12–12)")]
+    #[min = -12.0]
+    #[max = 12.0]
+    pitch: Option<f32>,
+    #[description = "Speed and pitch together, like nightcore (0.5–2.0)"]
+    #[description_localized("ko", "속도와 음높이를 함께 변경 (0.5–2.0)")]
+    #[min = 0.5]
+    #[max = 2.0]
+    tempo: Option<f32>,
+    #[description = "Clear every effect before applying the options above"]
+    #[description_localized("ko", "위 옵션을 적용하기 전에 모든 효과 초기화")]
+    reset: Option<bool>,
+    #[description = "Voice channel to use (defaults to your current channel)"]
+    #[description_localized("ko", "사용할 음성 채널 (기본값: 현재 채널)")]
+    #[channel_types("Voice")]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let session = util::resolve_session(ctx, channel).await?;
+    let ae = &ctx.data().service.audio_engine;
+
+    let state = ae
+        .get_session_state(session.guild_id, session.channel_id)
+        .await?;
+    let music_q = QueueName::from(MUSIC_QUEUE.to_string());
+    let track = state
+        .queues
+        .get(&music_q)
+        .and_then(|q| q.first())
+        .ok_or(Error::NothingPlaying)?;
+
+    let mut dsp = if reset.unwrap_or(false) {
+        TrackDsp::default()
+    } else {
+        track.dsp
+    };
+    if let Some(speed) = speed {
+        dsp.speed = speed;
+    }
+    if let Some(pitch) = pitch {
+        dsp.pitch = pitch;
+    }
+    if let Some(tempo) = tempo {
+        dsp.tempo = tempo;
+    }
+    let dsp = dsp.clamped();
+
+    ae.set_dsp(session.guild_id, session.channel_id, track.track_id, dsp)
+        .await?;
+
+    ctx.say(ui::messages::effects_set(dsp)).await?;
+    Ok(())
+}
+
+/// Export the last seconds of what the bot played as an audio file.
+#[poise::command(
+    slash_command,
+    name_localized("ko", "클립"),
+    description_localized("en-US", "Export the last seconds of what the bot played"),
+    description_localized("ko", "최근 재생된 소리를 파일로 내보내기")
+)]
+pub async fn clip(
+    ctx: Context<'_>,
+    #[description = "Seconds to export (default: 30, 0 exports everything kept, up to 5 minutes)"]
+    #[description_localized("ko", "내보낼 길이(초) (기본값: 30, 0이면 보관된 전체, 최대 5분)")]
+    #[max = 300]
+    seconds: Option<u32>,
+    #[description = "Voice channel to use (defaults to your current channel)"]
+    #[description_localized("ko", "사용할 음성 채널 (기본값: 현재 채널)")]
+    #[channel_types("Voice")]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    ctx.defer().await?; // Encoding and uploading a few minutes of audio can take a moment.
+
+    let session = util::resolve_session(ctx, channel).await?;
+    if !session.settings.recording.enabled {
+        return Err(Error::RecordingDisabled);
+    }
+
+    let seconds = match seconds.unwrap_or(DEFAULT_CLIP_SECS) {
+        0 => None,
+        seconds => Some(seconds),
+    };
+    let ogg = ctx
+        .data()
+        .service
+        .audio_engine
+        .export_recording(session.guild_id, session.channel_id, seconds)
+        .await?;
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(ui::messages::clip_exported(seconds))
+            .attachment(serenity::CreateAttachment::bytes(ogg, "clip.ogg")),
+    )
+    .await?;
+    Ok(())
+}
+
+fn seek_buttons() -> serenity::CreateActionRow {
+    serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(SEEK_BACK_ID)
+            .label("⏪ 10초")
+            .style(serenity::ButtonStyle::Secondary),
+        serenity::CreateButton::new(SEEK_FORWARD_ID)
+            .label("10초 ⏩")
+            .style(serenity::ButtonStyle::Secondary),
+    ])
+}
+
+/// Seek the head of the music queue and return its new position and duration.
+async fn seek_music(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    target: util::SeekTarget,
+) -> Result<(u64, Option<u64>), Error> {
+    let ae = &ctx.data().service.audio_engine;
+
+    let state = ae.get_session_state(guild_id, channel_id).await?;
+    let music_q = QueueName::from(MUSIC_QUEUE.to_string());
+    let track = state
+        .queues
+        .get(&music_q)
+        .and_then(|q| q.first())
+        .ok_or(Error::NothingPlaying)?;
+
+    let mut position_ms = target.resolve(track.position_ms);
+    if let Some(duration_ms) = track.duration_ms {
+        position_ms = position_ms.min(duration_ms);
+    }
+
+    ae.seek(guild_id, channel_id, track.track_id, position_ms)
+        .await?;
+
+    Ok((position_ms, track.duration_ms))
+}
@@ -1,6 +1,9 @@
 use crate::{Context, Error, ui, util};
 use hq_core::CoreError;
-use hq_types::{AudioRequestString, AudioStopFilter, QueueName, Track, UserId, Volume, hq::{DiscordUserId, TapName}};
+use hq_types::{
+    AudioRequestString, AudioStopFilter, QueueName, RepeatMode, Track, UserId, Volume,
+    hq::{DiscordUserId, TapName, playback::SetQueueModeDto},
+};
 use poise::serenity_prelude as serenity;
 
 const MUSIC_QUEUE_PREFIX: &str = "music";
@@ -16,9 +19,32 @@ pub enum ClearTarget {
     All,
 }
 
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum RepeatChoice {
+    #[name = "Off"]
+    #[name_localized("ko", "끄기")]
+    Off,
+    #[name = "Current track"]
+    #[name_localized("ko", "한 곡")]
+    One,
+    #[name = "Whole queue"]
+    #[name_localized("ko", "전체 대기열")]
+    Queue,
+}
+
+impl From<RepeatChoice> for RepeatMode {
+    fn from(choice: RepeatChoice) -> Self {
+        match choice {
+            RepeatChoice::Off => RepeatMode::Off,
+            RepeatChoice::One => RepeatMode::One,
+            RepeatChoice::Queue => RepeatMode::Queue,
+        }
+    }
+}
+
 #[poise::command(
     slash_command,
-    subcommands("music", "tts", "web"),
+    subcommands("music", "tts", "web", "mode"),
     name_localized("ko", "대기열"),
     description_localized("en-US", "View the current queue"),
     description_localized("ko", "현재 대기열 보기")
@@ -52,11 +78,55 @@ pub async fn music(
     let music_q = QueueName::from(MUSIC_QUEUE_PREFIX.to_string());
     let tracks: &[Track] = state.queues.get(&music_q).map(Vec::as_slice).unwrap_or(&[]);
 
-    let embed = ui::embeds::queue_music_embed(tracks);
+    let embed = ui::embeds::queue_music_embed(tracks, state.queue_mode);
     ctx.send(poise::CreateReply::default().embed(embed)).await?;
     Ok(())
 }
 
+/// Set repeat and shuffle for the music queue.
+#[poise::command(
+    slash_command,
+    name_localized("ko", "모드"),
+    description_localized("en-US", "Set repeat and shuffle for the music queue"),
+    description_localized("ko", "음악 대기열 반복 및 셔플 설정")
+)]
+pub async fn mode(
+    ctx: Context<'_>,
+    #[description = "Repeat mode (unchanged if omitted)"]
+    #[description_localized("ko", "반복 모드 (생략하면 유지)")]
+    repeat: Option<RepeatChoice>,
+    #[description = "Play the queue in random order (unchanged if omitted)"]
+    #[description_localized("ko", "무작위 순서로 재생 (생략하면 유지)")]
+    shuffle: Option<bool>,
+    #[description = "Voice channel to use (defaults to your current channel)"]
+    #[description_localized("ko", "사용할 음성 채널 (기본값: 현재 채널)")]
+    #[channel_types("Voice")]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let session = util::resolve_session(ctx, channel).await?;
+    let service = &ctx.data().service;
+
+    service
+        .playback
+        .set_queue_mode(
+            SetQueueModeDto {
+                guild_id: session.guild_id.to_string(),
+                channel_id: session.channel_id.to_string(),
+                repeat: repeat.map(RepeatMode::from),
+                shuffle,
+            },
+            &ctx.author().id.get().to_string(),
+        )
+        .await?;
+
+    let state = service
+        .audio_engine
+        .get_session_state(session.guild_id, session.channel_id)
+        .await?;
+    ctx.say(ui::messages::queue_mode(state.queue_mode)).await?;
+    Ok(())
+}
+
 /// Show the upcoming TTS messages.
 #[poise::command(
     slash_command,
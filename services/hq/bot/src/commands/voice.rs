@@ -39,7 +39,11 @@ pub(crate) async fn bot_join_and_announce(
 
     service.audio_engine.join(guild_id, channel_id).await?;
 
-    let queue_name: QueueName = format!("temp-alert-{}", uuid::Uuid::new_v4()).into();
+    if let Err(e) = service.user_settings.sync_session_settings(guild_id).await {
+        tracing::warn!(error = %e, "Failed to push guild audio settings after join");
+    }
+
+    let queue_name = QueueName::announcement();
 
     // Resolve tap ID for the announcement before spawning the task.
     let announcement_tap_id = service
@@ -17,6 +17,8 @@ pub enum BotError {
     UserNotInSession,
     #[error("볼륨은 0에서 150 사이여야 해요.")]
     InvalidVolume,
+    #[error("재생 위치는 `1:23`, `+10`, `-10` 같은 형식으로 입력해 주세요.")]
+    InvalidSeekPosition,
     #[error("이 작업을 수행할 권한이 없어요.")]
     Forbidden,
     #[error("먼저 로그인이 필요해요. `/settings`에서 로그인 링크를 확인하세요.")]
@@ -25,6 +27,8 @@ pub enum BotError {
     NothingPlaying,
     #[error("대기열이 비어 있어요.")]
     QueueEmpty,
+    #[error("이 서버는 세션 녹음이 꺼져 있어요. 서버 설정에서 켤 수 있어요.")]
+    RecordingDisabled,
     #[error("이 명령어는 서버에서만 사용할 수 있어요.")]
     ShouldRunInGuild,
     // ── Transparent infrastructure errors ──────────────────────────────────
@@ -48,12 +52,18 @@ pub fn to_user_message(&self) -> Cow<'_, str> {
             BotError::BotNotInVoiceChannel => "저는 현재 음성 채널에 있지 않아요.".into(),
             BotError::UserNotInSession => "제가 활동 중인 음성 채널에 있지 않으세요.".into(),
             BotError::InvalidVolume => "볼륨은 0에서 150 사이여야 해요.".into(),
+            BotError::InvalidSeekPosition => {
+                "재생 위치는 `1:23`, `+10`, `-10` 같은 형식으로 입력해 주세요.".into()
+            }
             BotError::Forbidden => "이 작업을 수행할 권한이 없어요.".into(),
             BotError::Unauthorized => {
                 "먼저 로그인이 필요해요. `/settings`에서 로그인 링크를 확인하세요.".into()
             }
             BotError::NothingPlaying => "현재 재생 중인 항목이 없어요.".into(),
             BotError::QueueEmpty => "대기열이 비어 있어요.".into(),
+            BotError::RecordingDisabled => {
+                "이 서버는 세션 녹음이 꺼져 있어요. 서버 설정에서 켤 수 있어요.".into()
+            }
             BotError::ShouldRunInGuild => "이 명령어는 서버에서만 사용할 수 있어요.".into(),
             BotError::Core(inner) => core_error_message(inner),
             BotError::Serenity(_) => "Discord와 통신하는 중 문제가 발생했어요.".into(),
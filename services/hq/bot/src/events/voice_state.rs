@@ -457,7 +457,7 @@ async fn announce_join_leave(
             continue;
         };
 
-        let queue_name: QueueName = format!("temp-alert-{}", uuid::Uuid::new_v4()).into();
+        let queue_name = QueueName::announcement();
         service
             .audio_engine
             .play(
@@ -117,6 +117,9 @@ pub async fn run(
                 commands::music::stop(),
                 commands::music::skip(),
                 commands::music::volume(),
+                commands::music::seek(),
+                commands::music::effects(),
+                commands::music::clip(),
                 commands::music::wedding(),
                 commands::queue::queue(),
                 commands::queue::clear(),
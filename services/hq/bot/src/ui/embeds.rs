@@ -1,4 +1,4 @@
-use hq_types::{AudioMetadata, ChannelId, Track, hq::dtos::TapWithAccessDto};
+use hq_types::{AudioMetadata, ChannelId, QueueMode, Track, hq::dtos::TapWithAccessDto};
 use poise::serenity_prelude::{self as serenity, Colour};
 
 const THEME: Colour = Colour(0xeb3489);
@@ -79,7 +79,7 @@ pub fn track_queued_embed(track: &Track, position: usize) -> serenity::CreateEmb
     embed
 }
 
-pub fn queue_music_embed(tracks: &[Track]) -> serenity::CreateEmbed {
+pub fn queue_music_embed(tracks: &[Track], mode: QueueMode) -> serenity::CreateEmbed {
     let description = if tracks.is_empty() {
         "음악 대기열이 비어 있어요.".to_string()
     } else {
@@ -98,6 +98,9 @@ pub fn queue_music_embed(tracks: &[Track]) -> serenity::CreateEmbed {
     serenity::CreateEmbed::new()
         .title("음악 대기열")
         .description(description)
+        .footer(serenity::CreateEmbedFooter::new(
+            super::messages::queue_mode_label(mode),
+        ))
         .colour(THEME)
 }
 
@@ -166,8 +169,12 @@ pub fn help_embed(category: HelpCategory) -> serenity::CreateEmbed {
              /stop [범위] — 재생 정지 (`current` 또는 `queue`)\n\
              /skip [개수] — 트랙 건너뛰기\n\
              /volume <0-150> — 재생 볼륨 조절\n\
+             /seek <위치|±초> — 재생 위치 이동 (예: `1:23`, `+10`)\n\
+             /effects [speed] [pitch] [tempo] — 현재 트랙의 속도·음높이·템포 변경\n\
+             /clip [초] — 최근 재생된 소리를 최대 5분까지 파일로 내보내기 (세션 녹음 필요)\n\
              /queue music — 현재 음악 대기열 보기\n\
              /queue web — 웹 대기열 인터페이스 열기\n\
+             /queue mode [반복] [셔플] — 반복 및 셔플 설정\n\
              /clear [music|tts|all] — 대기열 비우기",
         ),
         HelpCategory::Tts => (
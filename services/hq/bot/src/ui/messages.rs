@@ -1,5 +1,8 @@
+use hq_types::{QueueMode, RepeatMode, TrackDsp};
 use poise::serenity_prelude::ChannelId;
 
+use crate::util::format_position;
+
 pub fn channel_enabled(channel_id: ChannelId) -> String {
     format!("앞으로 <#{channel_id}>의 메시지를 읽을게요!")
 }
@@ -28,6 +31,48 @@ pub fn skipped(count: u32) -> String {
     }
 }
 
+pub fn seeked(position_ms: u64, duration_ms: Option<u64>) -> String {
+    let position = format_position(position_ms);
+    match duration_ms {
+        Some(duration_ms) => {
+            let duration = format_position(duration_ms);
+            format!("**{position}**로 이동했어요. (전체 {duration})")
+        }
+        None => format!("**{position}**로 이동했어요."),
+    }
+}
+
+pub fn effects_set(dsp: TrackDsp) -> String {
+    if dsp.is_identity() {
+        return "효과를 모두 껐어요.".to_string();
+    }
+    format!(
+        "효과를 적용했어요. 속도 **{:.2}x** · 음높이 **{:+.1}** · 템포 **{:.2}x**",
+        dsp.speed, dsp.pitch, dsp.tempo
+    )
+}
+
+pub fn clip_exported(seconds: Option<u32>) -> String {
+    match seconds {
+        Some(seconds) => format!("최근 {seconds}초를 내보냈어요."),
+        None => "보관된 녹음 전체를 내보냈어요.".to_string(),
+    }
+}
+
+pub fn queue_mode_label(mode: QueueMode) -> String {
+    let repeat = match mode.repeat {
+        RepeatMode::Off => "끔",
+        RepeatMode::One => "한 곡",
+        RepeatMode::Queue => "전체 대기열",
+    };
+    let shuffle = if mode.shuffle { "켬" } else { "끔" };
+    format!("반복: {repeat} · 셔플: {shuffle}")
+}
+
+pub fn queue_mode(mode: QueueMode) -> String {
+    format!("음악 대기열 모드를 바꿨어요. ({})", queue_mode_label(mode))
+}
+
 pub fn tts_skipped() -> &'static str {
     "지금 재생 중인 TTS를 건너뛰었어요."
 }
@@ -1,6 +1,8 @@
 pub mod emoji_parse;
+pub mod position;
 pub mod voice;
 pub use emoji_parse::{EmojiInfo, extract_custom_emojis, parse_discord_emoji};
+pub use position::{SeekTarget, format_position, parse_seek_target};
 pub use voice::{
     VoiceStateExt, get_user_voice_channel, require_guild_id, resolve_session,
     user_can_access_voice_channel,
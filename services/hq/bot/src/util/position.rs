@@ -0,0 +1,108 @@
+/// A `/seek` target as typed by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekTarget {
+    /// Absolute position in milliseconds.
+    Absolute(u64),
+    /// Offset from the current position in milliseconds.
+    Relative(i64),
+}
+
+impl SeekTarget {
+    /// Resolve against the current position, saturating at the start.
+    pub fn resolve(self, current_ms: u64) -> u64 {
+        match self {
+            SeekTarget::Absolute(ms) => ms,
+            SeekTarget::Relative(delta) => current_ms.saturating_add_signed(delta),
+        }
+    }
+}
+
+/// Parse `83`, `1:23`, `1:02:03`, or a signed offset such as `+10` / `-1:30`.
+pub fn parse_seek_target(input: &str) -> Option<SeekTarget> {
+    let input = input.trim();
+    let (sign, rest) = match input.as_bytes().first()? {
+        b'+' => (Some(1), &input[1..]),
+        b'-' => (Some(-1), &input[1..]),
+        _ => (None, input),
+    };
+
+    let mut secs: u64 = 0;
+    let mut parts = 0;
+    for part in rest.split(':') {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let value: u64 = part.parse().ok()?;
+        if parts > 0 && value >= 60 {
+            return None;
+        }
+        secs = secs.checked_mul(60)?.checked_add(value)?;
+        parts += 1;
+    }
+    if parts > 3 {
+        return None;
+    }
+
+    let ms = secs.checked_mul(1000)?;
+    match sign {
+        Some(sign) => Some(SeekTarget::Relative(sign * i64::try_from(ms).ok()?)),
+        None => Some(SeekTarget::Absolute(ms)),
+    }
+}
+
+/// Format milliseconds as `m:ss`, or `h:mm:ss` past an hour.
+pub fn format_position(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_absolute_positions() {
+        assert_eq!(parse_seek_target("83"), Some(SeekTarget::Absolute(83_000)));
+        assert_eq!(
+            parse_seek_target("1:23"),
+            Some(SeekTarget::Absolute(83_000))
+        );
+        assert_eq!(
+            parse_seek_target("1:02:03"),
+            Some(SeekTarget::Absolute(3_723_000))
+        );
+    }
+
+    #[test]
+    fn parses_relative_offsets() {
+        assert_eq!(parse_seek_target("+10"), Some(SeekTarget::Relative(10_000)));
+        assert_eq!(
+            parse_seek_target("-1:30"),
+            Some(SeekTarget::Relative(-90_000))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in ["", "+", "1:", "1:60", "a", "1:2:3:4", "1.5"] {
+            assert_eq!(parse_seek_target(input), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn relative_seek_saturates_at_start() {
+        assert_eq!(SeekTarget::Relative(-10_000).resolve(4_000), 0);
+        assert_eq!(SeekTarget::Relative(10_000).resolve(4_000), 14_000);
+    }
+
+    #[test]
+    fn formats_positions() {
+        assert_eq!(format_position(83_000), "1:23");
+        assert_eq!(format_position(3_723_000), "1:02:03");
+    }
+}
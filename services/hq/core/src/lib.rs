@@ -7,7 +7,7 @@
 pub use config::AppConfig;
 pub use db::get_pool;
 pub use error::{CoreError, CoreResult};
-pub use hq_types::hq::playback::PlaybackEvent;
+pub use hq_types::hq::playback::{PlaybackEvent, TrackEventDto};
 pub use service::{Claims, Service, SortDirection, TapSortField};
 use sqlx::migrate::Migrator;
 
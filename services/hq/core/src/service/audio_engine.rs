@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
 use hq_types::{
-    AudioRequestString, AudioStopFilter, ChannelId, GuildId, QueueName, SessionState, TrackId,
-    Volume,
+    AudioRequestString, AudioStopFilter, ChannelId, GuildId, Pan, QueueMode, QueueName,
+    SessionAudioSettings, SessionState, TrackDsp, TrackId, Volume,
     hq::{DiscordUserId, TapId, playback::PlaybackEvent},
 };
 use tokio::sync::broadcast;
@@ -71,6 +71,7 @@ pub async fn play(
                 audio_request_string,
                 volume,
                 discord_user_id,
+                TrackDsp::default(),
             )
             .await
             .map_err(map_tl_err)?;
@@ -92,6 +93,60 @@ pub async fn set_volume(
             .map_err(map_tl_err)
     }
 
+    #[instrument(skip(self), fields(guild_id = ?guild_id))]
+    pub async fn set_dsp(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        dsp: TrackDsp,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .set_dsp(guild_id, channel_id, track_id, dsp)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
+    #[instrument(skip(self), fields(guild_id = ?guild_id))]
+    pub async fn set_pan(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        pan: Pan,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .set_pan(guild_id, channel_id, track_id, pan)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
+    #[instrument(skip(self), fields(guild_id = ?guild_id))]
+    pub async fn seek(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        position_ms: u64,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .seek(guild_id, channel_id, track_id, position_ms)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
     #[instrument(skip(self), fields(guild_id = ?guild_id))]
     pub async fn stop(
         &self,
@@ -138,6 +193,103 @@ pub async fn next_music(&self, guild_id: GuildId, channel_id: ChannelId) -> Core
         Ok(result)
     }
 
+    #[instrument(skip(self), fields(guild_id = ?guild_id))]
+    pub async fn set_queue_mode(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        mode: QueueMode,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .set_queue_mode(guild_id, channel_id, mode)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(self, audio_request_string), fields(guild_id = ?guild_id, channel_id = ?channel_id, tap_id = %tap_id.0))]
+    pub async fn play_next(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        queue_name: QueueName,
+        tap_id: TapId,
+        audio_request_string: AudioRequestString,
+        volume: Volume,
+        discord_user_id: DiscordUserId,
+        dsp: TrackDsp,
+    ) -> CoreResult<()> {
+        self.client
+            .play_next(
+                guild_id,
+                channel_id,
+                queue_name,
+                tap_id,
+                audio_request_string,
+                volume,
+                discord_user_id,
+                dsp,
+            )
+            .await
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(self, audio_request_string), fields(guild_id = ?guild_id, channel_id = ?channel_id, tap_id = %tap_id.0))]
+    pub async fn insert_at(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        index: usize,
+        queue_name: QueueName,
+        tap_id: TapId,
+        audio_request_string: AudioRequestString,
+        volume: Volume,
+        discord_user_id: DiscordUserId,
+        dsp: TrackDsp,
+    ) -> CoreResult<()> {
+        self.client
+            .insert_at(
+                guild_id,
+                channel_id,
+                index,
+                queue_name,
+                tap_id,
+                audio_request_string,
+                volume,
+                discord_user_id,
+                dsp,
+            )
+            .await
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(guild_id = ?guild_id))]
+    pub async fn move_track(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        track_id: TrackId,
+        index: usize,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .move_track(guild_id, channel_id, track_id, index)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
     #[instrument(skip(self), fields(guild_id = ?guild_id))]
     pub async fn pause(
         &self,
@@ -172,6 +324,23 @@ pub async fn resume(
         Ok(result)
     }
 
+    #[instrument(skip(self, settings), fields(guild_id = ?guild_id, channel_id = ?channel_id))]
+    pub async fn set_session_settings(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        settings: SessionAudioSettings,
+    ) -> CoreResult<bool> {
+        let result = self
+            .client
+            .set_session_settings(guild_id, channel_id, settings)
+            .await
+            .map(|_| true)
+            .map_err(map_tl_err)?;
+        let _ = self.event_tx.send(PlaybackEvent::PlaybackChanged);
+        Ok(result)
+    }
+
     pub async fn get_session_state(
         &self,
         guild_id: GuildId,
@@ -183,6 +352,18 @@ pub async fn get_session_state(
             .map_err(map_tl_err)
     }
 
+    pub async fn export_recording(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        seconds: Option<u32>,
+    ) -> CoreResult<Vec<u8>> {
+        self.client
+            .export_recording(guild_id, channel_id, seconds)
+            .await
+            .map_err(map_tl_err)
+    }
+
     pub async fn get_sessions_in_guild(&self, guild_id: GuildId) -> CoreResult<Vec<SessionState>> {
         self.client
             .get_sessions_in_guild(guild_id)
@@ -193,4 +374,49 @@ pub async fn get_sessions_in_guild(&self, guild_id: GuildId) -> CoreResult<Vec<S
     pub async fn list_bot_ids(&self) -> CoreResult<Vec<String>> {
         self.client.list_bot_ids().await.map_err(map_tl_err)
     }
+
+    /// Speak `message` in every session of the guild, each on its own
+    /// announcement queue so music and TTS are held until it ends. A session
+    /// that fails does not keep it from the others.
+    #[instrument(skip(self, message), fields(guild_id = ?guild_id, tap_id = %tap_id.0))]
+    pub async fn announce(
+        &self,
+        guild_id: GuildId,
+        tap_id: TapId,
+        message: AudioRequestString,
+        discord_user_id: DiscordUserId,
+    ) -> CoreResult<AnnouncementDelivery> {
+        let sessions = self.get_sessions_in_guild(guild_id).await?;
+        let mut delivery = AnnouncementDelivery::default();
+        for session in &sessions {
+            match self
+                .play(
+                    guild_id,
+                    session.channel_id,
+                    QueueName::announcement(),
+                    tap_id.clone(),
+                    message.clone(),
+                    1.0.into(),
+                    discord_user_id.clone(),
+                )
+                .await
+            {
+                Ok(_) => delivery.delivered += 1,
+                Err(e) => {
+                    tracing::warn!(channel_id = ?session.channel_id, error = %e, "Announcement failed in session");
+                    delivery.failures.push((session.channel_id, e));
+                }
+            }
+        }
+        Ok(delivery)
+    }
+}
+
+/// Where an announcement went out.
+#[derive(Debug, Default)]
+pub struct AnnouncementDelivery {
+    /// Sessions the announcement was queued in.
+    pub delivered: usize,
+    /// Sessions it could not be queued in, and why.
+    pub failures: Vec<(ChannelId, CoreError)>,
 }
@@ -26,7 +26,7 @@
 pub use mapping::MappingService;
 pub use playback::{PlaybackService, UserVoiceInfo};
 pub mod audio_engine;
-pub use audio_engine::AudioEngineService;
+pub use audio_engine::{AnnouncementDelivery, AudioEngineService};
 pub mod emoji_match_publisher;
 pub use emoji_match_publisher::EmojiMatchPublisher;
 
@@ -271,6 +271,7 @@ pub async fn new(
                 user_guild_settings_repo,
                 global_settings_repo,
                 user_settings_cache,
+                audio_engine_service.clone(),
             ),
             voice_state,
             intended_vc,
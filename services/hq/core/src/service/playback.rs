@@ -1,13 +1,17 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use chrono::Utc;
 use hq_types::{
-    AudioMetadata, ChannelId, GuildId, QueueName, TrackId, Volume,
+    AudioMetadata, AudioRequestString, ChannelId, GuildId, QueueMode, QueueName, Track, TrackDsp,
+    TrackId, Volume,
     hq::{
-        UserSettings,
+        DiscordUserId, TapId, UserSettings,
         playback::{
             AudioMetadataDto, DiscordUserInfoDto, EditQueueDto, GuildPlaybackStateDto,
-            PlaybackActionDto, QueueMetaDto, TrackDto,
+            PlaybackActionDto, QueueMetaDto, QueueOperation, SetQueueModeDto, TrackDto,
         },
         settings::TextReadingRule,
     },
@@ -44,6 +48,14 @@ fn metadata_to_dto(m: &AudioMetadata) -> AudioMetadataDto {
     }
 }
 
+fn op_track_ids<'a>(operations: &'a [QueueOperation], op: &str) -> HashSet<&'a str> {
+    operations
+        .iter()
+        .filter(|o| o.op == op)
+        .map(|o| o.track_id.as_str())
+        .collect()
+}
+
 fn action_to_dto(a: &PlaybackAction) -> PlaybackActionDto {
     PlaybackActionDto {
         id: a.id.clone(),
@@ -140,6 +152,8 @@ pub async fn get_state_for_user(
                 })
                 .collect();
 
+            let settings = state.settings;
+            let queue_mode = state.queue_mode;
             let queues = state
                 .queues
                 .into_iter()
@@ -155,6 +169,8 @@ pub async fn get_state_for_user(
                             requested_by: t.request.discord_user_id.0.clone(),
                             volume: t.volume.into(),
                             paused: t.paused,
+                            duration_ms: t.duration_ms,
+                            position_ms: t.position_ms,
                         })
                         .collect();
                     (queue_name.to_string(), dtos)
@@ -169,6 +185,8 @@ pub async fn get_state_for_user(
                 channel_name,
                 queues,
                 queue_meta,
+                settings,
+                queue_mode,
             });
         }
 
@@ -250,6 +268,46 @@ pub async fn skip_music(
         Ok(action_to_dto(&action))
     }
 
+    pub async fn set_queue_mode(
+        &self,
+        dto: SetQueueModeDto,
+        actor_discord_user_id: &str,
+    ) -> CoreResult<PlaybackActionDto> {
+        let guild_id: u64 = dto
+            .guild_id
+            .parse()
+            .map_err(|_| CoreError::InvalidInput("invalid guild_id".into()))?;
+        let channel_id: u64 = dto
+            .channel_id
+            .parse()
+            .map_err(|_| CoreError::InvalidInput("invalid channel_id".into()))?;
+        let g = GuildId::from(guild_id);
+        let c = ChannelId::from(channel_id);
+
+        let previous = self.audio_engine.get_session_state(g, c).await?.queue_mode;
+        let mode = QueueMode {
+            repeat: dto.repeat.unwrap_or(previous.repeat),
+            shuffle: dto.shuffle.unwrap_or(previous.shuffle),
+        };
+
+        self.audio_engine.set_queue_mode(g, c, mode).await?;
+
+        // The snapshot holds the previous mode so the change can be undone.
+        let action = self
+            .repo
+            .create(&CreatePlaybackAction {
+                action_type: "queue_mode".to_string(),
+                guild_id: dto.guild_id.clone(),
+                channel_id: dto.channel_id.clone(),
+                actor_discord_user_id: actor_discord_user_id.to_string(),
+                track_snapshot: serde_json::to_value(previous)?,
+                queue_snapshot: None,
+            })
+            .await?;
+
+        Ok(action_to_dto(&action))
+    }
+
     pub async fn edit_queue(
         &self,
         dto: EditQueueDto,
@@ -272,6 +330,12 @@ pub async fn edit_queue(
         let queue_snapshot = serde_json::to_value(&state.queues)?;
 
         for op in &dto.operations {
+            if op.op == "play_next" || op.op == "insert_at" {
+                self.queue_new_track(g, c, op, actor_discord_user_id)
+                    .await?;
+                continue;
+            }
+
             let tid: u64 = op
                 .track_id
                 .parse()
@@ -289,6 +353,12 @@ pub async fn edit_queue(
                         .set_volume(g, c, track_id, Volume::from(vol))
                         .await?;
                 }
+                "move" => {
+                    let index = op.target_index.ok_or_else(|| {
+                        CoreError::InvalidInput("target_index required for move op".into())
+                    })?;
+                    self.audio_engine.move_track(g, c, track_id, index).await?;
+                }
                 other => {
                     return Err(CoreError::InvalidInput(format!("unknown op: {}", other)));
                 }
@@ -300,7 +370,11 @@ pub async fn edit_queue(
             .first()
             .map(|op| op.track_id.as_str())
             .unwrap_or("");
-        let track_snapshot = serde_json::json!({ "first_track_id": first_track_id });
+        // The operations are kept so undo knows which tracks the edit touched.
+        let track_snapshot = serde_json::json!({
+            "first_track_id": first_track_id,
+            "operations": dto.operations,
+        });
 
         let action = self
             .repo
@@ -357,10 +431,23 @@ pub async fn undo_action(
                     )
                     .await?;
             }
+            "queue_mode" => {
+                let previous: QueueMode = serde_json::from_value(action.track_snapshot.clone())?;
+                self.audio_engine
+                    .set_queue_mode(
+                        GuildId::from(guild_id),
+                        ChannelId::from(channel_id),
+                        previous,
+                    )
+                    .await?;
+            }
             "edit_queue" => {
-                return Err(CoreError::InvalidInput(
-                    "edit_queue undo is not supported".into(),
-                ));
+                self.undo_edit_queue(
+                    GuildId::from(guild_id),
+                    ChannelId::from(channel_id),
+                    &action,
+                )
+                .await?;
             }
             other => {
                 return Err(CoreError::InvalidInput(format!(
@@ -378,6 +465,156 @@ pub async fn undo_action(
         Ok(action_to_dto(&updated))
     }
 
+    async fn queue_new_track(
+        &self,
+        g: GuildId,
+        c: ChannelId,
+        op: &QueueOperation,
+        actor_discord_user_id: &str,
+    ) -> CoreResult<()> {
+        let tap_id = op
+            .tap_id
+            .clone()
+            .ok_or_else(|| CoreError::InvalidInput(format!("tap_id required for {} op", op.op)))?;
+        let audio_request = op.audio_request.clone().ok_or_else(|| {
+            CoreError::InvalidInput(format!("audio_request required for {} op", op.op))
+        })?;
+        let queue_name = QueueName::from(op.queue_name.clone().unwrap_or_else(|| "music".into()));
+        let volume = Volume::from(op.volume.unwrap_or(1.0));
+        let user = DiscordUserId::from(actor_discord_user_id.to_string());
+
+        if op.op == "play_next" {
+            return self
+                .audio_engine
+                .play_next(
+                    g,
+                    c,
+                    queue_name,
+                    TapId(tap_id),
+                    AudioRequestString(audio_request),
+                    volume,
+                    user,
+                    TrackDsp::default(),
+                )
+                .await;
+        }
+        let index = op.target_index.ok_or_else(|| {
+            CoreError::InvalidInput("target_index required for insert_at op".into())
+        })?;
+        self.audio_engine
+            .insert_at(
+                g,
+                c,
+                index,
+                queue_name,
+                TapId(tap_id),
+                AudioRequestString(audio_request),
+                volume,
+                user,
+                TrackDsp::default(),
+            )
+            .await
+    }
+
+    /// Bring the queues back to their state before an edit: queued tracks
+    /// are stopped, removed ones queued again, moved ones put back and
+    /// volumes restored. Tracks the edit did not touch are left alone.
+    async fn undo_edit_queue(
+        &self,
+        g: GuildId,
+        c: ChannelId,
+        action: &PlaybackAction,
+    ) -> CoreResult<()> {
+        let snapshot: HashMap<QueueName, Vec<Track>> = serde_json::from_value(
+            action
+                .queue_snapshot
+                .clone()
+                .ok_or_else(|| CoreError::Internal("edit_queue without queue snapshot".into()))?,
+        )?;
+        // Actions recorded before operations were kept have none to undo.
+        let operations: Vec<QueueOperation> = action
+            .track_snapshot
+            .get("operations")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        let removed = op_track_ids(&operations, "remove");
+        let moved = op_track_ids(&operations, "move");
+        let revolumed = op_track_ids(&operations, "set_volume");
+
+        let state = self.audio_engine.get_session_state(g, c).await?;
+        let before: HashSet<TrackId> = snapshot.values().flatten().map(|t| t.track_id).collect();
+
+        let mut inserted: Vec<&Track> = state
+            .queues
+            .values()
+            .flatten()
+            .filter(|t| !before.contains(&t.track_id))
+            .collect();
+        for op in operations
+            .iter()
+            .filter(|op| op.op == "play_next" || op.op == "insert_at")
+        {
+            let matches = |t: &&Track| {
+                op.tap_id.as_deref() == Some(t.request.tap_id.0.as_str())
+                    && op.audio_request.as_deref() == Some(t.request.audio_request.0.as_str())
+            };
+            if let Some(pos) = inserted.iter().position(matches) {
+                let track = inserted.remove(pos);
+                self.audio_engine.stop(g, c, track.track_id).await?;
+            }
+        }
+
+        // Going front to back keeps earlier tracks in place while later ones
+        // are put back.
+        for tracks in snapshot.values() {
+            for (index, track) in tracks.iter().enumerate() {
+                let id = track.track_id.to_string();
+                let present = state.find_track(track.track_id).is_some();
+                if removed.contains(id.as_str()) && !present {
+                    self.audio_engine
+                        .insert_at(
+                            g,
+                            c,
+                            index,
+                            track.queue_name.clone(),
+                            track.request.tap_id.clone(),
+                            track.request.audio_request.clone(),
+                            track.volume,
+                            track.request.discord_user_id.clone(),
+                            track.dsp,
+                        )
+                        .await?;
+                } else if moved.contains(id.as_str()) && present {
+                    self.audio_engine
+                        .move_track(g, c, track.track_id, index)
+                        .await?;
+                }
+                if revolumed.contains(id.as_str()) && present {
+                    self.audio_engine
+                        .set_volume(g, c, track.track_id, track.volume)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the user is in a voice channel of the guild, the same scope
+    /// the state and history endpoints use.
+    pub async fn is_in_guild(&self, discord_user_id: &str, guild_id: &str) -> CoreResult<bool> {
+        let locations = self
+            .voice_state
+            .get_user_channels(discord_user_id)
+            .await
+            .map_err(CoreError::StateError)?;
+        Ok(locations
+            .iter()
+            .any(|loc| loc.guild_id.to_string() == guild_id))
+    }
+
     pub async fn get_history(
         &self,
         discord_user_id: &str,
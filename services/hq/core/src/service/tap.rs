@@ -369,6 +369,7 @@ pub async fn get_tap_stats(&self, tap_id: TapId, user_id: UserId) -> CoreResult<
             uptime_percent,
             use_rate_history,
             cache_hit_rate_history,
+            connections: online_states.iter().map(Into::into).collect(),
         })
     }
 
@@ -653,6 +654,7 @@ async fn map_to_tap_dto(&self, tap: Tap) -> TapDto {
                 uptime_percent,
                 use_rate_history: vec![],
                 cache_hit_rate_history: vec![],
+                connections: online_states.iter().map(Into::into).collect(),
             },
         }
     }
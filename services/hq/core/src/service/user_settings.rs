@@ -1,10 +1,12 @@
 use std::sync::Arc;
 
+use hq_types::GuildId;
 use hq_types::hq::UserId;
 use hq_types::hq::settings::{PartialUserSettings, UserSettings, UserSettingsField};
 use hq_types::hq::tap::TapPermission;
 use zako3_states::UserSettingsStateService;
 
+use crate::service::AudioEngineService;
 use crate::repo::{
     GlobalSettingsRepository, GuildSettingsRepository, TapRepository, UserGuildSettingsRepository,
     UserRepository,
@@ -19,6 +21,7 @@ pub struct UserSettingsService {
     user_guild_settings_repo: Arc<dyn UserGuildSettingsRepository>,
     global_settings_repo: Arc<dyn GlobalSettingsRepository>,
     cache: UserSettingsStateService,
+    audio_engine: AudioEngineService,
 }
 
 impl UserSettingsService {
@@ -29,6 +32,7 @@ pub fn new(
         user_guild_settings_repo: Arc<dyn UserGuildSettingsRepository>,
         global_settings_repo: Arc<dyn GlobalSettingsRepository>,
         cache: UserSettingsStateService,
+        audio_engine: AudioEngineService,
     ) -> Self {
         Self {
             user_repo,
@@ -37,6 +41,7 @@ pub fn new(
             user_guild_settings_repo,
             global_settings_repo,
             cache,
+            audio_engine,
         }
     }
 
@@ -155,9 +160,32 @@ pub async fn save_guild_settings(
 
         let saved = self.guild_settings_repo.upsert(guild_id, &settings).await?;
         self.cache.invalidate_guild(guild_id).await;
+
+        if let Ok(gid) = guild_id.parse::<u64>()
+            && let Err(e) = self.sync_session_settings(GuildId::from(gid)).await
+        {
+            tracing::warn!(guild_id, error = %e, "Failed to push audio settings to active sessions");
+        }
+
         Ok(saved)
     }
 
+    /// Push the guild's resolved audio settings to every active AE session in
+    /// that guild. Global-scope changes are picked up on the next join.
+    pub async fn sync_session_settings(&self, guild_id: GuildId) -> CoreResult<()> {
+        let settings = self
+            .get_effective_settings(&None, Some(&guild_id.to_string()))
+            .await?
+            .session_audio_settings();
+
+        for session in self.audio_engine.get_sessions_in_guild(guild_id).await? {
+            self.audio_engine
+                .set_session_settings(session.guild_id, session.channel_id, settings.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
     // --- Global scope ---
 
     pub async fn get_global_settings(&self) -> CoreResult<Option<PartialUserSettings>> {
@@ -0,0 +1,403 @@
+//! Drives `PlaybackService` and `AudioEngineService` against an in-process
+//! Traffic Light that keeps session state in memory and records every
+//! session command it receives.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use hq_core::CoreResult;
+use hq_core::repo::{CreatePlaybackAction, PlaybackAction, PlaybackActionRepo};
+use hq_core::service::{AudioEngineService, PlaybackService, make_resolver_slot};
+use hq_types::hq::playback::{EditQueueDto, QueueOperation};
+use hq_types::hq::{DiscordUserId, TapId};
+use hq_types::{
+    AudioCachePolicy, AudioCacheType, AudioRequestString, CachedAudioRequest, ChannelId, GuildId,
+    QueueName, SessionEvent, SessionState, Track, TrackDsp, TrackId, Volume,
+};
+use jsonrpsee::core::{RpcResult, async_trait};
+use jsonrpsee::server::Server;
+use tl_protocol::{
+    AudioEngineCommand, AudioEngineCommandRequest, AudioEngineCommandResponse, AudioEngineError,
+    AudioEngineSessionCommand, TrafficLightRpcServer,
+};
+use tokio::sync::broadcast;
+use zako3_states::{CacheRepository, VoiceStateService};
+use zako3_tl_client::TlClient;
+
+const GUILD: u64 = 1;
+
+#[derive(Default)]
+struct FakeTlState {
+    sessions: HashMap<ChannelId, SessionState>,
+    /// Sessions whose `Play` commands are answered with an error.
+    failing: HashSet<ChannelId>,
+    commands: Vec<(ChannelId, AudioEngineSessionCommand)>,
+}
+
+#[derive(Clone, Default)]
+struct FakeTl(Arc<Mutex<FakeTlState>>);
+
+impl FakeTl {
+    fn commands(&self) -> Vec<(ChannelId, AudioEngineSessionCommand)> {
+        self.0.lock().unwrap().commands.clone()
+    }
+}
+
+#[async_trait]
+impl TrafficLightRpcServer for FakeTl {
+    async fn execute(
+        &self,
+        request: AudioEngineCommandRequest,
+    ) -> RpcResult<AudioEngineCommandResponse> {
+        let (Some(session), AudioEngineCommand::SessionCommand(command)) =
+            (request.session, request.command)
+        else {
+            return Ok(AudioEngineCommandResponse::Ok);
+        };
+        let channel_id = session.channel_id;
+        let mut state = self.0.lock().unwrap();
+        state.commands.push((channel_id, command.clone()));
+        let failing = state.failing.contains(&channel_id);
+        let Some(session) = state.sessions.get_mut(&channel_id) else {
+            return Ok(AudioEngineCommandResponse::Error(
+                AudioEngineError::NotJoined,
+            ));
+        };
+        Ok(match command {
+            AudioEngineSessionCommand::GetSessionState => {
+                AudioEngineCommandResponse::SessionState(session.clone())
+            }
+            AudioEngineSessionCommand::Stop(track_id) => {
+                for tracks in session.queues.values_mut() {
+                    tracks.retain(|t| t.track_id != track_id);
+                }
+                AudioEngineCommandResponse::Ok
+            }
+            AudioEngineSessionCommand::Play(_) if failing => AudioEngineCommandResponse::Error(
+                AudioEngineError::InternalError("session is gone".into()),
+            ),
+            _ => AudioEngineCommandResponse::Ok,
+        })
+    }
+
+    async fn get_sessions_in_guild(&self, guild_id: GuildId) -> RpcResult<Vec<SessionState>> {
+        let state = self.0.lock().unwrap();
+        Ok(state
+            .sessions
+            .values()
+            .filter(|s| s.guild_id == guild_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_bot_ids(&self) -> RpcResult<Vec<String>> {
+        Ok(vec![])
+    }
+
+    async fn report_guilds(&self, _token: String, _guilds: Vec<GuildId>) -> RpcResult<()> {
+        Ok(())
+    }
+
+    async fn register_ae(&self, _listen_addr: String) -> RpcResult<String> {
+        Ok(String::new())
+    }
+
+    async fn heartbeat_ae(&self, _token: String, _listen_addr: String) -> RpcResult<()> {
+        Ok(())
+    }
+
+    async fn publish_session_events(
+        &self,
+        _token: String,
+        _events: Vec<SessionEvent>,
+    ) -> RpcResult<()> {
+        Ok(())
+    }
+}
+
+async fn spawn_tl(tl: FakeTl) -> AudioEngineService {
+    let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = server.start(tl.into_rpc());
+    // Runs for the rest of the test process.
+    std::mem::forget(handle);
+
+    let client = TlClient::connect(&format!("http://{addr}")).await.unwrap();
+    let (event_tx, _) = broadcast::channel(16);
+    AudioEngineService::new(Arc::new(client), event_tx)
+}
+
+fn session(channel_id: u64, queues: HashMap<QueueName, Vec<Track>>) -> SessionState {
+    SessionState {
+        guild_id: GuildId::from(GUILD),
+        channel_id: ChannelId::from(channel_id),
+        queues,
+        settings: Default::default(),
+        queue_mode: Default::default(),
+    }
+}
+
+fn track(track_id: u64, dsp: TrackDsp) -> Track {
+    Track {
+        track_id: TrackId::from(track_id),
+        metadatas: vec![],
+        request: CachedAudioRequest {
+            tap_id: TapId("tap".into()),
+            audio_request: AudioRequestString(format!("song-{track_id}")),
+            cache_key: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            discord_user_id: DiscordUserId("user".into()),
+            headers: HashMap::new(),
+            start_offset_ms: 0,
+        },
+        volume: Volume::from(1.0),
+        queue_name: QueueName::from("music".to_string()),
+        paused: false,
+        dsp,
+        pan: None,
+        duration_ms: None,
+        position_ms: 0,
+    }
+}
+
+#[derive(Default)]
+struct MemoryActionRepo {
+    actions: Mutex<Vec<PlaybackAction>>,
+}
+
+#[async_trait]
+impl PlaybackActionRepo for MemoryActionRepo {
+    async fn create(&self, dto: &CreatePlaybackAction) -> CoreResult<PlaybackAction> {
+        let mut actions = self.actions.lock().unwrap();
+        let action = PlaybackAction {
+            id: actions.len().to_string(),
+            action_type: dto.action_type.clone(),
+            guild_id: dto.guild_id.clone(),
+            channel_id: dto.channel_id.clone(),
+            actor_discord_user_id: dto.actor_discord_user_id.clone(),
+            track_snapshot: dto.track_snapshot.clone(),
+            queue_snapshot: dto.queue_snapshot.clone(),
+            created_at: Utc::now(),
+            undone_at: None,
+            undone_by_discord_user_id: None,
+        };
+        actions.push(action.clone());
+        Ok(action)
+    }
+
+    async fn find_by_id(&self, id: &str) -> CoreResult<Option<PlaybackAction>> {
+        let actions = self.actions.lock().unwrap();
+        Ok(actions.iter().find(|a| a.id == id).cloned())
+    }
+
+    async fn find_by_guild_ids(
+        &self,
+        _guild_ids: &[String],
+        _limit: i64,
+    ) -> CoreResult<Vec<PlaybackAction>> {
+        Ok(self.actions.lock().unwrap().clone())
+    }
+
+    async fn mark_undone(
+        &self,
+        id: &str,
+        undone_by: &str,
+        undone_at: DateTime<Utc>,
+    ) -> CoreResult<PlaybackAction> {
+        let mut actions = self.actions.lock().unwrap();
+        let action = actions.iter_mut().find(|a| a.id == id).unwrap();
+        action.undone_at = Some(undone_at);
+        action.undone_by_discord_user_id = Some(undone_by.to_string());
+        Ok(action.clone())
+    }
+}
+
+/// Plain keys only; enough for the voice state.
+#[derive(Default)]
+struct MemoryCache(Mutex<HashMap<String, String>>);
+
+#[async_trait]
+impl CacheRepository for MemoryCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+    async fn set(&self, key: &str, value: &str) {
+        self.0.lock().unwrap().insert(key.into(), value.into());
+    }
+    async fn set_ex(&self, key: &str, value: &str, _ttl_secs: u64) {
+        self.set(key, value).await;
+    }
+    async fn del(&self, key: &str) {
+        self.0.lock().unwrap().remove(key);
+    }
+    async fn incr(&self, _key: &str) -> zako3_states::Result<i64> {
+        Ok(0)
+    }
+    async fn decr(&self, _key: &str) -> zako3_states::Result<i64> {
+        Ok(0)
+    }
+    async fn incrby(&self, _key: &str, _amount: i64) -> zako3_states::Result<i64> {
+        Ok(0)
+    }
+    async fn pfadd(&self, _key: &str, _element: &str) -> zako3_states::Result<()> {
+        Ok(())
+    }
+    async fn pfcount(&self, _key: &str) -> zako3_states::Result<u64> {
+        Ok(0)
+    }
+    async fn pfcount_multi(&self, _keys: &[String]) -> zako3_states::Result<u64> {
+        Ok(0)
+    }
+    async fn sadd(&self, _key: &str, _member: &str) -> zako3_states::Result<()> {
+        Ok(())
+    }
+    async fn smembers(&self, _key: &str) -> zako3_states::Result<Vec<String>> {
+        Ok(vec![])
+    }
+    async fn hgetall(&self, _key: &str) -> zako3_states::Result<Vec<(String, String)>> {
+        Ok(vec![])
+    }
+    async fn hincrby(&self, _key: &str, _field: &str, _amount: i64) -> zako3_states::Result<i64> {
+        Ok(0)
+    }
+    async fn hdel_key(&self, _key: &str) -> zako3_states::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn undoing_a_remove_restores_the_track_dsp() {
+    let dsp = TrackDsp {
+        speed: 1.25,
+        pitch: 3.0,
+        tempo: 0.9,
+    };
+    let tl = FakeTl::default();
+    tl.0.lock().unwrap().sessions.insert(
+        ChannelId::from(10),
+        session(
+            10,
+            HashMap::from([(
+                QueueName::from("music".to_string()),
+                vec![track(1, TrackDsp::default()), track(2, dsp)],
+            )]),
+        ),
+    );
+    let audio_engine = spawn_tl(tl.clone()).await;
+    let playback = PlaybackService::new(
+        audio_engine,
+        VoiceStateService::new(Arc::new(MemoryCache::default())),
+        Arc::new(MemoryActionRepo::default()),
+        make_resolver_slot(),
+    );
+
+    let action = playback
+        .edit_queue(
+            EditQueueDto {
+                guild_id: GUILD.to_string(),
+                channel_id: "10".into(),
+                operations: vec![QueueOperation {
+                    op: "remove".into(),
+                    track_id: "2".into(),
+                    target_index: None,
+                    volume: None,
+                    queue_name: None,
+                    tap_id: None,
+                    audio_request: None,
+                }],
+            },
+            "user",
+        )
+        .await
+        .unwrap();
+    playback.undo_action(&action.id, "user").await.unwrap();
+
+    let restored = tl
+        .commands()
+        .into_iter()
+        .find_map(|(_, command)| match command {
+            AudioEngineSessionCommand::InsertAt { request, index } => Some((request, index)),
+            _ => None,
+        })
+        .expect("undo re-inserts the removed track");
+    assert_eq!(restored.1, 1);
+    assert_eq!(restored.0.ars.0, "song-2");
+    assert_eq!(restored.0.dsp, dsp);
+}
+
+#[tokio::test]
+async fn announcement_reaches_every_session_despite_a_failing_one() {
+    let tl = FakeTl::default();
+    {
+        let mut state = tl.0.lock().unwrap();
+        for channel_id in [10, 20, 30] {
+            state.sessions.insert(
+                ChannelId::from(channel_id),
+                session(channel_id, HashMap::new()),
+            );
+        }
+        state.failing.insert(ChannelId::from(20));
+    }
+    let audio_engine = spawn_tl(tl.clone()).await;
+
+    let delivery = audio_engine
+        .announce(
+            GuildId::from(GUILD),
+            TapId("tap".into()),
+            AudioRequestString("maintenance in 5 minutes".into()),
+            DiscordUserId("admin".into()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(delivery.delivered, 2);
+    assert_eq!(delivery.failures.len(), 1);
+    assert_eq!(delivery.failures[0].0, ChannelId::from(20));
+    let mut played: Vec<ChannelId> = tl
+        .commands()
+        .into_iter()
+        .filter(|(_, command)| matches!(command, AudioEngineSessionCommand::Play(_)))
+        .map(|(channel_id, _)| channel_id)
+        .collect();
+    played.sort_by_key(|c| u64::from(*c));
+    assert_eq!(
+        played,
+        vec![
+            ChannelId::from(10),
+            ChannelId::from(20),
+            ChannelId::from(30)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn users_are_only_in_guilds_they_are_in_a_channel_of() {
+    let voice_state = VoiceStateService::new(Arc::new(MemoryCache::default()));
+    voice_state
+        .set_user_channel("user", GUILD, 10, "guild".into(), "channel".into())
+        .await
+        .unwrap();
+    let playback = PlaybackService::new(
+        spawn_tl(FakeTl::default()).await,
+        voice_state,
+        Arc::new(MemoryActionRepo::default()),
+        make_resolver_slot(),
+    );
+
+    assert!(
+        playback
+            .is_in_guild("user", &GUILD.to_string())
+            .await
+            .unwrap()
+    );
+    assert!(!playback.is_in_guild("user", "2").await.unwrap());
+    assert!(
+        !playback
+            .is_in_guild("other", &GUILD.to_string())
+            .await
+            .unwrap()
+    );
+}
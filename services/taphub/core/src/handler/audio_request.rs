@@ -5,17 +5,29 @@
 use opentelemetry::{KeyValue, global};
 use sha2::Digest;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use zako3_preload_cache::NextFrame;
-use zako3_types::{AudioMetaResponse, CachedAudioRequest, TapHubError};
+use zako3_types::{AudioMetaResponse, CachedAudioRequest, TapHubError, hq::TapId};
 use zako3_taphub_transport_server::Timestamp;
 use zakofish_taphub::ZakofishError;
+use zakofish_taphub::hub::ZakofishHub;
 
 use crate::hub::TapHub;
 use crate::metrics;
+use crate::routing::RequestKind;
 
-use super::{cache::build_cache_item, cache::resolve_metadata, stream::bridge_rel};
+use super::{
+    cache::build_cache_item,
+    cache::resolve_metadata,
+    coalesce::Joined,
+    failover::{FailoverError, with_failover},
+    stream::bridge_rel,
+};
+
+/// Cached and streamed audio is framed in 20 ms Opus packets.
+pub(super) const FRAME_MS: u64 = 20;
 
 pub(crate) async fn handle_request_audio_inner(
     tap_hub: &TapHub,
@@ -56,11 +68,7 @@ pub(crate) async fn handle_request_audio_inner(
         }
     };
 
-    let (tap_result, conn_result) = tokio::join!(
-        super::tap_lookup::resolve_tap(tap_hub, &tap_id),
-        tap_hub.select_connection(&tap_id),
-    );
-    let tap = tap_result?;
+    let tap = super::tap_lookup::resolve_tap(tap_hub, &tap_id).await?;
 
     super::permission::verify_permission(tap_hub, &tap, &request.discord_user_id).await?;
 
@@ -87,12 +95,19 @@ pub(crate) async fn handle_request_audio_inner(
             tracing::Span::current().record("cache_hit", true);
             tracing::info!(tap_id = %tap_id.0, cache_key = %item.key, cache_hit = true, "Cache hit");
 
+            let skip_frames = request.start_offset_ms / FRAME_MS;
             let (tx, rx) = mpsc::channel(100);
             tokio::spawn(async move {
                 let mut reader = reader;
-                let mut frame_count = 0u64;
+                let mut frame_count = match reader.skip_frames(skip_frames).await {
+                    Ok(skipped) => skipped,
+                    Err(e) => {
+                        tracing::warn!(%e, "Failed to seek cached audio");
+                        return;
+                    }
+                };
                 while let Ok(NextFrame::Frame(bytes)) = reader.next_frame().await {
-                    let ts = Timestamp(frame_count * 20);
+                    let ts = Timestamp(frame_count * FRAME_MS);
                     frame_count += 1;
                     if tx.send((ts, bytes)).await.is_err() {
                         break;
@@ -103,34 +118,14 @@ pub(crate) async fn handle_request_audio_inner(
                 metadatas: entry.metadatas,
                 cache_key: entry.cache_key,
                 base_volume: tap.base_volume,
+                loudness_lufs: entry.loudness_lufs,
+                duration_ms: entry.duration_ms,
             };
 
             let duration = start.elapsed().as_secs_f64();
             metrics::record_audio_request(&tap_id.0.to_string(), true, duration, true);
 
-            {
-                let entry = zako3_types::hq::history::UseHistoryEntry::PlayAudio(
-                    zako3_types::hq::history::PlayAudioHistory {
-                        user_id: None,
-                        discord_user_id: Some(request.discord_user_id.clone()),
-                        ars_length: ars.len(),
-                        trace_id: trace_id.clone(),
-                        tap_id: tap_id.clone(),
-                        cache_hit: true,
-                        success: true,
-                    },
-                );
-                let pubsub = tap_hub.history_pubsub.clone();
-                let metrics = tap_hub.metrics_service.clone();
-                let metrics_tap_id = tap_id.clone();
-                tokio::spawn(async move {
-                    let _ = metrics.incr_delta_total_uses(&metrics_tap_id).await;
-                    let _ = metrics.incr_delta_cache_hits(&metrics_tap_id).await;
-                    if let Err(e) = pubsub.publish_history(&entry).await {
-                        tracing::warn!(%e, "Failed to publish history (cache hit)");
-                    }
-                });
-            }
+            publish_history(tap_hub, &request, ars.len(), trace_id, true);
 
             return Ok((meta, rx));
         } else {
@@ -143,6 +138,35 @@ pub(crate) async fn handle_request_audio_inner(
     }
 
     tracing::Span::current().record("cache_hit", false);
+
+    // An identical request already being fetched shares its tap stream. If
+    // that fetch fails, or no longer holds the frames this request starts
+    // at, this request makes its own.
+    let mut leader = None;
+    if let Some(ref item) = cache_item {
+        match tap_hub.inflight.join(tap_id.clone(), item.key.clone()) {
+            Joined::Leader(l) => leader = Some(l),
+            Joined::Follower(follower) => {
+                let skip_frames = request.start_offset_ms / FRAME_MS;
+                if let Some((meta, rx)) = follower.stream(skip_frames).await {
+                    tracing::info!(
+                        tap_id = %tap_id.0,
+                        cache_key = %item.key,
+                        "Joined in-flight tap stream"
+                    );
+                    metrics::metrics()
+                        .coalesced_requests_total
+                        .add(1, &[KeyValue::new("tap_id", tap_id.0.to_string())]);
+                    let duration = start.elapsed().as_secs_f64();
+                    metrics::record_audio_request(&tap_id.0.to_string(), false, duration, true);
+                    publish_history(tap_hub, &request, ars.len(), trace_id, false);
+
+                    return Ok((meta, rx));
+                }
+            }
+        }
+    }
+
     tracing::info!(
         tap_id = %tap_id.0,
         cache_key = ?cache_item.as_ref().map(|i| &i.key),
@@ -150,54 +174,55 @@ pub(crate) async fn handle_request_audio_inner(
     );
 
     // Cache miss: request from zakofish
-    let (connection_id, disconnect_rx) = conn_result?;
-    tracing::Span::current().record("connection_id", connection_id);
-
-    let (succ, rel, mut unrel) = {
+    let request_id = tap_hub.zf_hub.next_request_id();
+    let served = with_failover(tap_hub, &tap_id, RequestKind::Audio, |connection_id| {
         let zakofish_span = tracing::info_span!(
             "zakofish.audio_request",
             tap_id = %tap_id.0,
             connection_id,
         );
-        let zf_result = tokio::time::timeout(
-            tap_hub.request_timeout,
-            tap_hub
-                .zf_hub
-                .request_audio(
-                    tap_id.clone(),
-                    connection_id,
-                    request.audio_request.clone(),
-                    request.headers.clone(),
-                )
-                .instrument(zakofish_span),
-        )
-        .await
-        .map_err(|_| {
-            TapHubError::Internal(format!(
-                "Tap request timed out after {:?}",
-                tap_hub.request_timeout
-            ))
-        })?;
-
-        match zf_result {
-            Ok(triple) => triple,
-            Err(ZakofishError::TapRequestFailure { reason, try_others }) => {
-                return Err(TapHubError::TapScript { reason, try_others });
-            }
-            Err(e) => {
-                return Err(TapHubError::Internal(format!(
-                    "Failed to request audio from tap: {}",
-                    e
-                )));
-            }
+        tap_hub
+            .zf_hub
+            .request_audio(
+                tap_id.clone(),
+                connection_id,
+                request_id,
+                request.audio_request.clone(),
+                request.headers.clone(),
+            )
+            .instrument(zakofish_span)
+    })
+    .await
+    .map_err(|e| match e {
+        FailoverError::Unavailable(e) => e,
+        FailoverError::TimedOut => TapHubError::Internal(format!(
+            "Tap request timed out after {:?}",
+            tap_hub.request_timeout
+        )),
+        FailoverError::Zakofish(ZakofishError::TapRequestFailure { reason, try_others }) => {
+            TapHubError::TapScript { reason, try_others }
         }
-    };
+        FailoverError::Zakofish(e) => {
+            TapHubError::Internal(format!("Failed to request audio from tap: {}", e))
+        }
+    })?;
+    let connection_id = served.connection_id;
+    let disconnect_rx = served.disconnect_rx;
+    let in_flight = served.in_flight;
+    let (succ, rel, mut unrel) = served.value;
+    tracing::Span::current().record("connection_id", connection_id);
+    let supports_cancel = tap_hub
+        .connections
+        .lock()
+        .get(&connection_id)
+        .is_some_and(|e| e.state.capabilities.supports_cancel);
 
     tracing::info!(tap_id = %tap_id.0, connection_id, "Received audio from Tap");
 
     // Bridge reliable stream (only present in Dual transfer mode; UnreliableOnly
     // skips caching since there is no authoritative copy to persist).
-    let rel_bridge = rel.map(|r| bridge_rel(r, disconnect_rx));
+    let cancel = CancellationToken::new();
+    let rel_bridge = rel.map(|r| bridge_rel(r, disconnect_rx, cancel.clone()));
 
     // Resolve metadata
     let metadatas = resolve_metadata(
@@ -208,7 +233,25 @@ pub(crate) async fn handle_request_audio_inner(
     )
     .await;
 
+    let meta = AudioMetaResponse {
+        metadatas: metadatas.clone(),
+        cache_key: succ.cache.clone(),
+        base_volume: tap.base_volume,
+        loudness_lufs: None,
+        duration_ms: succ.duration_secs.map(|secs| (secs * 1000.0) as u64),
+    };
+
+    let mut watchers = None;
     if let (Some(item), Some((rel_rx, done_rx))) = (cache_item, rel_bridge) {
+        // Requests that joined meanwhile are served from the reliable stream
+        // on its way to the cache.
+        let (rel_rx, done_rx) = match leader.take() {
+            Some(leader) => {
+                watchers = Some(leader.watchers());
+                leader.publish(meta.clone(), rel_rx, done_rx)
+            }
+            None => (rel_rx, done_rx),
+        };
         let cache = Arc::clone(&tap_hub.audio_cache);
         let metadatas_clone = metadatas.clone();
         let cache_key = succ.cache.clone();
@@ -259,49 +302,115 @@ pub(crate) async fn handle_request_audio_inner(
         }
     }
 
-    let meta = AudioMetaResponse {
-        metadatas,
-        cache_key: succ.cache,
-        base_volume: tap.base_volume,
-    };
-
     let duration = start.elapsed().as_secs_f64();
     metrics::record_audio_request(&tap_id.0.to_string(), false, duration, true);
 
+    // Taps cannot seek, so a start offset is served by dropping the leading
+    // frames; this still takes as long as the tap needs to produce them.
+    let mut skip_frames = request.start_offset_ms / FRAME_MS;
     let (tx, rx) = mpsc::channel(100);
+    let zf_hub = tap_hub.zf_hub.clone();
+    let tap_id_for_cancel = tap_id.clone();
     tokio::spawn(async move {
+        // The connection keeps a slot taken until the stream is done with.
+        let _in_flight = in_flight;
         // `unrel` (zakofish) yields zakofish's `Timestamp`; re-wrap it in the
         // transport's own `Timestamp` for the pf3 transfer.
-        while let Some((ts, bytes)) = unrel.recv().await {
-            if let Err(e) = tx.send((Timestamp(ts.0), bytes)).await {
-                tracing::warn!(%e, "Failed to send audio chunk to channel");
+        loop {
+            let chunk = tokio::select! {
+                _ = tx.closed() => break,
+                chunk = unrel.recv() => chunk,
+            };
+            let Some((ts, bytes)) = chunk else {
                 return;
+            };
+            if skip_frames > 0 {
+                skip_frames -= 1;
+                continue;
             }
+            if tx.send((Timestamp(ts.0), bytes)).await.is_err() {
+                break;
+            }
+        }
+
+        // The AE dropped the transfer, e.g. the track was skipped. Keep the
+        // tap going only while requests that joined this stream still listen.
+        drop(unrel);
+        // A tap that cannot stop finishes the stream anyway; let it reach the
+        // cache.
+        if !supports_cancel {
+            return;
         }
+        if let Some(watchers) = watchers
+            && !watchers.abandoned().await
+        {
+            return;
+        }
+        cancel_tap_request(
+            &zf_hub,
+            &tap_id_for_cancel,
+            connection_id,
+            request_id,
+            &cancel,
+        )
+        .await;
     });
 
+    publish_history(tap_hub, &request, ars.len(), trace_id, false);
+
+    Ok((meta, rx))
+}
+
+/// Tell the tap to stop a stream nobody listens to any more. `cancel` fires
+/// first so that what arrived so far is not cached as the whole stream.
+async fn cancel_tap_request(
+    zf_hub: &ZakofishHub,
+    tap_id: &TapId,
+    connection_id: u64,
+    request_id: u64,
+    cancel: &CancellationToken,
+) {
+    cancel.cancel();
+    tracing::info!(tap_id = %tap_id.0, connection_id, request_id, "Cancelling tap stream");
+    metrics::metrics()
+        .cancelled_requests_total
+        .add(1, &[KeyValue::new("tap_id", tap_id.0.to_string())]);
+    if let Err(e) = zf_hub
+        .cancel_audio_request(tap_id.clone(), connection_id, request_id)
+        .await
     {
-        let entry = zako3_types::hq::history::UseHistoryEntry::PlayAudio(
-            zako3_types::hq::history::PlayAudioHistory {
-                user_id: None,
-                discord_user_id: Some(request.discord_user_id.clone()),
-                ars_length: ars.len(),
-                trace_id: trace_id.clone(),
-                tap_id: tap_id.clone(),
-                cache_hit: false,
-                success: true,
-            },
-        );
-        let pubsub = tap_hub.history_pubsub.clone();
-        let metrics = tap_hub.metrics_service.clone();
-        let metrics_tap_id = tap_id.clone();
-        tokio::spawn(async move {
-            let _ = metrics.incr_delta_total_uses(&metrics_tap_id).await;
-            if let Err(e) = pubsub.publish_history(&entry).await {
-                tracing::warn!(%e, "Failed to publish history (cache miss)");
-            }
-        });
+        tracing::warn!(%e, tap_id = %tap_id.0, connection_id, "Failed to send cancel to Tap");
     }
+}
 
-    Ok((meta, rx))
+fn publish_history(
+    tap_hub: &TapHub,
+    request: &CachedAudioRequest,
+    ars_length: usize,
+    trace_id: Option<String>,
+    cache_hit: bool,
+) {
+    let entry = zako3_types::hq::history::UseHistoryEntry::PlayAudio(
+        zako3_types::hq::history::PlayAudioHistory {
+            user_id: None,
+            discord_user_id: Some(request.discord_user_id.clone()),
+            ars_length,
+            trace_id,
+            tap_id: request.tap_id.clone(),
+            cache_hit,
+            success: true,
+        },
+    );
+    let pubsub = tap_hub.history_pubsub.clone();
+    let metrics = tap_hub.metrics_service.clone();
+    let metrics_tap_id = request.tap_id.clone();
+    tokio::spawn(async move {
+        let _ = metrics.incr_delta_total_uses(&metrics_tap_id).await;
+        if cache_hit {
+            let _ = metrics.incr_delta_cache_hits(&metrics_tap_id).await;
+        }
+        if let Err(e) = pubsub.publish_history(&entry).await {
+            tracing::warn!(%e, cache_hit, "Failed to publish history");
+        }
+    });
 }
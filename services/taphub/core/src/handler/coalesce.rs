@@ -0,0 +1,394 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use tokio::sync::{mpsc, oneshot, watch};
+use zako3_taphub_transport_server::Timestamp;
+use zako3_types::{AudioMetaResponse, cache::AudioCacheItemKey, hq::TapId};
+
+use super::audio_request::FRAME_MS;
+
+type StreamKey = (TapId, AudioCacheItemKey);
+
+/// Frames kept for followers, 5 minutes of audio. A follower further behind
+/// than this is cut off.
+const MAX_BACKLOG_FRAMES: usize = 5 * 60 * 1000 / FRAME_MS as usize;
+
+/// Tap streams being fetched right now, by what they will be cached under,
+/// so identical requests arriving meanwhile share one tap stream instead of
+/// opening their own.
+#[derive(Default)]
+pub(crate) struct InflightStreams {
+    streams: Mutex<HashMap<StreamKey, Arc<SharedStream>>>,
+}
+
+pub(crate) enum Joined {
+    /// Nobody is fetching this yet; the caller does, and publishes through
+    /// the returned handle.
+    Leader(Leader),
+    Follower(Follower),
+}
+
+impl InflightStreams {
+    pub(crate) fn join(self: &Arc<Self>, tap_id: TapId, key: AudioCacheItemKey) -> Joined {
+        let key = (tap_id, key);
+        let mut streams = self.streams.lock();
+        if let Some(stream) = streams.get(&key) {
+            // Subscribed under the lock, so `Watchers` never misses a
+            // follower that has joined.
+            return Joined::Follower(Follower {
+                state_rx: stream.state.subscribe(),
+            });
+        }
+        let stream = Arc::new(SharedStream::new());
+        streams.insert(key.clone(), Arc::clone(&stream));
+        Joined::Leader(Leader {
+            inflight: Arc::clone(self),
+            key,
+            stream,
+        })
+    }
+
+    /// Remove `stream` unless a follower is still subscribed to it.
+    fn retire_if_unwatched(&self, key: &StreamKey, stream: &Arc<SharedStream>) -> bool {
+        let mut streams = self.streams.lock();
+        if stream.state.receiver_count() > 0 {
+            return false;
+        }
+        if streams.get(key).is_some_and(|s| Arc::ptr_eq(s, stream)) {
+            streams.remove(key);
+        }
+        true
+    }
+}
+
+#[derive(Default)]
+struct StreamState {
+    /// Set once the leader's tap request has been answered; `None` inside
+    /// means it failed.
+    meta: Option<Option<AudioMetaResponse>>,
+    /// The latest reliable-stream frames, for requests joining later.
+    backlog: VecDeque<Bytes>,
+    /// Index in the stream of the first frame in `backlog`.
+    first: usize,
+    finished: bool,
+}
+
+impl StreamState {
+    fn push(&mut self, frame: Bytes) {
+        self.backlog.push_back(frame);
+        if self.backlog.len() > MAX_BACKLOG_FRAMES {
+            self.backlog.pop_front();
+            self.first += 1;
+        }
+    }
+
+    /// Frames from index `next` on received so far, or `None` if some of
+    /// them are no longer kept.
+    fn frames_from(&self, next: usize) -> Option<Vec<Bytes>> {
+        let skip = next.checked_sub(self.first)?;
+        Some(self.backlog.iter().skip(skip).cloned().collect())
+    }
+}
+
+/// One tap stream fanned out to the requests that joined it. The last
+/// [`MAX_BACKLOG_FRAMES`] frames are kept until the leader finishes, so a
+/// request joining mid-stream replays from the start while that is still
+/// within the cap.
+struct SharedStream {
+    state: watch::Sender<StreamState>,
+}
+
+impl SharedStream {
+    fn new() -> Self {
+        Self {
+            state: watch::Sender::new(StreamState::default()),
+        }
+    }
+}
+
+/// A request attached to another's tap stream.
+pub(crate) struct Follower {
+    state_rx: watch::Receiver<StreamState>,
+}
+
+impl Follower {
+    /// Wait for the leader's tap request, then stream frames from
+    /// `skip_frames` on: those received so far, then the rest as they
+    /// arrive. `None` if the leader failed or the first frame wanted is no
+    /// longer kept, in which case the follower should make its own request.
+    pub(crate) async fn stream(
+        mut self,
+        skip_frames: u64,
+    ) -> Option<(AudioMetaResponse, mpsc::Receiver<(Timestamp, Bytes)>)> {
+        let meta = {
+            let state = self.state_rx.wait_for(|s| s.meta.is_some()).await.ok()?;
+            state.frames_from(skip_frames as usize)?;
+            state.meta.clone().flatten()?
+        };
+
+        let (tx, rx) = mpsc::channel(100);
+        let mut state_rx = self.state_rx;
+        tokio::spawn(async move {
+            let mut next = skip_frames as usize;
+            loop {
+                let (frames, finished) = {
+                    let state = state_rx.borrow_and_update();
+                    let Some(frames) = state.frames_from(next) else {
+                        tracing::warn!(frame = next, "Fell too far behind the shared tap stream");
+                        return;
+                    };
+                    (frames, state.finished)
+                };
+                for bytes in frames {
+                    let ts = Timestamp(next as u64 * FRAME_MS);
+                    next += 1;
+                    if tx.send((ts, bytes)).await.is_err() {
+                        return;
+                    }
+                }
+                if finished {
+                    return;
+                }
+                tokio::select! {
+                    _ = tx.closed() => return,
+                    changed = state_rx.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Some((meta, rx))
+    }
+}
+
+/// The request fetching a shared stream. Dropped without publishing, e.g. on
+/// an early error return, it fails the stream so followers fetch their own.
+pub(crate) struct Leader {
+    inflight: Arc<InflightStreams>,
+    key: StreamKey,
+    stream: Arc<SharedStream>,
+}
+
+impl Leader {
+    pub(crate) fn watchers(&self) -> Watchers {
+        Watchers {
+            inflight: Arc::clone(&self.inflight),
+            key: self.key.clone(),
+            stream: Arc::clone(&self.stream),
+        }
+    }
+
+    /// Hand `meta` to the followers and tee the bridged reliable stream into
+    /// the shared backlog. Returns the same
+    /// stream for the cache writer; the entry is released once it has ended.
+    pub(crate) fn publish(
+        self,
+        meta: AudioMetaResponse,
+        mut rel_rx: mpsc::Receiver<Bytes>,
+        done_rx: oneshot::Receiver<()>,
+    ) -> (mpsc::Receiver<Bytes>, oneshot::Receiver<()>) {
+        self.stream.state.send_modify(|s| s.meta = Some(Some(meta)));
+
+        let (tx, rx) = mpsc::channel(100);
+        let (done_tx, cache_done_rx) = oneshot::channel();
+        let leader = self;
+        tokio::spawn(async move {
+            while let Some(chunk) = rel_rx.recv().await {
+                leader.stream.state.send_modify(|s| s.push(chunk.clone()));
+                // Followers still need the rest if the cache writer gave up.
+                let _ = tx.send(chunk).await;
+            }
+            if done_rx.await.is_ok() {
+                let _ = done_tx.send(());
+            }
+            drop(leader);
+        });
+        (rx, cache_done_rx)
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        {
+            let mut streams = self.inflight.streams.lock();
+            if streams
+                .get(&self.key)
+                .is_some_and(|s| Arc::ptr_eq(s, &self.stream))
+            {
+                streams.remove(&self.key);
+            }
+        }
+        let watched = self.stream.state.receiver_count() > 0;
+        self.stream.state.send_modify(|s| {
+            s.meta.get_or_insert(None);
+            s.finished = true;
+            // Followers still attached read the rest; nobody else can join.
+            if !watched {
+                s.first += s.backlog.len();
+                s.backlog = VecDeque::new();
+            }
+        });
+    }
+}
+
+/// The followers of a leader's stream, for telling when they have all gone.
+pub(crate) struct Watchers {
+    inflight: Arc<InflightStreams>,
+    key: StreamKey,
+    stream: Arc<SharedStream>,
+}
+
+impl Watchers {
+    /// Wait until no follower is listening any more. Returns `true` if that
+    /// happened while the stream was still running; from then on new
+    /// requests no longer join it. `false` if it simply ended.
+    pub(crate) async fn abandoned(self) -> bool {
+        loop {
+            self.stream.state.closed().await;
+            if self.stream.state.borrow().finished {
+                return false;
+            }
+            if self.inflight.retire_if_unwatched(&self.key, &self.stream) {
+                return true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zako3_types::{AudioCachePolicy, AudioCacheType};
+
+    use super::*;
+
+    fn key() -> (TapId, AudioCacheItemKey) {
+        (
+            TapId("tap".to_string()),
+            AudioCacheItemKey::ARHash("song".to_string()),
+        )
+    }
+
+    fn join(inflight: &Arc<InflightStreams>) -> Joined {
+        let (tap_id, key) = key();
+        inflight.join(tap_id, key)
+    }
+
+    fn meta() -> AudioMetaResponse {
+        AudioMetaResponse {
+            metadatas: vec![],
+            cache_key: AudioCachePolicy {
+                cache_type: AudioCacheType::None,
+                ttl_seconds: None,
+            },
+            base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
+        }
+    }
+
+    fn frame(i: usize) -> Bytes {
+        Bytes::from(vec![i as u8])
+    }
+
+    #[tokio::test]
+    async fn follower_joining_an_in_flight_stream_gets_what_the_leader_gets() {
+        let inflight = Arc::new(InflightStreams::default());
+        let Joined::Leader(leader) = join(&inflight) else {
+            panic!("first request should lead");
+        };
+        let Joined::Follower(follower) = join(&inflight) else {
+            panic!("second request should follow");
+        };
+
+        let (rel_tx, rel_rx) = mpsc::channel(10);
+        let (done_tx, done_rx) = oneshot::channel();
+        let (mut cache_rx, cache_done_rx) = leader.publish(meta(), rel_rx, done_rx);
+        let (_, mut follower_rx) = follower.stream(1).await.unwrap();
+
+        for i in 0..3 {
+            rel_tx.send(frame(i)).await.unwrap();
+        }
+        drop(rel_tx);
+        done_tx.send(()).unwrap();
+
+        for i in 0..3 {
+            assert_eq!(cache_rx.recv().await, Some(frame(i)));
+        }
+        assert!(cache_done_rx.await.is_ok());
+        // Skipping the first frame keeps the stream's timing.
+        for i in 1..3 {
+            let expected = (Timestamp(i as u64 * FRAME_MS), frame(i));
+            assert_eq!(follower_rx.recv().await, Some(expected));
+        }
+        assert_eq!(follower_rx.recv().await, None);
+        // The stream is over, so the next request fetches its own.
+        assert!(matches!(join(&inflight), Joined::Leader(_)));
+    }
+
+    #[tokio::test]
+    async fn follower_of_a_failed_leader_makes_its_own_request() {
+        let inflight = Arc::new(InflightStreams::default());
+        let Joined::Leader(leader) = join(&inflight) else {
+            panic!("first request should lead");
+        };
+        let Joined::Follower(follower) = join(&inflight) else {
+            panic!("second request should follow");
+        };
+
+        drop(leader);
+
+        assert!(follower.stream(0).await.is_none());
+        assert!(matches!(join(&inflight), Joined::Leader(_)));
+    }
+
+    #[tokio::test]
+    async fn follower_joining_mid_stream_replays_from_the_first_frame() {
+        let inflight = Arc::new(InflightStreams::default());
+        let Joined::Leader(leader) = join(&inflight) else {
+            panic!("first request should lead");
+        };
+
+        let (rel_tx, rel_rx) = mpsc::channel(10);
+        let (done_tx, done_rx) = oneshot::channel();
+        let (mut cache_rx, _) = leader.publish(meta(), rel_rx, done_rx);
+        for i in 0..3 {
+            rel_tx.send(frame(i)).await.unwrap();
+            assert_eq!(cache_rx.recv().await, Some(frame(i)));
+        }
+
+        let Joined::Follower(late) = join(&inflight) else {
+            panic!("the stream is still running");
+        };
+        let (_, mut late_rx) = late.stream(0).await.unwrap();
+        for i in 3..5 {
+            rel_tx.send(frame(i)).await.unwrap();
+        }
+        drop(rel_tx);
+        done_tx.send(()).unwrap();
+
+        for i in 0..5 {
+            let expected = (Timestamp(i as u64 * FRAME_MS), frame(i));
+            assert_eq!(late_rx.recv().await, Some(expected));
+        }
+        assert_eq!(late_rx.recv().await, None);
+    }
+
+    #[test]
+    fn backlog_drops_the_oldest_frames_past_its_cap() {
+        let mut state = StreamState::default();
+        for i in 0..MAX_BACKLOG_FRAMES + 2 {
+            state.push(frame(i));
+        }
+
+        assert_eq!(state.backlog.len(), MAX_BACKLOG_FRAMES);
+        assert!(state.frames_from(1).is_none());
+        assert_eq!(
+            state.frames_from(MAX_BACKLOG_FRAMES + 1),
+            Some(vec![frame(MAX_BACKLOG_FRAMES + 1)])
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+use opentelemetry::KeyValue;
+use parking_lot::Mutex;
+use tokio::sync::watch;
+use zako3_types::{TapHubError, hq::TapId};
+use zakofish_taphub::ZakofishError;
+
+use crate::hub::TapHub;
+use crate::metrics;
+use crate::routing::{DynamicSampler, RequestKind};
+
+/// Connections tried for one request, the first one included.
+const MAX_ATTEMPTS: usize = 3;
+
+pub(crate) struct Served<T> {
+    pub(crate) value: T,
+    pub(crate) connection_id: u64,
+    pub(crate) disconnect_rx: watch::Receiver<bool>,
+    /// Keeps the request counted against the connection. Hold on to it for
+    /// as long as the answer is still streaming.
+    pub(crate) in_flight: InFlight,
+}
+
+pub(crate) enum FailoverError {
+    /// No live connection of the tap was left to try.
+    Unavailable(TapHubError),
+    /// `request_timeout` ran out across all attempts.
+    TimedOut,
+    /// The last connection tried failed. A `TapRequestFailure` here either
+    /// did not ask for a retry or had nothing left to retry on.
+    Zakofish(ZakofishError),
+}
+
+/// Run `request` on a connection of `tap_id` able to serve `kind`, picked by
+/// the sampler. When the tap fails with `try_others`, try again on another
+/// live connection that has not failed yet, up to `MAX_ATTEMPTS` connections
+/// and within one `request_timeout` overall.
+pub(crate) async fn with_failover<T, F, Fut>(
+    tap_hub: &TapHub,
+    tap_id: &TapId,
+    kind: RequestKind,
+    mut request: F,
+) -> Result<Served<T>, FailoverError>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<T, ZakofishError>>,
+{
+    let deadline = tokio::time::Instant::now() + tap_hub.request_timeout;
+    let mut failed = HashSet::new();
+    let mut last_failure = None;
+
+    loop {
+        let selected = tap_hub.select_connection(tap_id, kind, &failed).await;
+        let (connection_id, disconnect_rx) = match selected {
+            Ok(selected) => selected,
+            // Out of connections: the tap's own reason beats "unavailable".
+            Err(e) => {
+                return Err(match last_failure {
+                    Some(failure) => FailoverError::Zakofish(failure),
+                    None => FailoverError::Unavailable(e),
+                });
+            }
+        };
+
+        let in_flight = InFlight::begin(&tap_hub.sampler, connection_id);
+        let result = tokio::time::timeout_at(deadline, request(connection_id)).await;
+        // A tap refusing the request outright still answered; only timeouts,
+        // transport errors and retriable failures count against the connection.
+        let served = matches!(
+            result,
+            Ok(Ok(_))
+                | Ok(Err(ZakofishError::TapRequestFailure {
+                    try_others: false,
+                    ..
+                }))
+        );
+        if in_flight.answered(served) {
+            tracing::warn!(
+                tap_id = %tap_id.0,
+                connection_id,
+                "Connection keeps failing, taking it out of rotation"
+            );
+            metrics::metrics()
+                .connection_ejections_total
+                .add(1, &[KeyValue::new("tap_id", tap_id.0.to_string())]);
+        }
+        let result = result.map_err(|_| FailoverError::TimedOut)?;
+
+        match result {
+            Ok(value) => {
+                return Ok(Served {
+                    value,
+                    connection_id,
+                    disconnect_rx,
+                    in_flight,
+                });
+            }
+            Err(
+                e @ ZakofishError::TapRequestFailure {
+                    try_others: true, ..
+                },
+            ) if failed.len() + 1 < MAX_ATTEMPTS => {
+                tracing::info!(
+                    tap_id = %tap_id.0,
+                    connection_id,
+                    error = %e,
+                    "Tap asked to try other connections"
+                );
+                metrics::metrics()
+                    .tap_failovers_total
+                    .add(1, &[KeyValue::new("tap_id", tap_id.0.to_string())]);
+                failed.insert(connection_id);
+                last_failure = Some(e);
+            }
+            Err(e) => return Err(FailoverError::Zakofish(e)),
+        }
+    }
+}
+
+/// Counts a request against its connection in the sampler until dropped.
+/// Dropped before it was answered, e.g. when the caller goes away, it only
+/// releases the slot.
+pub(crate) struct InFlight {
+    sampler: Arc<Mutex<DynamicSampler>>,
+    connection_id: u64,
+    started: Instant,
+}
+
+impl InFlight {
+    fn begin(sampler: &Arc<Mutex<DynamicSampler>>, connection_id: u64) -> Self {
+        sampler.lock().begin_request(connection_id);
+        Self {
+            sampler: Arc::clone(sampler),
+            connection_id,
+            started: Instant::now(),
+        }
+    }
+
+    /// The connection answered. Returns whether it was taken out of
+    /// rotation.
+    fn answered(&self, served: bool) -> bool {
+        self.sampler
+            .lock()
+            .record_answer(self.connection_id, self.started.elapsed(), served)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.sampler.lock().end_request(self.connection_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::tests::{connection, picks};
+
+    #[test]
+    fn answered_request_holds_its_slot_until_dropped() {
+        let sampler = Arc::new(Mutex::new(DynamicSampler::new()));
+        let states = vec![connection(1, Some(1)), connection(2, None)];
+
+        let in_flight = InFlight::begin(&sampler, 1);
+        assert!(!in_flight.answered(true));
+        // Still streaming.
+        assert!(
+            picks(&mut sampler.lock(), &states)
+                .iter()
+                .all(|&id| id == 2)
+        );
+
+        drop(in_flight);
+        assert!(picks(&mut sampler.lock(), &states).contains(&1));
+    }
+
+    #[test]
+    fn abandoned_request_only_releases_its_slot() {
+        let sampler = Arc::new(Mutex::new(DynamicSampler::new()));
+        let states = vec![connection(1, Some(1)), connection(2, None)];
+
+        for _ in 0..10 {
+            drop(InFlight::begin(&sampler, 1));
+        }
+        assert!(picks(&mut sampler.lock(), &states).contains(&1));
+    }
+}
@@ -0,0 +1,50 @@
+use std::io::ErrorKind;
+
+use zako3_types::{CachedAudioRequest, TapHubError};
+
+use crate::hub::TapHub;
+
+use super::cache::build_cache_item;
+
+pub(crate) async fn handle_store_loudness_inner(
+    tap_hub: &TapHub,
+    request: CachedAudioRequest,
+    loudness_lufs: f32,
+) -> Result<(), TapHubError> {
+    if !loudness_lufs.is_finite() {
+        return Err(TapHubError::Internal(format!(
+            "Invalid loudness value: {}",
+            loudness_lufs
+        )));
+    }
+
+    let Some(item) = build_cache_item(
+        request.tap_id.clone(),
+        &request.cache_key,
+        &request.audio_request,
+    ) else {
+        return Ok(());
+    };
+
+    match tap_hub
+        .audio_cache
+        .set_loudness(&item.tap_id, &item.key, loudness_lufs)
+        .await
+    {
+        Ok(()) => {
+            tracing::debug!(
+                tap_id = %item.tap_id.0,
+                key = %item.key,
+                loudness_lufs,
+                "Stored measured loudness"
+            );
+            Ok(())
+        }
+        // Not cached (yet, or evicted) — nothing to attach the measurement to.
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(TapHubError::Internal(format!(
+            "Failed to store loudness: {}",
+            e
+        ))),
+    }
+}
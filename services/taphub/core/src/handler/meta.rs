@@ -11,6 +11,9 @@
 };
 
 use crate::hub::TapHub;
+use crate::routing::RequestKind;
+
+use super::failover::{FailoverError, with_failover};
 
 pub(crate) async fn handle_request_audio_meta_inner(
     tap_hub: &TapHub,
@@ -37,6 +40,8 @@ pub(crate) async fn handle_request_audio_meta_inner(
             metadatas: entry.metadatas,
             cache_key: entry.cache_key,
             base_volume: tap.base_volume,
+            loudness_lufs: None,
+            duration_ms: entry.duration_ms,
         });
     }
 
@@ -49,29 +54,29 @@ enum FetchOutcome<M> {
         TapFailure { reason: String, try_others: bool },
     }
 
-    let outcome = 'fetch: {
-        let Ok((connection_id, _disconnect_rx)) = tap_hub.select_connection(&tap_id).await else {
-            break 'fetch FetchOutcome::ConnectionUnavailable;
-        };
-        match tap_hub
-            .zf_hub
-            .request_audio_metadata(
-                tap_id.clone(),
-                connection_id,
-                req.request.clone(),
-                req.headers.clone(),
-            )
-            .await
-        {
-            Ok(m) => FetchOutcome::Ok(m),
-            Err(ZakofishError::TapRequestFailure { reason, try_others }) => {
-                FetchOutcome::TapFailure { reason, try_others }
-            }
-            Err(e) => {
-                tracing::warn!(error = %e, "request_audio_metadata transport error; falling back");
-                FetchOutcome::ConnectionUnavailable
-            }
+    let fetched = with_failover(tap_hub, &tap_id, RequestKind::Metadata, |connection_id| {
+        tap_hub.zf_hub.request_audio_metadata(
+            tap_id.clone(),
+            connection_id,
+            req.request.clone(),
+            req.headers.clone(),
+        )
+    })
+    .await;
+    let outcome = match fetched {
+        Ok(served) => FetchOutcome::Ok(served.value),
+        Err(FailoverError::Zakofish(ZakofishError::TapRequestFailure { reason, try_others })) => {
+            FetchOutcome::TapFailure { reason, try_others }
+        }
+        Err(FailoverError::Zakofish(e)) => {
+            tracing::warn!(error = %e, "request_audio_metadata transport error; falling back");
+            FetchOutcome::ConnectionUnavailable
+        }
+        Err(FailoverError::TimedOut) => {
+            tracing::warn!("request_audio_metadata timed out; falling back");
+            FetchOutcome::ConnectionUnavailable
         }
+        Err(FailoverError::Unavailable(_)) => FetchOutcome::ConnectionUnavailable,
     };
 
     let meta = match outcome {
@@ -88,6 +93,8 @@ enum FetchOutcome<M> {
                     metadatas: entry.metadatas,
                     cache_key: entry.cache_key,
                     base_volume: tap.base_volume,
+                    loudness_lufs: None,
+                    duration_ms: entry.duration_ms,
                 });
             }
             return Err(TapHubError::TapUnavailable);
@@ -118,5 +125,7 @@ enum FetchOutcome<M> {
         metadatas: meta.metadatas,
         cache_key: meta.cache,
         base_volume: tap.base_volume,
+        loudness_lufs: None,
+        duration_ms: None,
     })
 }
@@ -9,7 +9,10 @@
 
 mod audio_request;
 mod cache;
+pub(crate) mod coalesce;
+mod failover;
 mod invalidate_cache;
+mod loudness;
 mod meta;
 mod permission;
 mod preload;
@@ -49,4 +52,13 @@ async fn handle_invalidate_cache(
     ) -> Result<(), TapHubError> {
         invalidate_cache::handle_invalidate_cache_inner(self, req).await
     }
+
+    async fn handle_store_loudness(
+        &self,
+        req: CachedAudioRequest,
+        loudness_lufs: f32,
+        _headers: HashMap<String, String>,
+    ) -> Result<(), TapHubError> {
+        loudness::handle_store_loudness_inner(self, req, loudness_lufs).await
+    }
 }
@@ -1,13 +1,20 @@
 use std::sync::Arc;
 
 use opentelemetry::global;
+use tokio_util::sync::CancellationToken;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use zako3_types::{AudioMetaResponse, CachedAudioRequest, TapHubError};
 use zakofish_taphub::ZakofishError;
 
 use crate::hub::TapHub;
+use crate::routing::RequestKind;
 
-use super::{cache::build_cache_item, cache::resolve_metadata, stream::bridge_rel};
+use super::{
+    cache::build_cache_item,
+    cache::resolve_metadata,
+    failover::{FailoverError, with_failover},
+    stream::bridge_rel,
+};
 
 pub(crate) async fn handle_preload_audio_inner(
     tap_hub: &TapHub,
@@ -20,11 +27,7 @@ pub(crate) async fn handle_preload_audio_inner(
 
     let tap_id = req.tap_id.clone();
 
-    let (tap_result, conn_result) = tokio::join!(
-        super::tap_lookup::resolve_tap(tap_hub, &tap_id),
-        tap_hub.select_connection(&tap_id),
-    );
-    let tap = tap_result?;
+    let tap = super::tap_lookup::resolve_tap(tap_hub, &tap_id).await?;
 
     super::permission::verify_permission(tap_hub, &tap, &req.discord_user_id).await?;
 
@@ -43,45 +46,44 @@ pub(crate) async fn handle_preload_audio_inner(
             metadatas: entry.metadatas,
             cache_key: entry.cache_key,
             base_volume: tap.base_volume,
+            loudness_lufs: entry.loudness_lufs,
+            duration_ms: entry.duration_ms,
         });
     }
 
-    // Connection selection
-    let (connection_id, disconnect_rx) = conn_result?;
-
     // Request audio from zakofish
-    let zf_result = tokio::time::timeout(
-        tap_hub.request_timeout,
+    let request_id = tap_hub.zf_hub.next_request_id();
+    let served = with_failover(tap_hub, &tap_id, RequestKind::Audio, |connection_id| {
         tap_hub.zf_hub.request_audio(
             tap_id.clone(),
             connection_id,
+            request_id,
             req.audio_request.clone(),
             req.headers.clone(),
-        ),
-    )
+        )
+    })
     .await
-    .map_err(|_| {
-        TapHubError::Internal(format!(
+    .map_err(|e| match e {
+        FailoverError::Unavailable(e) => e,
+        FailoverError::TimedOut => TapHubError::Internal(format!(
             "Tap preload timed out after {:?}",
             tap_hub.request_timeout
-        ))
-    })?;
-
-    let (succ, rel, unrel) = match zf_result {
-        Ok(triple) => triple,
-        Err(ZakofishError::TapRequestFailure { reason, try_others }) => {
-            return Err(TapHubError::TapScript { reason, try_others });
+        )),
+        FailoverError::Zakofish(ZakofishError::TapRequestFailure { reason, try_others }) => {
+            TapHubError::TapScript { reason, try_others }
         }
-        Err(e) => {
-            return Err(TapHubError::Internal(format!(
-                "Failed to request audio from tap: {}",
-                e
-            )));
+        FailoverError::Zakofish(e) => {
+            TapHubError::Internal(format!("Failed to request audio from tap: {}", e))
         }
-    };
+    })?;
+    let disconnect_rx = served.disconnect_rx;
+    let in_flight = served.in_flight;
+    let (succ, rel, unrel) = served.value;
 
     // consume unrel frames to avoid buildup (but don't wait for them)
     tokio::spawn(async move {
+        // The connection keeps a slot taken until the tap stops streaming.
+        let _in_flight = in_flight;
         let mut unrel = unrel;
         while let Some(_) = unrel.recv().await {
             tokio::task::yield_now().await;
@@ -102,7 +104,8 @@ pub(crate) async fn handle_preload_audio_inner(
     let rel = rel.ok_or_else(|| {
         TapHubError::Internal("Tap returned UnreliableOnly transfer for preload request".to_string())
     })?;
-    let (rel_rx, done_rx) = bridge_rel(rel, disconnect_rx);
+    // A preload has no listener that could go away, so it is never cancelled.
+    let (rel_rx, done_rx) = bridge_rel(rel, disconnect_rx, CancellationToken::new());
 
     if let Some(item) = cache_item {
         // Hand the stream to the cache server. The client opens a preload
@@ -137,5 +140,7 @@ pub(crate) async fn handle_preload_audio_inner(
         metadatas,
         cache_key: succ.cache,
         base_volume: tap.base_volume,
+        loudness_lufs: None,
+        duration_ms: succ.duration_secs.map(|secs| (secs * 1000.0) as u64),
     })
 }
@@ -1,5 +1,6 @@
 use bytes::Bytes;
 use tokio::sync::{mpsc, oneshot, watch};
+use tokio_util::sync::CancellationToken;
 use zakofish_taphub::RelChunkStream;
 
 use crate::metrics;
@@ -7,12 +8,13 @@
 /// Bridge a reliable stream to an `mpsc::Receiver<Bytes>`.
 /// Also returns a oneshot that fires `()` only when the stream ends naturally
 /// AND the Tap has not disconnected. If the Tap disconnects mid-stream
-/// (`disconnect_rx` becomes `true`) the task exits without firing `done_tx`,
-/// so `done_rx.await` returns `Err` — preventing partial audio from being
-/// committed to cache.
+/// (`disconnect_rx` becomes `true`) or the request is cancelled, the task
+/// exits without firing `done_tx`, so `done_rx.await` returns `Err` —
+/// preventing partial audio from being committed to cache.
 pub(crate) fn bridge_rel(
     mut rel: RelChunkStream,
     mut disconnect_rx: watch::Receiver<bool>,
+    cancel: CancellationToken,
 ) -> (mpsc::Receiver<Bytes>, oneshot::Receiver<()>) {
     let (tx, rx) = mpsc::channel(100);
     let (done_tx, done_rx) = oneshot::channel();
@@ -34,6 +36,10 @@ pub(crate) fn bridge_rel(
                     tracing::warn!("Tap disconnected mid-stream; aborting and discarding stream");
                     break 'outer;
                 }
+                _ = cancel.cancelled() => {
+                    tracing::debug!("Audio request cancelled; discarding stream");
+                    break 'outer;
+                }
                 chunk_opt = rel.recv() => {
                     match chunk_opt {
                         Some(chunk) => {
@@ -1,5 +1,8 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use opentelemetry::KeyValue;
+use parking_lot::Mutex;
 use tokio::sync::watch;
 use zako3_types::{OnlineTapState, hq::TapId};
 use zakofish_taphub::{
@@ -9,6 +12,7 @@
 
 use crate::app::App;
 use crate::metrics;
+use crate::routing::DynamicSampler;
 use zako3_metrics::TapRedisMetrics;
 use zako3_states::TapHubStateService;
 
@@ -19,6 +23,7 @@ pub struct TapHubConnectionHandler {
     pub(super) state_service: TapHubStateService,
     pub(super) metrics_service: TapRedisMetrics,
     pub(super) connections: ConnectionRegistry,
+    pub(super) sampler: Arc<Mutex<DynamicSampler>>,
 }
 
 impl TapHubConnectionHandler {
@@ -72,6 +77,8 @@ async fn on_tap_authenticate(
                 tracing::info!(
                     tap_id = %tap.id.0,
                     connection_id,
+                    protocol_version = hello.negotiated_protocol_version(),
+                    capabilities = ?hello.capabilities,
                     "Tap authenticated"
                 );
 
@@ -79,9 +86,11 @@ async fn on_tap_authenticate(
                     tap_id: tap.id.clone(),
                     tap_name: zako3_types::TapName(tap.name.0.clone()),
                     connection_id,
+                    protocol_version: hello.negotiated_protocol_version(),
                     friendly_name: hello.friendly_name,
                     selection_weight: hello.selection_weight,
                     connected_at: chrono::Utc::now(),
+                    capabilities: hello.capabilities,
                 };
 
                 let tap_id = tap.id.clone();
@@ -163,6 +172,7 @@ async fn on_tap_disconnected(&self, tap_id: TapId, connection_id: u64) {
             "Tap disconnected — erroring all active streams for this connection"
         );
         let _ = entry.disconnect_tx.send(true);
+        self.sampler.lock().forget(connection_id);
 
         let uptime_secs = (chrono::Utc::now() - entry.state.connected_at)
             .num_seconds()
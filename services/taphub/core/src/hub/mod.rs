@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::Path,
     sync::Arc,
     time::Duration,
@@ -12,7 +12,11 @@
 
 use zako3_preload_cache::AudioCache;
 
-use crate::{app::App, routing::DynamicSampler};
+use crate::{
+    app::App,
+    handler::coalesce::InflightStreams,
+    routing::{DynamicSampler, RequestKind},
+};
 use zako3_metrics::TapRedisMetrics;
 use zako3_states::{RedisPubSub, TapHubStateService};
 
@@ -43,6 +47,8 @@ pub struct TapHub {
     pub request_timeout: Duration,
     pub history_pubsub: Arc<RedisPubSub>,
     pub(crate) connections: ConnectionRegistry,
+    /// Audio requests currently being fetched from a tap, for coalescing.
+    pub(crate) inflight: Arc<InflightStreams>,
 }
 
 impl TapHub {
@@ -67,19 +73,21 @@ pub async fn new(
         )?;
 
         let connections: ConnectionRegistry = Arc::new(Mutex::new(HashMap::new()));
+        let sampler = Arc::new(Mutex::new(DynamicSampler::new()));
 
         let handler = TapHubConnectionHandler {
             app: app.clone(),
             state_service: app.tap_state_service.clone(),
             metrics_service: app.tap_metrics_service.clone(),
             connections: Arc::clone(&connections),
+            sampler: Arc::clone(&sampler),
         };
 
         let zf_hub = ZakofishHub::new(server_config, Arc::new(handler))?;
 
         Ok(Self {
             zf_hub,
-            sampler: Arc::new(Mutex::new(DynamicSampler::new())),
+            sampler,
             state_service: app.tap_state_service.clone(),
             metrics_service: app.tap_metrics_service.clone(),
             app,
@@ -87,6 +95,7 @@ pub async fn new(
             request_timeout: Duration::from_millis(request_timeout_ms),
             history_pubsub,
             connections,
+            inflight: Arc::default(),
         })
     }
 
@@ -135,15 +144,21 @@ async fn lease_heartbeat(&self) {
     /// connections — so a connection that has already been dropped is never
     /// handed out. The sampler picks a connection, then we subscribe to that
     /// connection's disconnect signal under the same logical view.
+    /// Connections in `exclude`, and those that did not announce what `kind`
+    /// needs, are skipped.
     pub(crate) async fn select_connection(
         &self,
         tap_id: &TapId,
+        kind: RequestKind,
+        exclude: &HashSet<u64>,
     ) -> Result<(u64, watch::Receiver<bool>), zako3_types::TapHubError> {
         let guard = self.connections.lock();
 
         let available: OnlineTapStates = guard
             .values()
             .filter(|e| &e.state.tap_id == tap_id)
+            .filter(|e| !exclude.contains(&e.state.connection_id))
+            .filter(|e| kind.served_by(&e.state.capabilities))
             .map(|e| e.state.clone())
             .collect();
 
@@ -13,6 +13,10 @@ pub struct TapHubMetrics {
     pub cache_hits_total: Counter<u64>,
     pub connection_duration: Histogram<f64>,
     pub active_streams: UpDownCounter<i64>,
+    pub tap_failovers_total: Counter<u64>,
+    pub connection_ejections_total: Counter<u64>,
+    pub coalesced_requests_total: Counter<u64>,
+    pub cancelled_requests_total: Counter<u64>,
 }
 
 static METRICS: OnceLock<TapHubMetrics> = OnceLock::new();
@@ -55,6 +59,22 @@ pub fn metrics() -> &'static TapHubMetrics {
                 .i64_up_down_counter("taphub_active_streams")
                 .with_description("Number of active audio relay streams")
                 .build(),
+            tap_failovers_total: meter
+                .u64_counter("taphub_tap_failovers_total")
+                .with_description("Requests retried on another connection at the tap's request")
+                .build(),
+            connection_ejections_total: meter
+                .u64_counter("taphub_connection_ejections_total")
+                .with_description("Tap connections taken out of rotation after repeated failures")
+                .build(),
+            coalesced_requests_total: meter
+                .u64_counter("taphub_coalesced_requests_total")
+                .with_description("Audio requests that joined an in-flight tap stream")
+                .build(),
+            cancelled_requests_total: meter
+                .u64_counter("taphub_cancelled_requests_total")
+                .with_description("Tap streams cancelled after every listener went away")
+                .build(),
         }
     })
 }
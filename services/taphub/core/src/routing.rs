@@ -1,12 +1,61 @@
-use zako3_types::OnlineTapStates;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
+use zako3_types::{OnlineTapState, OnlineTapStates, TapCapabilities};
+
+/// Smoothing of the per-connection latency average; higher reacts faster.
+const LATENCY_ALPHA: f64 = 0.2;
+/// Smoothing of the per-connection failure rate.
+const FAILURE_ALPHA: f64 = 0.1;
+/// Failures in a row that take a connection out of rotation.
+const EJECT_AFTER: u32 = 3;
+const EJECT_FOR: Duration = Duration::from_secs(30);
+/// Share of its weight a connection keeps however often it fails, so it
+/// still sees enough traffic to show it has recovered.
+const MIN_HEALTH: f64 = 0.05;
+
+/// What a request needs from the connection that serves it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Audio,
+    /// Metadata on its own, which a tap may leave to its other connections.
+    Metadata,
+}
+
+impl RequestKind {
+    pub fn served_by(self, capabilities: &TapCapabilities) -> bool {
+        match self {
+            Self::Audio => true,
+            Self::Metadata => capabilities.supports_metadata_only,
+        }
+    }
+}
+
+/// What the sampler has seen of one connection's recent requests.
+#[derive(Debug, Default)]
+struct ConnectionHealth {
+    in_flight: u32,
+    latency_ms: Option<f64>,
+    failure_rate: f64,
+    consecutive_failures: u32,
+    ejected_until: Option<Instant>,
+}
+
+/// Picks connections in proportion to the weight each tap declared, scaled
+/// down by how loaded, slow and unreliable the connection has been lately.
+/// Connections that keep failing are left out for a while, and those already
+/// serving as many requests as they said they take are skipped.
 pub struct DynamicSampler {
     cursor: f64,
+    health: HashMap<u64, ConnectionHealth>,
 }
 
 impl DynamicSampler {
     pub fn new() -> Self {
-        Self { cursor: 0.5 }
+        Self {
+            cursor: 0.5,
+            health: HashMap::new(),
+        }
     }
 
     fn next_pick(&mut self, ids: &[u64], weights: &[f64]) -> u64 {
@@ -27,13 +76,191 @@ fn next_pick(&mut self, ids: &[u64], weights: &[f64]) -> u64 {
     }
 
     pub fn next_connection_id(&mut self, states: &OnlineTapStates) -> Option<u64> {
+        let now = Instant::now();
+        let open: OnlineTapStates = states
+            .iter()
+            .filter(|s| !self.is_saturated(s))
+            .cloned()
+            .collect();
+        let healthy: OnlineTapStates = open
+            .iter()
+            .filter(|s| !self.is_ejected(s.connection_id, now))
+            .cloned()
+            .collect();
+        // With every connection ejected, a likely failure beats a certain one.
+        let states = if healthy.is_empty() { &open } else { &healthy };
         if states.is_empty() {
             return None;
         }
 
+        // Latency counts relative to the fastest candidate. A connection with
+        // no history yet is assumed to be as fast as the best one.
+        let fastest = states
+            .iter()
+            .filter_map(|s| self.health.get(&s.connection_id)?.latency_ms)
+            .fold(f64::INFINITY, f64::min);
+
         let ids: Vec<u64> = states.iter().map(|s| s.connection_id).collect();
-        let weights: Vec<f64> = states.iter().map(|s| s.selection_weight as f64).collect();
+        let weights: Vec<f64> = states
+            .iter()
+            .map(|s| {
+                let base = s.selection_weight as f64;
+                let Some(health) = self.health.get(&s.connection_id) else {
+                    return base;
+                };
+                let speed = match health.latency_ms {
+                    Some(latency) if latency > 0.0 && fastest.is_finite() => fastest / latency,
+                    _ => 1.0,
+                };
+                let reliability = (1.0 - health.failure_rate).max(MIN_HEALTH);
+                base * speed.max(MIN_HEALTH) * reliability / (1 + health.in_flight) as f64
+            })
+            .collect();
 
         Some(self.next_pick(&ids, &weights))
     }
+
+    fn is_ejected(&self, connection_id: u64, now: Instant) -> bool {
+        self.health
+            .get(&connection_id)
+            .is_some_and(|h| h.ejected_until.is_some_and(|until| now < until))
+    }
+
+    fn is_saturated(&self, state: &OnlineTapState) -> bool {
+        let Some(max) = state.capabilities.max_concurrent_requests else {
+            return false;
+        };
+        self.health
+            .get(&state.connection_id)
+            .is_some_and(|h| h.in_flight >= max)
+    }
+
+    /// A request was sent to `connection_id`. It counts against the
+    /// connection until [`Self::end_request`], streaming included.
+    pub fn begin_request(&mut self, connection_id: u64) {
+        self.health.entry(connection_id).or_default().in_flight += 1;
+    }
+
+    /// A request on `connection_id` was answered after `latency`, served or
+    /// not. Returns whether the connection was just ejected.
+    pub fn record_answer(&mut self, connection_id: u64, latency: Duration, ok: bool) -> bool {
+        let Some(health) = self.health.get_mut(&connection_id) else {
+            return false;
+        };
+
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        health.latency_ms = Some(match health.latency_ms {
+            Some(avg) => avg + (latency_ms - avg) * LATENCY_ALPHA,
+            None => latency_ms,
+        });
+        let failed = if ok { 0.0 } else { 1.0 };
+        health.failure_rate += (failed - health.failure_rate) * FAILURE_ALPHA;
+
+        if ok {
+            health.consecutive_failures = 0;
+            return false;
+        }
+        health.consecutive_failures += 1;
+        if health.consecutive_failures < EJECT_AFTER {
+            return false;
+        }
+        health.consecutive_failures = 0;
+        health.ejected_until = Some(Instant::now() + EJECT_FOR);
+        true
+    }
+
+    /// A request on `connection_id` is over, answered or not, and no longer
+    /// takes up one of its slots.
+    pub fn end_request(&mut self, connection_id: u64) {
+        if let Some(health) = self.health.get_mut(&connection_id) {
+            health.in_flight = health.in_flight.saturating_sub(1);
+        }
+    }
+
+    /// Drop what is known about a connection that went away.
+    pub fn forget(&mut self, connection_id: u64) {
+        self.health.remove(&connection_id);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::Utc;
+    use zako3_types::{TapName, hq::TapId};
+
+    use super::*;
+
+    /// A connection of one tap, taking at most `max_concurrent_requests`.
+    pub(crate) fn connection(
+        connection_id: u64,
+        max_concurrent_requests: Option<u32>,
+    ) -> OnlineTapState {
+        OnlineTapState {
+            tap_id: TapId("tap".to_string()),
+            tap_name: TapName("tap".to_string()),
+            connection_id,
+            friendly_name: format!("connection {connection_id}"),
+            selection_weight: 1.0,
+            connected_at: Utc::now(),
+            protocol_version: 1,
+            capabilities: TapCapabilities {
+                max_concurrent_requests,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub(crate) fn picks(sampler: &mut DynamicSampler, states: &OnlineTapStates) -> Vec<u64> {
+        (0..20)
+            .filter_map(|_| sampler.next_connection_id(states))
+            .collect()
+    }
+
+    #[test]
+    fn saturated_connection_is_skipped_until_a_request_ends() {
+        let mut sampler = DynamicSampler::new();
+        let states = vec![connection(1, Some(1)), connection(2, None)];
+
+        sampler.begin_request(1);
+        assert!(picks(&mut sampler, &states).iter().all(|&id| id == 2));
+
+        sampler.end_request(1);
+        assert!(picks(&mut sampler, &states).contains(&1));
+    }
+
+    #[test]
+    fn no_connection_is_picked_when_all_are_saturated() {
+        let mut sampler = DynamicSampler::new();
+        let states = vec![connection(1, Some(2))];
+
+        sampler.begin_request(1);
+        assert_eq!(sampler.next_connection_id(&states), Some(1));
+        sampler.begin_request(1);
+        assert_eq!(sampler.next_connection_id(&states), None);
+    }
+
+    #[test]
+    fn connection_without_a_limit_is_never_saturated() {
+        let mut sampler = DynamicSampler::new();
+        let states = vec![connection(1, None)];
+
+        for _ in 0..100 {
+            sampler.begin_request(1);
+        }
+        assert_eq!(sampler.next_connection_id(&states), Some(1));
+    }
+
+    #[test]
+    fn failing_connection_is_ejected() {
+        let mut sampler = DynamicSampler::new();
+        let states = vec![connection(1, None), connection(2, None)];
+
+        for attempt in 1..=EJECT_AFTER {
+            sampler.begin_request(1);
+            let ejected = sampler.record_answer(1, Duration::from_millis(10), false);
+            sampler.end_request(1);
+            assert_eq!(ejected, attempt == EJECT_AFTER);
+        }
+        assert!(picks(&mut sampler, &states).iter().all(|&id| id == 2));
+    }
 }
@@ -83,6 +83,8 @@ async fn handle_request_audio(
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         };
 
         Ok((meta, rx))
@@ -100,6 +102,8 @@ async fn handle_preload_audio(
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -115,6 +119,8 @@ async fn handle_request_audio_meta(
                 ttl_seconds: None,
             },
             base_volume: 1.0,
+            loudness_lufs: None,
+            duration_ms: None,
         })
     }
 
@@ -125,4 +131,13 @@ async fn handle_invalidate_cache(
     ) -> Result<(), TapHubError> {
         Ok(())
     }
+
+    async fn handle_store_loudness(
+        &self,
+        _req: CachedAudioRequest,
+        _loudness_lufs: f32,
+        _headers: HashMap<String, String>,
+    ) -> Result<(), TapHubError> {
+        Ok(())
+    }
 }
@@ -2,8 +2,12 @@
 use std::sync::Arc;
 
 use serde::Deserialize;
-use tl_protocol::{AudioEngineCommandRequest, AudioEngineCommandResponse, TrafficLightRpcServer};
-use zako3_types::{GuildId, SessionState};
+use tl_protocol::{
+    AudioEngineCommandRequest, AudioEngineCommandResponse, TrafficLightEventsRpcServer,
+    TrafficLightRpcServer,
+};
+use tokio::sync::broadcast::error::RecvError;
+use zako3_types::{GuildId, SessionEvent, SessionState};
 use tokio::sync::RwLock;
 use tracing::info;
 use zako3_tl_core::{
@@ -12,8 +16,9 @@
 use zako3_tl_infra::AeRegistry;
 use zako3_telemetry::TelemetryConfig;
 use zako3_types::hq::DiscordUserId;
-use jsonrpsee::core::{async_trait, RpcResult};
+use jsonrpsee::core::{async_trait, RpcResult, SubscriptionResult};
 use jsonrpsee::server::Server;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
 
 // ---------------------------------------------------------------------------
 // Config
@@ -121,6 +126,30 @@ async fn heartbeat_ae(&self, token: String, listen_addr: String) -> RpcResult<()
             }
         }
     }
+
+    async fn publish_session_events(&self, token: String, events: Vec<SessionEvent>) -> RpcResult<()> {
+        Ok(self.tl.publish_session_events(token, events).await)
+    }
+}
+
+#[async_trait]
+impl TrafficLightEventsRpcServer for TrafficLightServiceImpl {
+    async fn subscribe_session_events(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut rx = self.tl.subscribe_session_events();
+        let sink = pending.accept().await?;
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "session event subscriber lagged");
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+            // Fails once the subscriber has gone away.
+            sink.send(SubscriptionMessage::from_json(&event)?).await?;
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -218,15 +247,17 @@ async fn main() -> anyhow::Result<()> {
     });
 
     let svc = TrafficLightServiceImpl { tl: tl_service, ae_registry };
+    let mut rpc = TrafficLightRpcServer::into_rpc(svc.clone());
+    rpc.merge(TrafficLightEventsRpcServer::into_rpc(svc))?;
 
-    // Start JSON-RPC HTTP listener
+    // Start JSON-RPC listener (HTTP, plus WebSocket for event subscriptions)
     let server = Server::builder().build(config.rpc_addr).await?;
     info!("RPC server listening on {}", config.rpc_addr);
 
     telemetry.healthy();
     info!("Traffic Light is ready");
 
-    let handle = server.start(svc.into_rpc());
+    let handle = server.start(rpc);
     handle.stopped().await;
 
     Ok(())
@@ -6,10 +6,10 @@
     AudioEngineCommand, AudioEngineCommandRequest, AudioEngineCommandResponse, AudioEngineError,
     AudioEngineSessionCommand, SessionInfo,
 };
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{error, info, warn};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
-use zako3_types::{GuildId, SessionState};
+use zako3_types::{GuildId, SessionEvent, SessionState};
 
 use crate::{
     router, AeDispatcher, RouterError, RouterResult, SessionRoute, StateChangeEvent,
@@ -22,11 +22,16 @@
 /// from evicting a healthy session and triggering a leave/rejoin.
 const TEARDOWN_THRESHOLD: u8 = 3;
 
+/// Lifecycle events buffered per subscriber before a slow one starts missing them.
+const SESSION_EVENT_CAPACITY: usize = 1024;
+
 pub struct TlService {
     state: Arc<RwLock<ZakoState>>,
     dispatcher: Arc<dyn AeDispatcher>,
     /// Consecutive `sync_sessions` failures per route. Reset to 0 on a healthy check.
     sync_failures: Mutex<FxHashMap<SessionRoute, u8>>,
+    /// Lifecycle events published by AEs, fanned out to every subscriber.
+    session_events: broadcast::Sender<SessionEvent>,
 }
 
 impl TlService {
@@ -35,6 +40,7 @@ pub fn new(state: Arc<RwLock<ZakoState>>, dispatcher: Arc<dyn AeDispatcher>) ->
             state,
             dispatcher,
             sync_failures: Mutex::new(FxHashMap::default()),
+            session_events: broadcast::channel(SESSION_EVENT_CAPACITY).0,
         }
     }
 
@@ -415,6 +421,33 @@ pub async fn report_guilds(&self, token: String, guilds: Vec<GuildId>) {
         }
     }
 
+    /// Relays lifecycle events from the AE holding `token`. Events from an unknown token are
+    /// dropped.
+    pub async fn publish_session_events(&self, token: String, events: Vec<SessionEvent>) {
+        let known = self
+            .state
+            .read()
+            .await
+            .workers
+            .values()
+            .any(|w| w.discord_token.0 == token);
+        if !known {
+            warn!(
+                event_count = events.len(),
+                "publish_session_events: no worker found for token"
+            );
+            return;
+        }
+        for event in events {
+            // No subscribers is fine; HQ may not be connected yet.
+            let _ = self.session_events.send(event);
+        }
+    }
+
+    pub fn subscribe_session_events(&self) -> broadcast::Receiver<SessionEvent> {
+        self.session_events.subscribe()
+    }
+
     /// Reconciles TL's session cache toward the AEs' live Discord connections. For each AE
     /// route it fetches the actual voice connections and *re-adopts* any the cache is missing
     /// (e.g. after a TL restart, or an AE restart that rejoined from persisted state). TL
@@ -731,7 +764,7 @@ mod tests {
     use rustc_hash::FxHashMap;
     use std::sync::Mutex;
     use tl_protocol::{AudioEngineCommand, AudioEngineSessionCommand, SessionInfo};
-    use zako3_types::{ChannelId, GuildId};
+    use zako3_types::{ChannelId, GuildId, TrackEventKind, TrackId};
 
     #[derive(Clone)]
     enum MockCall {
@@ -1011,6 +1044,8 @@ async fn sync_sessions_failure_strike_resets_on_recovery() {
                         guild_id: GuildId::from(1u64),
                         channel_id: ChannelId::from(100u64),
                         queues: Default::default(),
+                        settings: Default::default(),
+                        queue_mode: Default::default(),
                     }))
                 } else {
                     Ok(AudioEngineCommandResponse::Error(AudioEngineError::NotJoined))
@@ -1292,4 +1327,29 @@ async fn execute_join_no_permitted_worker_is_rejected_without_fallback() {
             "rejected Join must not commit any session"
         );
     }
+
+    #[tokio::test]
+    async fn publish_session_events_relays_only_known_workers() {
+        let dispatcher = Arc::new(TestDispatcher {
+            calls: Arc::new(Mutex::new(Vec::new())),
+            response: Arc::new(|| Ok(AudioEngineCommandResponse::Ok)),
+        });
+        let svc = TlService::new(state_with_connected_ae(), dispatcher);
+        let mut rx = svc.subscribe_session_events();
+        let event = SessionEvent {
+            guild_id: GuildId::from(1),
+            channel_id: ChannelId::from(100),
+            track_id: TrackId::from(7),
+            kind: TrackEventKind::Started,
+        };
+
+        svc.publish_session_events("unknown".to_string(), vec![event.clone()])
+            .await;
+        assert!(rx.try_recv().is_err(), "unknown token must be ignored");
+
+        svc.publish_session_events(String::new(), vec![event]).await;
+        let relayed = rx.try_recv().unwrap();
+        assert_eq!(relayed.track_id, TrackId::from(7));
+        assert!(matches!(relayed.kind, TrackEventKind::Started));
+    }
 }
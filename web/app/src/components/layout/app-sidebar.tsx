@@ -64,7 +64,7 @@ export const AppSidebar = () => {
             onmessage(ev) {
                 try {
                     const event = JSON.parse(ev.data) as { type: string }
-                    if (event.type === 'playbackChanged') {
+                    if (event.type === 'playbackChanged' || event.type === 'track') {
                         queryClient.invalidateQueries({ queryKey: playbackKeys.state() })
                     } else if (event.type === 'voiceStateChanged') {
                         queryClient.invalidateQueries({ queryKey: guildKeys.myGuilds() })
@@ -4,6 +4,8 @@ import type {
   PaginatedResponse,
   PaginationParams,
   AdminActivity,
+  Announcement,
+  AnnouncementResult,
   Tap,
   VerificationRequestFull,
   VerificationStatus,
@@ -74,4 +76,8 @@ export const adminApi = {
   getStats: async (): Promise<{ globalUniqueUsers: number }> => {
     return apiCall(apiClient.get<{ globalUniqueUsers: number }>('/admin/stats'))
   },
+
+  sendAnnouncement: async (data: Announcement): Promise<AnnouncementResult> => {
+    return apiCall(apiClient.post<AnnouncementResult>('/admin/announcements', data))
+  },
 }
@@ -1,6 +1,11 @@
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
 import { adminApi } from './api'
-import type { PaginationParams, VerificationStatus, TapOccupation } from '@zako-ac/zako3-data'
+import type {
+  Announcement,
+  PaginationParams,
+  VerificationStatus,
+  TapOccupation,
+} from '@zako-ac/zako3-data'
 import { tapKeys } from '../taps/hooks'
 
 interface GetVerificationRequestsParams extends Partial<PaginationParams> {
@@ -92,3 +97,9 @@ export const useAdminStats = () => {
     queryFn: () => adminApi.getStats(),
   })
 }
+
+export const useSendAnnouncement = () => {
+  return useMutation({
+    mutationFn: (data: Announcement) => adminApi.sendAnnouncement(data),
+  })
+}
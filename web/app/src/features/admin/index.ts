@@ -7,4 +7,5 @@ export {
   useApproveVerification,
   useRejectVerification,
   useAdminStats,
+  useSendAnnouncement,
 } from './hooks'
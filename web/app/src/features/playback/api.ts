@@ -6,6 +6,7 @@ import type {
     PauseTrackDto,
     PlaybackActionDto,
     ResumeTrackDto,
+    SetQueueModeDto,
     SkipDto,
     StopTrackDto,
 } from '@zako-ac/zako3-data'
@@ -26,6 +27,9 @@ export const playbackApi = {
     resumeTrack: (body: ResumeTrackDto): Promise<PlaybackActionDto> =>
         apiCall(apiClient.post<PlaybackActionDto>('/playback/resume', body)),
 
+    setQueueMode: (body: SetQueueModeDto): Promise<PlaybackActionDto> =>
+        apiCall(apiClient.post<PlaybackActionDto>('/playback/queue-mode', body)),
+
     editQueue: (body: EditQueueDto): Promise<PlaybackActionDto> =>
         apiCall(apiClient.patch<PlaybackActionDto>('/playback/queue', body)),
 
@@ -2,7 +2,14 @@ import { useEffect } from 'react'
 import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
 import { fetchEventSource } from '@microsoft/fetch-event-source'
 import { playbackApi } from './api'
-import type { EditQueueDto, PauseTrackDto, ResumeTrackDto, SkipDto, StopTrackDto } from '@zako-ac/zako3-data'
+import type {
+    EditQueueDto,
+    PauseTrackDto,
+    ResumeTrackDto,
+    SetQueueModeDto,
+    SkipDto,
+    StopTrackDto,
+} from '@zako-ac/zako3-data'
 import { AUTH_TOKEN_KEY, API_BASE_URL } from '@/lib/constants'
 import { useNameCache } from '@/features/guild/name-cache'
 
@@ -77,6 +84,17 @@ export const useSkipMusic = () => {
     })
 }
 
+export const useSetQueueMode = () => {
+    const queryClient = useQueryClient()
+    return useMutation({
+        mutationFn: (body: SetQueueModeDto) => playbackApi.setQueueMode(body),
+        onSuccess: () => {
+            queryClient.invalidateQueries({ queryKey: playbackKeys.state() })
+            queryClient.invalidateQueries({ queryKey: playbackKeys.history() })
+        },
+    })
+}
+
 export const usePauseTrack = () => {
     const queryClient = useQueryClient()
     return useMutation({
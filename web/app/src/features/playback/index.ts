@@ -7,6 +7,7 @@ export {
     usePlaybackSSE,
     useStopTrack,
     useSkipMusic,
+    useSetQueueMode,
     usePauseTrack,
     useResumeTrack,
     useEditQueue,
@@ -19,6 +20,9 @@ export type {
     PlaybackActionDto,
     StopTrackDto,
     SkipDto,
+    SetQueueModeDto,
+    QueueModeDto,
+    RepeatModeDto,
     PauseTrackDto,
     ResumeTrackDto,
     EditQueueDto,
@@ -5,6 +5,7 @@ import type {
     TapOccupation,
     TapRole,
     TapPermissionConfig,
+    TapConnection,
     TapStats,
     TimeSeriesPoint,
     UserSummary,
@@ -112,6 +113,18 @@ export const createTimeSeriesData = (
     return data
 }
 
+export const createTapConnection = (
+    overrides?: Partial<TapConnection>
+): TapConnection => ({
+    friendlyName: `${faker.location.city()} ${faker.number.int({ min: 1, max: 9 })}`,
+    connectedAt: faker.date.recent({ days: 3 }).toISOString(),
+    protocolVersion: faker.helpers.arrayElement([1, 2]),
+    maxConcurrentRequests: faker.helpers.arrayElement([null, 4, 8, 16]),
+    supportsMetadataOnly: faker.datatype.boolean({ probability: 0.9 }),
+    supportsCancel: faker.datatype.boolean({ probability: 0.8 }),
+    ...overrides,
+})
+
 export const createTapStats = (
     tapId: string,
     overrides?: Partial<TapStats>
@@ -125,6 +138,10 @@ export const createTapStats = (
         uptimePercent: faker.number.float({ min: 90, max: 100, fractionDigits: 2 }),
         useRateHistory: createTimeSeriesData(30, 0, 500),
         cacheHitRateHistory: createTimeSeriesData(30, 0, 100),
+        connections: Array.from(
+            { length: faker.number.int({ min: 0, max: 3 }) },
+            () => createTapConnection()
+        ),
     }
 
     return {
@@ -6,6 +6,7 @@ import type {
   PlaybackActionDto,
   StopTrackDto,
   SkipDto,
+  SetQueueModeDto,
   PauseTrackDto,
   ResumeTrackDto,
   EditQueueDto,
@@ -26,6 +27,8 @@ const createMockTrack = (): TrackDto => ({
   requestedBy: 'test_user',
   volume: 100,
   paused: false,
+  durationMs: faker.number.int({ min: 60_000, max: 300_000 }),
+  positionMs: 0,
 })
 
 const mockPlaybackStates: Record<string, GuildPlaybackStateDto> = {
@@ -38,6 +41,7 @@ const mockPlaybackStates: Record<string, GuildPlaybackStateDto> = {
     queues: {
       main: [createMockTrack(), createMockTrack()],
     },
+    queueMode: { repeat: 'off', shuffle: false },
     queueMeta: {
       main: {
         user: {
@@ -109,6 +113,28 @@ export const playbackHandlers = [
     return HttpResponse.json(action)
   }),
 
+  http.post(`${API_BASE}/playback/queue-mode`, async ({ request }) => {
+    await delay(150)
+    const body = (await request.json()) as SetQueueModeDto
+    const state = mockPlaybackStates[body.guildId]
+
+    if (!state) {
+      return HttpResponse.json(
+        { code: 'NOT_FOUND', message: 'Guild not found' },
+        { status: 404 }
+      )
+    }
+
+    const current = state.queueMode ?? { repeat: 'off', shuffle: false }
+    state.queueMode = {
+      repeat: body.repeat ?? current.repeat,
+      shuffle: body.shuffle ?? current.shuffle,
+    }
+    const action = createAction('queue_mode', body.guildId, body.channelId)
+    mockPlaybackHistory.push(action)
+    return HttpResponse.json(action)
+  }),
+
   http.post(`${API_BASE}/playback/pause`, async ({ request }) => {
     await delay(150)
     const body = (await request.json()) as PauseTrackDto
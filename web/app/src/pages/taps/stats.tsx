@@ -28,7 +28,7 @@ import { Skeleton } from '@/components/ui/skeleton'
 import { Badge } from '@/components/ui/badge'
 import { Button } from '@/components/ui/button'
 import { formatRelativeTime } from '@/lib/date'
-import { TapAuditLogEntry } from '@zako-ac/zako3-data'
+import { TapAuditLogEntry, TapConnection } from '@zako-ac/zako3-data'
 import { UserBadge } from '@/components/tap/user-badge'
 
 export const TapStatsPage = () => {
@@ -168,6 +168,60 @@ export const TapStatsPage = () => {
                 />
             </div>
 
+            <Card>
+                <CardHeader>
+                    <CardTitle>{t('taps.stats.connections')}</CardTitle>
+                    <CardDescription>
+                        {t('taps.stats.connectionsDescription')}
+                    </CardDescription>
+                </CardHeader>
+                <CardContent>
+                    {stats.connections.length === 0 ? (
+                        <p className="text-muted-foreground py-8 text-center">
+                            {t('taps.stats.noConnections')}
+                        </p>
+                    ) : (
+                        <Table>
+                            <TableHeader>
+                                <TableRow>
+                                    <TableHead>Name</TableHead>
+                                    <TableHead>{t('taps.stats.protocolVersion')}</TableHead>
+                                    <TableHead>{t('taps.stats.capabilities')}</TableHead>
+                                    <TableHead>{t('taps.stats.maxConcurrent')}</TableHead>
+                                    <TableHead>Connected</TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {stats.connections.map((conn: TapConnection, i: number) => (
+                                    <TableRow key={`${conn.friendlyName}-${i}`}>
+                                        <TableCell>{conn.friendlyName || '-'}</TableCell>
+                                        <TableCell className="font-mono">
+                                            v{conn.protocolVersion}
+                                        </TableCell>
+                                        <TableCell>
+                                            <div className="flex flex-wrap gap-1">
+                                                {conn.supportsMetadataOnly && (
+                                                    <Badge variant="outline">{t('taps.stats.metadataOnly')}</Badge>
+                                                )}
+                                                {conn.supportsCancel && (
+                                                    <Badge variant="outline">{t('taps.stats.cancel')}</Badge>
+                                                )}
+                                            </div>
+                                        </TableCell>
+                                        <TableCell>
+                                            {conn.maxConcurrentRequests ?? t('taps.stats.unlimited')}
+                                        </TableCell>
+                                        <TableCell className="text-muted-foreground">
+                                            {formatRelativeTime(conn.connectedAt, i18n.language)}
+                                        </TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    )}
+                </CardContent>
+            </Card>
+
             <Card>
                 <CardHeader>
                     <CardTitle>{t('taps.stats.auditLog')}</CardTitle>
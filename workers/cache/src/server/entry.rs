@@ -4,7 +4,8 @@
     http::StatusCode,
 };
 use zako3_cache_client::{
-    CacheEntryDto, ClearTapResp, DeleteEntryResp, EntryQuery, StoreMetadataReq, TapQuery,
+    CacheEntryDto, ClearTapResp, DeleteEntryResp, EntryQuery, SetLoudnessReq, StoreMetadataReq,
+    TapQuery,
 };
 use zako3_preload_cache::AudioCache;
 use zako3_types::{cache::AudioCacheItemKey, hq::TapId};
@@ -79,3 +80,26 @@ pub async fn store_metadata(
         })?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// `PUT /entry/loudness?tap_id&key` — record the measured integrated loudness.
+pub async fn set_loudness(
+    State(state): State<AppState>,
+    Query(q): Query<EntryQuery>,
+    Json(req): Json<SetLoudnessReq>,
+) -> Result<StatusCode, StatusCode> {
+    let key: AudioCacheItemKey =
+        serde_json::from_str(&q.key).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let tap_id = TapId(q.tap_id);
+    state
+        .cache
+        .set_loudness(&tap_id, &key, req.loudness_lufs)
+        .await
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return StatusCode::NOT_FOUND;
+            }
+            tracing::warn!(%e, "set_loudness failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
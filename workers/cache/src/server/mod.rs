@@ -10,7 +10,7 @@
 
 use axum::{
     Router, middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
 };
 use dashmap::DashMap;
 use tokio::net::TcpListener;
@@ -40,6 +40,7 @@ pub fn build(
         .route("/preload/:id/abort", post(preload::abort))
         .route("/stream", get(stream::stream))
         .route("/entry", get(entry::get_entry).delete(entry::delete_entry))
+        .route("/entry/loudness", put(entry::set_loudness))
         .route("/entries", delete(entry::delete_entries))
         .route("/metadata", post(entry::store_metadata))
         .route("/healthz", get(|| async { "ok" }))
@@ -58,6 +58,7 @@ pub async fn handle_command(cmd: TaphubCommands) -> Result<()> {
                 },
                 discord_user_id: DiscordUserId(discord_user_id),
                 headers: HashMap::new(),
+                start_offset_ms: 0,
             };
 
             println!("Sending request_audio...");